@@ -1,10 +1,14 @@
 use clap::Arg;
 use once_cell::sync::OnceCell;
-use rsa::{RsaPrivateKey, RsaPublicKey};
+use rsa::{
+    pkcs1::DecodeRsaPrivateKey,
+    pkcs8::{DecodePrivateKey, EncodePrivateKey, LineEnding},
+    RsaPrivateKey, RsaPublicKey,
+};
 use serde::{Deserialize, Serialize};
 use std::io::{Read, Write};
 use std::{fs::File, path::Path, path::PathBuf};
-use tracing::{error, trace, warn};
+use tracing::{error, info, trace, warn};
 
 #[cfg(feature = "proxy")]
 use crate::proxy::config::{ProxyArgs, ProxyConfig};
@@ -20,6 +24,17 @@ pub static CONFIG: OnceCell<Config> = OnceCell::new();
 pub static ARGS: OnceCell<Args> = OnceCell::new();
 const DEFAULT_CONFIG_FILE: &str = "composition.toml";
 pub const DEFAULT_LOG_DIR: &str = "logs";
+const DEFAULT_RSA_KEY_FILE: &str = "composition.pem";
+const DEFAULT_RSA_KEY_SIZE: usize = 1024;
+
+/// Why the RSA key pair couldn't be loaded, generated or saved.
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("could not generate RSA key: {0}")]
+    RsaKeyGeneration(#[from] rsa::Error),
+}
 
 /// Helper function to read a file from a `Path`
 /// and return its bytes as a `Vec<u8>`.
@@ -44,16 +59,16 @@ pub struct Config {
     #[cfg(feature = "proxy")]
     pub proxy: ProxyConfig,
     /// RSA key pair used for encryption and decryption.
-    /// Generated on each startup and not saved to disk.
+    /// Loaded or generated the first time it's used, see `Config::rsa_key_pair()`.
     #[serde(skip)]
-    pub rsa_key_pair: (RsaPublicKey, RsaPrivateKey),
+    rsa_key_pair: OnceCell<(RsaPublicKey, RsaPrivateKey)>,
 }
 impl Config {
     pub fn get_formatted_version(subcommand: Subcommand) -> String {
         format!(
             "composition{} {} ({} {})",
             match subcommand {
-                Subcommand::None => "",
+                Subcommand::None | Subcommand::RotateKey => "",
                 #[cfg(feature = "server")]
                 Subcommand::Server => "",
                 #[cfg(feature = "proxy")]
@@ -111,6 +126,49 @@ impl Config {
         CONFIG.set(config).expect("could not set CONFIG");
         Self::instance()
     }
+    /// Get the RSA key pair used for login encryption.
+    ///
+    /// The first call loads the key pair from `rsa-key-file`,
+    /// generating and saving a new one if the file doesn't exist.
+    pub fn rsa_key_pair(&self) -> Result<&(RsaPublicKey, RsaPrivateKey), ConfigError> {
+        self.rsa_key_pair.get_or_try_init(|| {
+            let path = &self.global.rsa_key_file;
+            let private_key = if path.exists() {
+                match read_rsa_key(path) {
+                    Ok(key) => key,
+                    Err(e) => {
+                        // Don't overwrite the file, it might be recoverable.
+                        error!("Could not read RSA key file ({}), using a temporary key", e);
+                        generate_rsa_key(self.global.rsa_key_size)?
+                    }
+                }
+            } else {
+                warn!(
+                    "RSA key file does not exist, creating {}",
+                    path.to_str().unwrap_or("")
+                );
+                let key = generate_rsa_key(self.global.rsa_key_size)?;
+                if let Err(e) = write_rsa_key(path, &key) {
+                    error!("Could not write RSA key file: {}", e);
+                }
+                key
+            };
+            Ok((private_key.to_public_key(), private_key))
+        })
+    }
+    /// Generate a new RSA key pair and save it to `rsa-key-file`,
+    /// replacing the existing one.
+    pub fn rotate_rsa_key_pair(&self) -> Result<(), ConfigError> {
+        let path = &self.global.rsa_key_file;
+        let key = generate_rsa_key(self.global.rsa_key_size)?;
+        write_rsa_key(path, &key)?;
+        info!(
+            "Wrote new {}-bit RSA key pair to {}",
+            self.global.rsa_key_size,
+            path.to_str().unwrap_or("")
+        );
+        Ok(())
+    }
     #[tracing::instrument]
     fn write(&self, path: &Path) {
         trace!("Config.write()");
@@ -133,20 +191,74 @@ impl Config {
 }
 impl Default for Config {
     fn default() -> Self {
-        let rsa_key_pair = RsaPrivateKey::new(&mut rand::thread_rng(), 1024)
-            .map(|key| (key.to_public_key(), key))
-            .expect("Failed to generate RSA key pair");
         Config {
             global: GlobalConfig::default(),
             #[cfg(feature = "server")]
             server: ServerConfig::default(),
             #[cfg(feature = "proxy")]
             proxy: ProxyConfig::default(),
-            rsa_key_pair,
+            rsa_key_pair: OnceCell::new(),
         }
     }
 }
 
+/// Generate a new RSA private key with the given size in bits.
+fn generate_rsa_key(bits: usize) -> Result<RsaPrivateKey, ConfigError> {
+    trace!("Generating {}-bit RSA key", bits);
+    Ok(RsaPrivateKey::new(&mut rand::thread_rng(), bits)?)
+}
+/// Read an RSA private key from a file.
+///
+/// Files with a `der` extension are read as PKCS#8 DER,
+/// otherwise the file is read as PKCS#8 or PKCS#1 PEM.
+fn read_rsa_key(path: &Path) -> std::io::Result<RsaPrivateKey> {
+    use std::io::{Error, ErrorKind};
+
+    let data = read_file(path)?;
+    let key = if is_der_file(path) {
+        RsaPrivateKey::from_pkcs8_der(&data).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    } else {
+        let pem = String::from_utf8_lossy(&data);
+        RsaPrivateKey::from_pkcs8_pem(&pem)
+            .or_else(|_| RsaPrivateKey::from_pkcs1_pem(&pem).map_err(|e| e.into()))
+            .map_err(|e: rsa::pkcs8::Error| Error::new(ErrorKind::InvalidData, e))
+    }?;
+    Ok(key)
+}
+/// Write an RSA private key to a file, using the same format as `read_rsa_key`.
+///
+/// On unix, only the file's owner can read it.
+fn write_rsa_key(path: &Path, key: &RsaPrivateKey) -> std::io::Result<()> {
+    use std::io::{Error, ErrorKind};
+    #[cfg(unix)]
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+    let data = if is_der_file(path) {
+        key.to_pkcs8_der()
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?
+            .as_bytes()
+            .to_vec()
+    } else {
+        key.to_pkcs8_pem(LineEnding::LF)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?
+            .as_bytes()
+            .to_vec()
+    };
+    let mut options = File::options();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path)?;
+    // The mode only applies to new files, so also restrict a key being replaced.
+    #[cfg(unix)]
+    file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    file.write_all(&data)
+}
+fn is_der_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("der"))
+}
+
 /// The global configuration.
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
@@ -159,6 +271,11 @@ pub struct GlobalConfig {
     #[serde(skip)]
     pub game_version: String,
     pub threads: Option<usize>,
+    /// File the RSA key pair for login encryption is stored in.
+    /// Keys are PEM encoded, unless the file has a `der` extension.
+    pub rsa_key_file: PathBuf,
+    /// Size in bits of newly generated RSA keys.
+    pub rsa_key_size: usize,
 }
 impl Default for GlobalConfig {
     fn default() -> Self {
@@ -167,6 +284,8 @@ impl Default for GlobalConfig {
             protocol_version: crate::PROTOCOL_VERSION,
             game_version: crate::GAME_VERSION.to_owned(),
             threads: None,
+            rsa_key_file: PathBuf::from(DEFAULT_RSA_KEY_FILE),
+            rsa_key_size: DEFAULT_RSA_KEY_SIZE,
         }
     }
 }
@@ -180,6 +299,7 @@ impl GlobalConfig {
 pub enum Subcommand {
     #[default]
     None,
+    RotateKey,
     #[cfg(feature = "server")]
     Server,
     #[cfg(feature = "proxy")]
@@ -275,6 +395,10 @@ impl Args {
                     .value_hint(clap::ValueHint::DirPath)
                     .value_parser(clap::value_parser!(PathBuf))
                     .default_value(DEFAULT_LOG_DIR),
+            )
+            .subcommand(
+                clap::Command::new("rotate-key")
+                    .about("Generate a new RSA key pair for login encryption"),
             );
         #[cfg(feature = "server")]
         {
@@ -321,15 +445,15 @@ impl Args {
                 if cfg!(feature = "world") {
                     features.push("world");
                 }
-                if cfg!(feature = "update_1_20") {
-                    features.push("update_1_20");
-                }
                 println!("features: {}", features.join(", "));
             }
             std::process::exit(0);
         }
 
         match m.subcommand() {
+            Some(("rotate-key", _)) => {
+                args.subcommand = Subcommand::RotateKey;
+            }
             #[cfg(feature = "server")]
             Some(("server", m)) => {
                 args.subcommand = Subcommand::Server;
@@ -350,3 +474,41 @@ impl Args {
         args
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rsa_key_file_round_trip_works() {
+        let key = generate_rsa_key(512).unwrap();
+        for file_name in ["composition-test-key.pem", "composition-test-key.der"] {
+            let path = std::env::temp_dir().join(file_name);
+            write_rsa_key(&path, &key).unwrap();
+            assert_eq!(read_rsa_key(&path).unwrap(), key);
+            let _ = std::fs::remove_file(path);
+        }
+        assert!(matches!(
+            generate_rsa_key(0),
+            Err(ConfigError::RsaKeyGeneration(_))
+        ));
+    }
+    #[cfg(unix)]
+    #[test]
+    fn rsa_key_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let key = generate_rsa_key(512).unwrap();
+        let path = std::env::temp_dir().join("composition-test-private-key.pem");
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+        let _ = std::fs::remove_file(&path);
+        write_rsa_key(&path, &key).unwrap();
+        assert_eq!(mode(&path), 0o600);
+        // Replacing a key that others could read restricts it too.
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        write_rsa_key(&path, &key).unwrap();
+        assert_eq!(mode(&path), 0o600);
+        let _ = std::fs::remove_file(path);
+    }
+}
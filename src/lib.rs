@@ -20,8 +20,8 @@ use std::time::Instant;
 use tokio_util::sync::CancellationToken;
 use tracing::{error, info};
 
-pub const PROTOCOL_VERSION: i32 = 770;
-pub const GAME_VERSION: &str = "1.21.5";
+pub const PROTOCOL_VERSION: i32 = protocol::ProtocolVersion::LATEST.protocol_version();
+pub const GAME_VERSION: &str = protocol::ProtocolVersion::LATEST.game_version();
 
 /// A globally accessible instant of Composition's start time.
 ///
@@ -34,6 +34,11 @@ pub async fn run(command: Subcommand, running: CancellationToken) {
         Subcommand::Server => server::Server::run(running).await,
         #[cfg(feature = "proxy")]
         Subcommand::Proxy => proxy::Proxy::run(running).await,
+        Subcommand::RotateKey => {
+            if let Err(e) = config::Config::instance().rotate_rsa_key_pair() {
+                error!("Could not rotate RSA key pair: {}", e);
+            }
+        }
         Subcommand::None => unreachable!(),
     }
 }
@@ -0,0 +1,119 @@
+use crate::net::error::Error;
+use crate::protocol::types::GameProfile;
+use once_cell::sync::Lazy;
+use std::time::Duration;
+use tracing::trace;
+
+/// The session server used by vanilla servers.
+pub const DEFAULT_SESSION_SERVER: &str = "https://sessionserver.mojang.com";
+
+static HTTP_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .expect("could not create HTTP client")
+});
+
+/// Check with the session server that the player has joined using the given server hash.
+///
+/// Returns the player's profile if the session is valid.
+pub async fn has_joined(
+    session_server: &str,
+    username: &str,
+    server_hash: &str,
+) -> Result<GameProfile, Error> {
+    let url = format!(
+        "{}/session/minecraft/hasJoined",
+        session_server.trim_end_matches('/')
+    );
+    let response = HTTP_CLIENT
+        .get(url)
+        .query(&[("username", username), ("serverId", server_hash)])
+        .send()
+        .await
+        .map_err(|e| {
+            trace!("Could not reach the session server: {}", e);
+            Error::Authentication
+        })?;
+
+    if response.status() != reqwest::StatusCode::OK {
+        trace!(
+            "Session server rejected {} with status {}",
+            username,
+            response.status()
+        );
+        return Err(Error::Authentication);
+    }
+
+    response.json::<GameProfile>().await.map_err(|e| {
+        trace!("Could not parse the session server response: {}", e);
+        Error::Authentication
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::types::{ProfileProperty, Uuid};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    /// Serve a single HTTP response, returning the base URL and the received request.
+    async fn mock_session_server(
+        status: &'static str,
+        body: &'static str,
+    ) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![0u8; 4096];
+            let n = stream.read(&mut request).await.unwrap();
+            let response = format!(
+                "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request[..n]).to_string()
+        });
+        (url, handle)
+    }
+
+    #[tokio::test]
+    async fn has_joined_returns_profile() {
+        let (url, request) = mock_session_server(
+            "200 OK",
+            r#"{"id":"069a79f444e94726a5befca90e38aaf5","name":"Notch","properties":[{"name":"textures","value":"e30=","signature":"c2ln"}]}"#,
+        )
+        .await;
+
+        let profile = has_joined(&url, "Notch", "-7c9d5b00").await.unwrap();
+        assert_eq!(
+            profile.uuid,
+            Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap()
+        );
+        assert_eq!(profile.username, "Notch");
+        assert_eq!(
+            profile.properties,
+            vec![ProfileProperty {
+                name: "textures".to_owned(),
+                value: "e30=".to_owned(),
+                signature: Some("c2ln".to_owned()),
+            }]
+        );
+
+        let request = request.await.unwrap();
+        assert!(request
+            .starts_with("GET /session/minecraft/hasJoined?username=Notch&serverId=-7c9d5b00 "));
+    }
+    #[tokio::test]
+    async fn has_joined_rejects_invalid_sessions() {
+        let (url, _) = mock_session_server("204 No Content", "").await;
+        assert!(matches!(
+            has_joined(&url, "Notch", "0").await,
+            Err(Error::Authentication)
+        ));
+    }
+}
@@ -1,141 +1,765 @@
 use super::error::Error;
 use crate::protocol::{
-    encryption::*,
-    packets::{Packet, PacketDirection},
-    parsing::Parsable,
+    packets::{
+        configuration::{
+            clientbound::ConfigurationDisconnect, serverbound::AcknowledgeFinishConfiguration,
+        },
+        handshake::serverbound::Handshake,
+        login::{
+            clientbound::{LoginDisconnect, SetCompression},
+            serverbound::LoginAcknowledged,
+        },
+        play::{
+            clientbound::{BundleDelimiter, PlayDisconnect, StartConfiguration},
+            serverbound::AcknowledgeConfiguration,
+        },
+        status::clientbound::PingResponse,
+        Packet, PacketDirection, RawPacket,
+    },
+    parsing::{BufMut, Parsable},
     types::VarInt,
-    ClientState,
+    ClientState, ProtocolVersion,
 };
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use std::io::{Read, Write};
 use tokio_util::{
-    bytes::{Buf, BytesMut},
+    bytes::{Buf, Bytes, BytesMut},
     codec::{Decoder, Encoder},
 };
 use tracing::trace;
 
+/// The largest frame that vanilla clients and servers will read,
+/// which is the most a 3 byte `VarInt` length can hold.
+pub const MAX_PACKET_LENGTH: usize = 2_097_151;
+/// The largest uncompressed packet that vanilla clients and servers will accept.
+pub const MAX_UNCOMPRESSED_PACKET_LENGTH: usize = 8_388_608;
+/// The most packets that vanilla clients will accept in one bundle.
+pub const MAX_BUNDLE_PACKETS: usize = 4096;
+
 #[derive(Clone, Debug)]
 pub struct PacketCodec {
     pub client_state: ClientState,
     pub packet_direction: PacketDirection,
-    pub aes_cipher: Option<(Aes128Cfb8Encryptor, Aes128Cfb8Decryptor, usize)>,
+    /// The packet ids and layouts to use, set from the handshake.
+    pub protocol_version: ProtocolVersion,
+    /// Packets with bodies at least this long get compressed.
+    ///
+    /// `None` means compression is disabled and the uncompressed frame format is used.
+    pub compression_threshold: Option<usize>,
+    /// How many packets have been read since a `BundleDelimiter` opened a bundle.
+    reading_bundle: Option<usize>,
+    /// How many packets have been written since a `BundleDelimiter` opened a bundle.
+    writing_bundle: Option<usize>,
 }
 impl PacketCodec {
     pub fn new(client_state: ClientState, packet_direction: PacketDirection) -> PacketCodec {
         PacketCodec {
             client_state,
             packet_direction,
-            aes_cipher: None,
+            protocol_version: ProtocolVersion::LATEST,
+            compression_threshold: None,
+            reading_bundle: None,
+            writing_bundle: None,
+        }
+    }
+    /// Set the compression threshold as sent in `SetCompression`.
+    ///
+    /// A negative threshold disables compression.
+    pub fn set_compression_threshold(&mut self, threshold: i32) {
+        self.compression_threshold = usize::try_from(threshold).ok();
+    }
+    /// Whether the packets being read are inside a bundle.
+    pub fn is_reading_bundle(&self) -> bool {
+        self.reading_bundle.is_some()
+    }
+    /// Whether the packets being written are inside a bundle.
+    ///
+    /// Writers should hold off flushing until the bundle is closed.
+    pub fn is_writing_bundle(&self) -> bool {
+        self.writing_bundle.is_some()
+    }
+    /// Open or close a bundle on `BundleDelimiter`, or count a packet inside one.
+    fn track_bundle(bundle: &mut Option<usize>, is_delimiter: bool) -> Result<(), Error> {
+        match (is_delimiter, bundle.as_mut()) {
+            (true, Some(_)) => *bundle = None,
+            (true, None) => *bundle = Some(0),
+            (_, Some(count)) => {
+                *count += 1;
+                if *count > MAX_BUNDLE_PACKETS {
+                    trace!("bundle has more than {} packets", MAX_BUNDLE_PACKETS);
+                    return Err(Error::Invalid);
+                }
+            }
+            (_, None) => {}
+        }
+        Ok(())
+    }
+    /// Whether the codec has to parse a packet to keep track of the connection.
+    ///
+    /// These are the packets that change the version, compression, bundle or state
+    /// (every packet with a `Packet::state_change`), so the rest can be read lazily.
+    fn needs_parsing(packet: &RawPacket) -> bool {
+        packet.is::<Handshake>()
+            || packet.is::<PingResponse>()
+            || packet.is::<SetCompression>()
+            || packet.is::<LoginDisconnect>()
+            || packet.is::<LoginAcknowledged>()
+            || packet.is::<ConfigurationDisconnect>()
+            || packet.is::<AcknowledgeFinishConfiguration>()
+            || packet.is::<BundleDelimiter>()
+            || packet.is::<StartConfiguration>()
+            || packet.is::<PlayDisconnect>()
+            || packet.is::<AcknowledgeConfiguration>()
+    }
+    /// Switch to the protocol version in a handshake.
+    ///
+    /// Unsupported versions fall back to the latest one.
+    fn set_protocol_version(&mut self, packet: &Packet) {
+        if let Packet::Handshake(handshake) = packet {
+            self.protocol_version =
+                ProtocolVersion::from_protocol_version(*handshake.protocol_version)
+                    .unwrap_or(ProtocolVersion::LATEST);
+        }
+    }
+    /// Decompress (if necessary) the contents of a frame into the packet id and body.
+    fn decompress_frame(&self, frame: Bytes) -> Result<Bytes, Error> {
+        let Some(threshold) = self.compression_threshold else {
+            return Ok(frame);
+        };
+
+        let (compressed, data_length) = VarInt::parse_usize(&frame).map_err(|_| Error::Parsing)?;
+        if data_length == 0 {
+            // The packet was below the threshold and was sent uncompressed.
+            return Ok(frame.slice(frame.len() - compressed.len()..));
+        }
+        if data_length < threshold {
+            trace!(
+                "compressed packet of {} bytes is below the threshold of {}",
+                data_length,
+                threshold
+            );
+            return Err(Error::Parsing);
+        }
+        if data_length > MAX_UNCOMPRESSED_PACKET_LENGTH {
+            trace!("compressed packet too long: {} bytes", data_length);
+            return Err(Error::Parsing);
+        }
+
+        let mut decompressed = Vec::with_capacity(data_length);
+        ZlibDecoder::new(compressed)
+            .take(data_length as u64)
+            .read_to_end(&mut decompressed)?;
+        if decompressed.len() != data_length {
+            trace!(
+                "decompressed length {} did not match data length {}",
+                decompressed.len(),
+                data_length
+            );
+            return Err(Error::Parsing);
+        }
+        Ok(Bytes::from(decompressed))
+    }
+    /// Compress a packet id and body that are over the threshold into the contents of a frame.
+    fn compress_frame(body: &[u8]) -> Result<Vec<u8>, Error> {
+        let frame = VarInt::from(body.len()).serialize();
+        let mut encoder = ZlibEncoder::new(frame, Compression::default());
+        encoder.write_all(body)?;
+        Ok(encoder.finish()?)
+    }
+    /// Write a frame for a packet, compressing it if it's over the threshold.
+    fn write_frame(&self, item: &impl EncodeFrame, dst: &mut BytesMut) -> Result<(), Error> {
+        let frame_len = item
+            .frame_len(self.protocol_version)
+            .ok_or(Error::UnsupportedPacket)?;
+        match self.compression_threshold {
+            Some(threshold) if frame_len >= threshold => {
+                let mut body = Vec::with_capacity(frame_len);
+                item.serialize_frame_into(self.protocol_version, &mut body);
+                let frame = PacketCodec::compress_frame(&body)?;
+                let packet_len = VarInt::from(frame.len());
+                dst.reserve(packet_len.encoded_len() + frame.len());
+                packet_len.serialize_into(dst);
+                dst.extend_from_slice(&frame);
+            }
+            compression_threshold => {
+                // Write the packet straight into `dst`, with a data length
+                // of 0 to signal an uncompressed packet if compression is enabled.
+                let data_len = compression_threshold.map(|_| VarInt::from(0));
+                let packet_len = VarInt::from(data_len.encoded_len() + frame_len);
+                dst.reserve(packet_len.encoded_len() + *packet_len as usize);
+                packet_len.serialize_into(dst);
+                data_len.serialize_into(dst);
+                item.serialize_frame_into(self.protocol_version, dst);
+            }
         }
+        Ok(())
+    }
+}
+
+/// A packet that the encoder can write a frame for.
+trait EncodeFrame {
+    fn frame_len(&self, version: ProtocolVersion) -> Option<usize>;
+    fn serialize_frame_into(&self, version: ProtocolVersion, buf: &mut impl BufMut);
+}
+impl EncodeFrame for Packet {
+    fn frame_len(&self, version: ProtocolVersion) -> Option<usize> {
+        self.frame_len_versioned(version)
+    }
+    fn serialize_frame_into(&self, version: ProtocolVersion, buf: &mut impl BufMut) {
+        self.serialize_frame_versioned_into(version, buf);
+    }
+}
+impl EncodeFrame for RawPacket {
+    fn frame_len(&self, version: ProtocolVersion) -> Option<usize> {
+        self.frame_len_versioned(version)
+    }
+    fn serialize_frame_into(&self, version: ProtocolVersion, buf: &mut impl BufMut) {
+        self.serialize_frame_versioned_into(version, buf);
     }
 }
 impl Default for PacketCodec {
     fn default() -> Self {
-        PacketCodec {
-            client_state: ClientState::Handshake,
-            packet_direction: PacketDirection::Serverbound,
-            aes_cipher: None,
-        }
+        PacketCodec::new(ClientState::Handshake, PacketDirection::Serverbound)
     }
 }
 impl Decoder for PacketCodec {
-    type Item = Packet;
+    type Item = RawPacket;
     type Error = Error;
 
+    /// Read a packet, only parsing its body if the codec needs it.
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        // Bytes from [0..encryption_start] are decrypted.
-        // Bytes from [encryption_start..] are decrypted.
-        if let Some((_, ref mut aes_decryptor, encryption_start)) = self.aes_cipher.as_mut() {
-            // We have to do a bunch of stupid type fuckery to get CFB8 working
-            // because for some reason decrypt() consumes self?!
-            let encrypted_src = src.split_off(*encryption_start);
-            // Convert BytesMut to Vec<GenericArray<u8, UInt<UTerm, B1>>>
-            let mut encrypted_src = encrypted_src
-                .into_iter()
-                .map(|b| *GenericCFB8BlockArray::from_slice(&[b]))
-                .collect::<Vec<_>>();
-            // Decrypt the bytes in place.
-            aes_decryptor.decrypt_blocks_mut(encrypted_src.as_mut_slice());
-            // Convert Vec<GenericArray<u8, UInt<UTerm, B1>>> to Vec<u8>
-            let encrypted_src = encrypted_src
-                .into_iter()
-                .flat_map(|b| b.to_vec())
-                .collect::<Vec<u8>>();
-
-            // Append the decrypted bytes back to the source and move the encryption start position.
-            *encryption_start += encrypted_src.len();
-            src.extend_from_slice(&encrypted_src);
-        }
-
-        match Packet::parse(self.client_state, self.packet_direction, src) {
-            Ok((rest, packet)) => {
-                let bytes_consumed = src.len() - rest.len();
-                src.advance(bytes_consumed);
-                if let Some((_, _, encryption_start)) = &mut self.aes_cipher {
-                    // Adjust the encryption start position if we are using AES encryption.
-                    *encryption_start -= bytes_consumed;
-                }
+        // Read the frame length.
+        let (frame_length, header_length) = match VarInt::parse_usize(src) {
+            Ok((rest, frame_length)) => (frame_length, src.len() - rest.len()),
+            // Lengths that don't fit in 3 bytes are too long anyway.
+            Err(nom::Err::Incomplete(_)) if src.len() < 3 => {
+                src.reserve(3);
+                return Ok(None);
+            }
+            Err(_) => return Err(Error::Parsing),
+        };
+        // Check the length before reserving space for the frame.
+        if frame_length > MAX_PACKET_LENGTH {
+            trace!("packet too long: {} bytes", frame_length);
+            return Err(Error::Parsing);
+        }
+        if src.len() < header_length + frame_length {
+            src.reserve(header_length + frame_length - src.len());
+            return Ok(None);
+        }
 
-                if let Some(next_state) = packet.state_change() {
-                    self.client_state = next_state;
-                }
+        // Remove the whole frame from the source.
+        src.advance(header_length);
+        let frame = src.split_to(frame_length).freeze();
 
-                Ok(Some(packet))
-            }
-            Err(nom::Err::Incomplete(_)) => {
-                // Try to read the packet length.
-                match VarInt::parse_usize(src) {
-                    Ok((_, packet_length)) => {
-                        src.reserve(packet_length + 64);
-                        Ok(None)
-                    }
-                    Err(nom::Err::Incomplete(_)) => {
-                        src.reserve(5);
-                        Ok(None)
-                    }
-                    Err(_) => Err(Error::Parsing),
-                }
+        let frame = self.decompress_frame(frame)?;
+        let invalid_frame = || {
+            let error = Packet::frame_error(
+                self.protocol_version,
+                self.client_state,
+                self.packet_direction,
+                &frame,
+            );
+            trace!("{}: {:02X?}", error, frame);
+            Error::InvalidPacket(Box::new(error))
+        };
+        let Ok((body, packet_id)) = VarInt::parse(&frame) else {
+            return Err(invalid_frame());
+        };
+        let Some(packet_id) = Packet::latest_id(
+            self.protocol_version,
+            self.client_state,
+            self.packet_direction,
+            *packet_id,
+        ) else {
+            return Err(invalid_frame());
+        };
+        let mut packet = RawPacket::new(
+            self.protocol_version,
+            self.client_state,
+            self.packet_direction,
+            packet_id,
+            frame.slice(frame.len() - body.len()..),
+        );
+
+        PacketCodec::track_bundle(&mut self.reading_bundle, packet.is::<BundleDelimiter>())?;
+        if PacketCodec::needs_parsing(&packet) {
+            let parsed = packet.parse().map_err(|_| invalid_frame())?;
+            self.set_protocol_version(parsed);
+            if let Some(next_state) = parsed.state_change() {
+                self.client_state = next_state;
             }
-            Err(nom::Err::Error(e)) => {
-                trace!("parsing error: {:02X?}", e.input);
-                Err(Error::Parsing)
+            if let Packet::SetCompression(set_compression) = parsed {
+                self.set_compression_threshold(*set_compression.threshold);
             }
-            Err(nom::Err::Failure(_)) => Err(Error::Parsing),
         }
+
+        Ok(Some(packet))
     }
 }
 impl Encoder<Packet> for PacketCodec {
     type Error = Error;
 
     fn encode(&mut self, item: Packet, dst: &mut BytesMut) -> Result<(), Self::Error> {
-        let mut body = vec![];
-        let (packet_id, packet_body) = item.serialize();
-        body.extend(packet_id.serialize().to_vec());
-        body.extend(packet_body);
-        // TODO: Packet compression on `body`.
-        let packet_len = VarInt::from(body.len()).serialize();
-        let mut out = Vec::with_capacity(packet_len.len() + body.len());
-        out.extend(packet_len);
-        out.extend(body);
-
-        if let Some((ref mut aes_encryptor, _, _)) = &mut self.aes_cipher {
-            // We have to do a bunch of stupid type fuckery to get CFB8 working
-            // because for some reason decrypt() consumes self?!
-            // Convert Vec<u8> to Vec<GenericArray<u8, UInt<UTerm, B1>>>
-            let mut encrypted_out = out
-                .into_iter()
-                .map(|b| *GenericCFB8BlockArray::from_slice(&[b]))
-                .collect::<Vec<_>>();
-            // Decrypt the bytes in place.
-            aes_encryptor.encrypt_blocks_mut(encrypted_out.as_mut_slice());
-            // Convert Vec<GenericArray<u8, UInt<UTerm, B1>>> to Vec<u8>
-            let encrypted_out = encrypted_out
-                .into_iter()
-                .flat_map(|b| b.to_vec())
-                .collect::<Vec<u8>>();
-
-            out = encrypted_out;
-        }
-
-        dst.extend(out);
+        PacketCodec::track_bundle(
+            &mut self.writing_bundle,
+            matches!(item, Packet::BundleDelimiter(_)),
+        )?;
+        self.write_frame(&item, dst)?;
+
+        self.set_protocol_version(&item);
+        // Compression starts with the packet after SetCompression.
+        if let Packet::SetCompression(ref set_compression) = item {
+            self.set_compression_threshold(*set_compression.threshold);
+        }
+
         Ok(())
     }
 }
+impl Encoder<RawPacket> for PacketCodec {
+    type Error = Error;
+
+    /// Write a packet, copying its body as is if it's already in the connection's version.
+    fn encode(&mut self, item: RawPacket, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if PacketCodec::needs_parsing(&item) || item.version != self.protocol_version {
+            let packet = item
+                .into_packet()
+                .map_err(|e| Error::InvalidPacket(Box::new(e)))?;
+            return self.encode(packet, dst);
+        }
+        PacketCodec::track_bundle(&mut self.writing_bundle, false)?;
+        self.write_frame(&item, dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::packets::{
+        configuration::{
+            clientbound::{
+                ClientboundKnownPacks, ConfigurationClientboundPluginMessage, FinishConfiguration,
+            },
+            serverbound::ServerboundKnownPacks,
+        },
+        handshake::serverbound::Handshake,
+        login::{
+            clientbound::{LoginSuccess, SetCompression},
+            serverbound::LoginStart,
+        },
+        play::{
+            clientbound::{BundleDelimiter, PlayClientboundKeepAlive, TestInstanceBlockStatus},
+            serverbound::{ChatMessage, PlayServerboundKeepAlive},
+        },
+    };
+    use crate::protocol::{
+        types::{GameProfile, KnownPack, Uuid},
+        HandshakeIntent,
+    };
+
+    fn codec(threshold: Option<usize>) -> PacketCodec {
+        let mut codec = PacketCodec::new(ClientState::Configuration, PacketDirection::Clientbound);
+        codec.compression_threshold = threshold;
+        codec
+    }
+    fn decode(codec: &mut PacketCodec, buf: &mut BytesMut) -> Option<Packet> {
+        codec
+            .decode(buf)
+            .unwrap()
+            .map(|packet| packet.into_packet().unwrap())
+    }
+    fn plugin_message(len: usize) -> Packet {
+        ConfigurationClientboundPluginMessage {
+            channel: "composition:test".to_owned(),
+            data: vec![0x2a; len],
+        }
+        .into()
+    }
+
+    #[test]
+    fn compressed_round_trip_works() {
+        for threshold in [None, Some(0), Some(64), Some(256)] {
+            for len in [0, 10, 255, 4096] {
+                let mut codec = codec(threshold);
+                let mut buf = BytesMut::new();
+                codec.encode(plugin_message(len), &mut buf).unwrap();
+                let decoded = decode(&mut codec, &mut buf);
+                assert_eq!(decoded, Some(plugin_message(len)));
+                assert!(buf.is_empty());
+            }
+        }
+    }
+    #[test]
+    fn small_packets_are_not_compressed() {
+        let mut buf = BytesMut::new();
+        codec(Some(256))
+            .encode(plugin_message(4), &mut buf)
+            .unwrap();
+        // Frame length, data length of 0, packet id, then the uncompressed body.
+        assert_eq!(buf[1], 0x00);
+        assert_eq!(buf[2], 0x01);
+    }
+    #[test]
+    fn large_packets_are_compressed() {
+        let mut buf = BytesMut::new();
+        codec(Some(256))
+            .encode(plugin_message(4096), &mut buf)
+            .unwrap();
+        assert!(buf.len() < 4096);
+    }
+    #[test]
+    fn long_frames_are_rejected() {
+        let mut codec = codec(None);
+        // The largest length that fits in 3 bytes waits for the rest of the frame.
+        let mut buf = BytesMut::from(&[0xff, 0xff, 0x7f][..]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert!(buf.capacity() >= MAX_PACKET_LENGTH + 3);
+
+        // One more needs a 4th byte, and is rejected without reserving the space.
+        for frame in [&[0x80, 0x80, 0x80, 0x01][..], &[0x80, 0x80, 0x80]] {
+            let mut buf = BytesMut::from(frame);
+            assert!(matches!(codec.decode(&mut buf), Err(Error::Parsing)));
+            assert!(buf.capacity() < MAX_PACKET_LENGTH);
+        }
+        // Lengths are read once they start arriving.
+        let mut buf = BytesMut::from(&[0x80, 0x80][..]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+    }
+    #[test]
+    fn compressed_packets_below_the_threshold_are_rejected() {
+        // Compress a 10 byte packet for a codec that only compresses from 256 bytes.
+        let mut buf = BytesMut::new();
+        codec(Some(0)).encode(plugin_message(10), &mut buf).unwrap();
+        assert_ne!(buf[1], 0x00);
+        assert!(matches!(
+            codec(Some(256)).decode(&mut buf),
+            Err(Error::Parsing)
+        ));
+    }
+    #[test]
+    fn set_compression_enables_compression() {
+        let mut encoder = PacketCodec::new(ClientState::Login, PacketDirection::Clientbound);
+        let mut decoder = encoder.clone();
+        let mut buf = BytesMut::new();
+        encoder
+            .encode(
+                Packet::from(SetCompression {
+                    threshold: VarInt::from(128),
+                }),
+                &mut buf,
+            )
+            .unwrap();
+        assert_eq!(encoder.compression_threshold, Some(128));
+        assert!(decoder.decode(&mut buf).unwrap().is_some());
+        assert_eq!(decoder.compression_threshold, Some(128));
+    }
+    #[test]
+    fn partial_frames_are_buffered() {
+        let mut codec = codec(Some(64));
+        let mut buf = BytesMut::new();
+        codec.encode(plugin_message(1024), &mut buf).unwrap();
+        let mut partial = buf.split_to(buf.len() / 2);
+        assert_eq!(codec.decode(&mut partial).unwrap(), None);
+        partial.unsplit(buf);
+        assert_eq!(decode(&mut codec, &mut partial), Some(plugin_message(1024)));
+    }
+    #[test]
+    fn handshake_sets_protocol_version() {
+        let mut encoder = PacketCodec::default();
+        let mut decoder = PacketCodec::default();
+        let mut buf = BytesMut::new();
+        let handshake = |protocol_version: i32| -> Packet {
+            Handshake {
+                protocol_version: VarInt::from(protocol_version),
+                host: "localhost".into(),
+                port: 25565,
+                intent: HandshakeIntent::Login,
+            }
+            .into()
+        };
+        encoder.encode(handshake(769), &mut buf).unwrap();
+        assert_eq!(encoder.protocol_version, ProtocolVersion::V1_21_4);
+        decoder.decode(&mut buf).unwrap();
+        assert_eq!(decoder.protocol_version, ProtocolVersion::V1_21_4);
+
+        // Unsupported versions fall back to the latest.
+        let mut decoder = PacketCodec::default();
+        PacketCodec::default()
+            .encode(handshake(1), &mut buf)
+            .unwrap();
+        decoder.decode(&mut buf).unwrap();
+        assert_eq!(decoder.protocol_version, ProtocolVersion::LATEST);
+    }
+    #[test]
+    fn protocol_769_connections_work() {
+        // The client's end of the connection, and the server's.
+        let mut client = PacketCodec::new(ClientState::Handshake, PacketDirection::Clientbound);
+        let mut server = PacketCodec::default();
+        /// Write `packet` from one end, check its frame, and read it on the other.
+        fn send(from: &mut PacketCodec, to: &mut PacketCodec, packet: Packet, frame: &[u8]) {
+            let mut buf = BytesMut::new();
+            from.encode(packet.clone(), &mut buf).unwrap();
+            assert_eq!(&buf[..], frame);
+            assert_eq!(decode(to, &mut buf), Some(packet));
+            assert!(buf.is_empty());
+        }
+        let uuid = Uuid::from_u128(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef);
+        let uuid_bytes = uuid.as_bytes();
+        let known_packs = vec![KnownPack::core(ProtocolVersion::V1_21_4)];
+        let known_packs_body = [
+            0x01, // known_packs: Vec<KnownPack>
+            0x09, b'm', b'i', b'n', b'e', b'c', b'r', b'a', b'f', b't', // namespace: String
+            0x04, b'c', b'o', b'r', b'e', // id: String
+            0x06, b'1', b'.', b'2', b'1', b'.', b'4', // version: String
+        ];
+
+        let handshake = Handshake {
+            protocol_version: VarInt::from(769),
+            host: "localhost".into(),
+            port: 25565,
+            intent: HandshakeIntent::Login,
+        };
+        send(
+            &mut client,
+            &mut server,
+            handshake.into(),
+            &[
+                0x10, // Packet length
+                0x00, // Packet ID
+                0x81, 0x06, // protocol_version: VarInt
+                0x09, b'l', b'o', b'c', b'a', b'l', b'h', b'o', b's', b't', // host: String
+                0x63, 0xdd, // port: u16
+                0x02, // intent: HandshakeIntent (VarInt)
+            ],
+        );
+        assert_eq!(client.protocol_version, ProtocolVersion::V1_21_4);
+        assert_eq!(server.protocol_version, ProtocolVersion::V1_21_4);
+        assert_eq!(server.client_state, ClientState::Login);
+        client.client_state = ClientState::Login;
+
+        let mut frame = vec![
+            0x17, // Packet length
+            0x00, // Packet ID
+            0x05, b'S', b't', b'e', b'v', b'e', // name: String
+        ];
+        frame.extend(uuid_bytes); // uuid: Uuid
+        let login_start = LoginStart {
+            name: "Steve".into(),
+            uuid,
+        };
+        send(&mut client, &mut server, login_start.into(), &frame);
+
+        let mut frame = vec![
+            0x18, // Packet length
+            0x02, // Packet ID
+        ];
+        frame.extend(uuid_bytes); // profile.uuid: Uuid
+        frame.extend([0x05, b'S', b't', b'e', b'v', b'e']); // profile.username: String
+        frame.push(0x00); // profile.properties: Vec<ProfileProperty>
+        let login_success = LoginSuccess {
+            profile: GameProfile::offline("Steve".into(), Some(uuid)),
+        };
+        send(&mut server, &mut client, login_success.into(), &frame);
+
+        send(
+            &mut client,
+            &mut server,
+            LoginAcknowledged {}.into(),
+            &[
+                0x01, // Packet length
+                0x03, // Packet ID
+            ],
+        );
+        assert_eq!(server.client_state, ClientState::Configuration);
+        client.client_state = ClientState::Configuration;
+
+        let mut frame = vec![
+            0x18, // Packet length
+            0x0e, // Packet ID
+        ];
+        frame.extend(known_packs_body);
+        let clientbound_known_packs = ClientboundKnownPacks {
+            known_packs: known_packs.clone(),
+        };
+        send(
+            &mut server,
+            &mut client,
+            clientbound_known_packs.into(),
+            &frame,
+        );
+        let mut frame = vec![
+            0x18, // Packet length
+            0x07, // Packet ID
+        ];
+        frame.extend(known_packs_body);
+        let serverbound_known_packs = ServerboundKnownPacks { known_packs };
+        send(
+            &mut client,
+            &mut server,
+            serverbound_known_packs.into(),
+            &frame,
+        );
+
+        send(
+            &mut server,
+            &mut client,
+            FinishConfiguration {}.into(),
+            &[
+                0x01, // Packet length
+                0x03, // Packet ID
+            ],
+        );
+        send(
+            &mut client,
+            &mut server,
+            AcknowledgeFinishConfiguration {}.into(),
+            &[
+                0x01, // Packet length
+                0x03, // Packet ID
+            ],
+        );
+        assert_eq!(server.client_state, ClientState::Play);
+        client.client_state = ClientState::Play;
+
+        // Play packet ids and layouts differ from 1.21.5.
+        send(
+            &mut client,
+            &mut server,
+            PlayServerboundKeepAlive { payload: 7 }.into(),
+            &[
+                0x09, // Packet length
+                0x1a, // Packet ID
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, // payload: i64
+            ],
+        );
+        let chat_message = ChatMessage {
+            message: "hi".into(),
+            timestamp: 1,
+            salt: 2,
+            ..Default::default()
+        };
+        send(
+            &mut client,
+            &mut server,
+            chat_message.into(),
+            &[
+                0x19, // Packet length
+                0x07, // Packet ID
+                0x02, b'h', b'i', // message: String
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, // timestamp: i64
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, // salt: i64
+                0x00, // signature: PrefixedOptional<[u8; 256]>
+                0x00, // message_count: VarInt
+                0x00, 0x00, 0x00, // acknowledged: FixedBitSet<3>
+            ],
+        );
+        send(
+            &mut server,
+            &mut client,
+            PlayClientboundKeepAlive { payload: 7 }.into(),
+            &[
+                0x09, // Packet length
+                0x27, // Packet ID
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, // payload: i64
+            ],
+        );
+    }
+    #[test]
+    fn missing_packets_are_not_encoded() {
+        let mut codec = PacketCodec::new(ClientState::Play, PacketDirection::Clientbound);
+        codec.protocol_version = ProtocolVersion::V1_21_4;
+        let result = codec.encode(
+            Packet::from(TestInstanceBlockStatus::default()),
+            &mut BytesMut::new(),
+        );
+        assert!(matches!(result, Err(Error::UnsupportedPacket)));
+    }
+    #[test]
+    fn invalid_packets_are_described() {
+        let mut codec = PacketCodec::new(ClientState::Status, PacketDirection::Serverbound);
+        // A PingRequest whose payload is one byte short, which isn't parsed until it's needed.
+        let mut buf = BytesMut::from(&[0x08, 0x01, 0, 0, 0, 0, 0, 0, 0][..]);
+        let packet = codec.decode(&mut buf).unwrap().unwrap();
+        let error = packet.into_packet().unwrap_err();
+        assert_eq!(error.packet_id, Some(0x01));
+        assert_eq!(error.packet_name, Some("PingRequest"));
+        assert_eq!(error.field, Some("payload"));
+        assert_eq!(error.offset, 1);
+
+        // Unknown packet ids are rejected right away.
+        let mut buf = BytesMut::from(&[0x01, 0x7f][..]);
+        match codec.decode(&mut buf) {
+            Err(Error::InvalidPacket(error)) => assert_eq!(error.packet_id, Some(0x7f)),
+            result => panic!("expected an invalid packet, got {result:?}"),
+        }
+    }
+    #[test]
+    fn raw_packets_are_forwarded_unparsed() {
+        let mut decoder = codec(Some(64));
+        let mut encoder = codec(None);
+        for len in [10, 4096] {
+            let mut buf = BytesMut::new();
+            decoder.encode(plugin_message(len), &mut buf).unwrap();
+            let packet = decoder.decode(&mut buf).unwrap().unwrap();
+            assert_eq!(packet.packet(), None);
+
+            let mut raw = BytesMut::new();
+            encoder.encode(packet, &mut raw).unwrap();
+            let mut parsed = BytesMut::new();
+            encoder.encode(plugin_message(len), &mut parsed).unwrap();
+            assert_eq!(raw, parsed);
+        }
+
+        // Packets that change the connection are parsed as they're read and written.
+        let mut decoder = PacketCodec::new(ClientState::Login, PacketDirection::Clientbound);
+        let mut encoder = decoder.clone();
+        let mut buf = BytesMut::new();
+        let set_compression = Packet::from(SetCompression {
+            threshold: VarInt::from(128),
+        });
+        decoder.encode(set_compression.clone(), &mut buf).unwrap();
+        decoder.compression_threshold = None;
+        let packet = decoder.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(packet.packet(), Some(&set_compression));
+        assert_eq!(decoder.compression_threshold, Some(128));
+        encoder.encode(packet, &mut buf).unwrap();
+        assert_eq!(encoder.compression_threshold, Some(128));
+    }
+    #[test]
+    fn bundles_are_tracked() {
+        let mut encoder = PacketCodec::new(ClientState::Play, PacketDirection::Clientbound);
+        let mut decoder = encoder.clone();
+        let mut buf = BytesMut::new();
+        let delimiter = || -> Packet { Packet::from(BundleDelimiter {}) };
+
+        encoder.encode(delimiter(), &mut buf).unwrap();
+        assert!(encoder.is_writing_bundle());
+        encoder
+            .encode(Packet::from(TestInstanceBlockStatus::default()), &mut buf)
+            .unwrap();
+        encoder.encode(delimiter(), &mut buf).unwrap();
+        assert!(!encoder.is_writing_bundle());
+
+        assert_eq!(decode(&mut decoder, &mut buf), Some(delimiter()));
+        assert!(decoder.is_reading_bundle());
+        decoder.decode(&mut buf).unwrap();
+        assert!(decoder.is_reading_bundle());
+        assert_eq!(decode(&mut decoder, &mut buf), Some(delimiter()));
+        assert!(!decoder.is_reading_bundle());
+    }
+    #[test]
+    fn oversized_bundles_are_rejected() {
+        let mut codec = PacketCodec::new(ClientState::Play, PacketDirection::Clientbound);
+        let mut buf = BytesMut::new();
+        codec
+            .encode(Packet::from(BundleDelimiter {}), &mut buf)
+            .unwrap();
+        for _ in 0..MAX_BUNDLE_PACKETS {
+            codec
+                .encode(Packet::from(TestInstanceBlockStatus::default()), &mut buf)
+                .unwrap();
+        }
+        let result = codec.encode(Packet::from(TestInstanceBlockStatus::default()), &mut buf);
+        assert!(matches!(result, Err(Error::Invalid)));
+    }
+}
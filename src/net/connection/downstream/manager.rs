@@ -3,7 +3,7 @@ use crate::{
         connection::{DownstreamConnection, DownstreamConnectionState},
         error::Error,
     },
-    protocol::{types::Chat, ClientState},
+    protocol::{text::TextComponent, ClientState},
 };
 use std::{collections::HashMap, time::Duration};
 use tokio::{
@@ -96,14 +96,10 @@ impl DownstreamConnectionManager {
                     let id = connection.id();
 
                     match self.max_clients {
-                        Some(max) => {
-                            if self.clients.len() >= max {
-                                let _ = connection.disconnect(None).await;
-                            } else {
-                                self.clients.insert(id, connection);
-                            }
+                        Some(max) if self.clients.len() >= max => {
+                            let _ = connection.disconnect(None).await;
                         }
-                        None => {
+                        _ => {
                             self.clients.insert(id, connection);
                         }
                     }
@@ -143,15 +139,13 @@ impl DownstreamConnectionManager {
     pub async fn disconnect(
         &mut self,
         id: u128,
-        reason: Option<Chat>,
+        reason: Option<TextComponent>,
     ) -> Option<Result<(), Error>> {
         let mut client = self.clients.remove(&id)?;
         Some(client.disconnect(reason).await)
     }
-    pub async fn shutdown(mut self, reason: Option<Chat>) -> Result<(), Error> {
-        let reason = reason.unwrap_or(serde_json::json!({
-            "text": "You have been disconnected!"
-        }));
+    pub async fn shutdown(mut self, reason: Option<TextComponent>) -> Result<(), Error> {
+        let reason = reason.unwrap_or(TextComponent::text("You have been disconnected!"));
 
         let mut clients = self.clients.drain().map(|(_, c)| c).collect::<Vec<_>>();
         let disconnections = clients
@@ -2,16 +2,50 @@ pub mod manager;
 
 use crate::{
     config::Config,
-    net::{connection::GenericConnection, error::Error},
+    net::{
+        connection::GenericConnection,
+        error::Error,
+        plugin::{self, PluginChannels, PluginContext, PluginMessage},
+    },
     protocol::{
-        packets::{self, Packet, PacketDirection},
-        types::Chat,
-        ClientState,
+        encryption::*,
+        entities::EntityPosition,
+        legacy_ping::{LegacyPing, LegacyStatus},
+        packets::{
+            self, configuration::serverbound::ConfigurationClientInformation, Packet,
+            PacketDirection,
+        },
+        parsing::Parsable,
+        registry,
+        text::TextComponent,
+        types::{GameProfile, KnownPack, VarInt},
+        ClientState, HandshakeIntent, ProtocolVersion,
     },
 };
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
 use tokio::net::TcpStream;
 use tracing::trace;
 
+#[cfg(feature = "server")]
+use crate::net::auth;
+
+/// How long to wait for the client to answer a login plugin request or cookie request.
+const LOGIN_RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often to send keep alives to clients in Configuration and Play.
+///
+/// This is shorter than the manager's idle timeout, so clients that are otherwise quiet stay connected.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(5);
+/// How long the client has to answer a keep alive before it's disconnected.
+const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(15);
+/// How long to wait for a packet from a client that may have nothing to send.
+///
+/// Clients in Configuration and Play can go quiet, so reads from them give up
+/// after this instead of holding up every other connection.
+const IDLE_READ_TIMEOUT: Duration = Duration::from_millis(50);
+
 /// The connection's current state.
 /// Similar to crate::protocol::ClientState,
 /// but has more fine-grained tracking for packet responses.
@@ -19,11 +53,23 @@ use tracing::trace;
 pub enum DownstreamConnectionState {
     #[default]
     Handshake,
+    /// Waiting to answer a pre-1.7 server list ping.
+    LegacyStatusRequest,
     StatusRequest,
     StatusPing,
     LoginStart,
     EncryptionResponse,
     LoginPluginResponse,
+    /// Waiting for the client to acknowledge login success.
+    LoginAcknowledge,
+    /// Sending the start of the configuration.
+    ConfigurationStart,
+    /// Waiting for the client to reply with the packs it knows.
+    ConfigurationKnownPacks,
+    /// Waiting for the client to acknowledge the end of configuration.
+    ConfigurationFinish,
+    /// Waiting for a client in Play to acknowledge going back to Configuration.
+    ConfigurationAcknowledge,
     Play,
     Disconnected,
 }
@@ -32,6 +78,30 @@ pub enum DownstreamConnectionState {
 pub struct DownstreamConnection {
     inner: GenericConnection,
     state: DownstreamConnectionState,
+    /// The protocol version sent in the client's handshake, which may not be supported.
+    handshake_protocol_version: i32,
+    /// The legacy ping the client sent instead of a handshake.
+    legacy_ping: Option<LegacyPing>,
+    /// The player's profile, set once login succeeds.
+    profile: Option<GameProfile>,
+    /// The client's settings, which it sends during configuration.
+    client_information: Option<ConfigurationClientInformation>,
+    /// The payload of the last keep alive that hasn't been answered.
+    keep_alive: Option<i64>,
+    /// When the last keep alive was sent.
+    last_keep_alive_time: Instant,
+    /// The message id of the next login plugin request.
+    next_plugin_message_id: i32,
+    /// Login plugin responses that arrived while waiting for a different response.
+    plugin_responses: HashMap<i32, Option<Vec<u8>>>,
+    /// Cookies that arrived while waiting for a different response.
+    cookies: HashMap<String, Option<Vec<u8>>>,
+    /// The plugin channels the client has registered.
+    client_channels: HashSet<String>,
+    /// The client's brand, which it sends on `minecraft:brand`.
+    client_brand: Option<String>,
+    /// Whether another server transferred the client here.
+    transferred: bool,
 }
 impl DownstreamConnection {
     pub fn new(id: u128, stream: TcpStream) -> Self {
@@ -39,6 +109,18 @@ impl DownstreamConnection {
             // receiving_direction: PacketDirection::Serverbound
             inner: GenericConnection::new(id, PacketDirection::Serverbound, stream),
             state: DownstreamConnectionState::Handshake,
+            handshake_protocol_version: 0,
+            legacy_ping: None,
+            profile: None,
+            client_information: None,
+            keep_alive: None,
+            last_keep_alive_time: Instant::now(),
+            next_plugin_message_id: 0,
+            plugin_responses: HashMap::new(),
+            cookies: HashMap::new(),
+            client_channels: HashSet::new(),
+            client_brand: None,
+            transferred: false,
         }
     }
     pub fn client_state(&self) -> DownstreamConnectionState {
@@ -47,6 +129,29 @@ impl DownstreamConnection {
     pub fn client_state_mut(&mut self) -> &mut DownstreamConnectionState {
         &mut self.state
     }
+    /// Whether the client's handshake named a protocol version we can speak.
+    pub fn is_version_supported(&self) -> bool {
+        ProtocolVersion::from_protocol_version(self.handshake_protocol_version).is_some()
+    }
+    pub fn profile(&self) -> Option<&GameProfile> {
+        self.profile.as_ref()
+    }
+    pub fn client_information(&self) -> Option<&ConfigurationClientInformation> {
+        self.client_information.as_ref()
+    }
+    pub fn client_channels(&self) -> &HashSet<String> {
+        &self.client_channels
+    }
+    pub fn client_brand(&self) -> Option<&str> {
+        self.client_brand.as_deref()
+    }
+    /// Whether another server transferred the client here.
+    ///
+    /// Transferred clients keep their cookies, so use `request_cookie` during login
+    /// to read back anything the previous server stored.
+    pub fn was_transferred(&self) -> bool {
+        self.transferred
+    }
     pub fn inner_state(&self) -> ClientState {
         self.inner.client_state()
     }
@@ -56,27 +161,58 @@ impl DownstreamConnection {
     pub async fn handle_handshake(&mut self) -> Result<(), Error> {
         use packets::handshake::serverbound::Handshake;
 
+        if let Some(ping) = self.inner.read_legacy_ping().await? {
+            self.legacy_ping = Some(ping);
+            *self.client_state_mut() = DownstreamConnectionState::LegacyStatusRequest;
+            return Ok(());
+        }
+
         let handshake = self.read_specific_packet::<Handshake>().await?;
+        self.handshake_protocol_version = *handshake.protocol_version;
 
-        match handshake.next_state {
-            ClientState::Status => {
+        // The codec already switched to the intent's next state.
+        match handshake.intent {
+            HandshakeIntent::Status => {
                 *self.client_state_mut() = DownstreamConnectionState::StatusRequest;
-                *self.inner_state_mut() = ClientState::Status;
             }
-            ClientState::Login => {
+            HandshakeIntent::Login => {
                 *self.client_state_mut() = DownstreamConnectionState::LoginStart;
-                *self.inner_state_mut() = ClientState::Login;
             }
-            _ => {
-                self.disconnect(Some(
-                    serde_json::json!({ "text": "Received invalid handshake." }),
-                ))
-                .await?;
+            HandshakeIntent::Transfer => {
+                if !Config::instance().server.accept_transfers {
+                    self.disconnect(Some(TextComponent::translatable(
+                        "multiplayer.disconnect.transfers_disabled",
+                        vec![],
+                    )))
+                    .await?;
+                    return Ok(());
+                }
+                self.transferred = true;
+                *self.client_state_mut() = DownstreamConnectionState::LoginStart;
             }
         }
 
         Ok(())
     }
+    /// Answer a pre-1.7 server list ping and disconnect.
+    pub async fn handle_legacy_status_ping(
+        &mut self,
+        online_player_count: usize,
+    ) -> Result<(), Error> {
+        let ping = self.legacy_ping.take().ok_or(Error::Unexpected)?;
+        let config = Config::instance();
+        let status = LegacyStatus {
+            game_version: config.global.game_version.clone(),
+            motd: config.server.motd.clone(),
+            online_players: online_player_count,
+            max_players: config.server.max_players,
+        };
+        self.inner
+            .send_legacy_response(&status.serialize(&ping))
+            .await?;
+        *self.client_state_mut() = DownstreamConnectionState::Disconnected;
+        Ok(())
+    }
     pub async fn handle_status_ping(&mut self, online_player_count: usize) -> Result<(), Error> {
         // The state just changed from Handshake to Status.
         use base64::Engine;
@@ -89,13 +225,23 @@ impl DownstreamConnection {
         let _status_request = self.read_specific_packet::<StatusRequest>().await?;
 
         // Send the status response packet.
+        // Supported clients get their own version back so they show as compatible,
+        // and other clients see the protocol mismatch and show the supported range.
         let config = Config::instance();
+        let version = if self.is_version_supported() {
+            serde_json::json!({
+                "name": self.protocol_version().game_version(),
+                "protocol": self.handshake_protocol_version
+            })
+        } else {
+            serde_json::json!({
+                "name": ProtocolVersion::supported_range(),
+                "protocol": config.global.protocol_version
+            })
+        };
         self.send_packet(StatusResponse {
             response: serde_json::json!({
-                "version": {
-                    "name": config.global.game_version,
-                    "protocol": config.global.protocol_version
-                },
+                "version": version,
                 "players": {
                     "max": config.server.max_players,
                     "online": online_player_count,
@@ -123,36 +269,89 @@ impl DownstreamConnection {
         // The state just changed from Handshake to Login.
         use packets::login::{clientbound::LoginSuccess, serverbound::LoginStart};
 
+        let config = Config::instance();
+        // LoginStart differs between versions, so refuse unsupported clients before reading it.
+        if !self.is_version_supported() {
+            trace!(
+                "Connection {} has unsupported protocol version {}",
+                self.inner.id,
+                self.handshake_protocol_version
+            );
+            self.disconnect(Some(config.server.unsupported_version_message()))
+                .await?;
+            return Ok(());
+        }
+
         // Read login start packet.
         let login_start = self.read_specific_packet::<LoginStart>().await?;
 
-        // Enable encryption and authenticate with Mojang.
-        // self.enable_encryption().await?;
+        #[cfg(feature = "server")]
+        let online_profile = if config.server.online_mode {
+            // Enable encryption and authenticate with Mojang.
+            match self.authenticate(&login_start.name).await {
+                Ok(profile) => Some(profile),
+                Err(e) => {
+                    self.disconnect(Some(TextComponent::text("Failed to verify username!")))
+                        .await?;
+                    return Err(e);
+                }
+            }
+        } else {
+            None
+        };
+        // Only the server authenticates players.
+        #[cfg(not(feature = "server"))]
+        let online_profile = None;
+        let profile = online_profile.unwrap_or_else(|| {
+            let uuid = Some(login_start.uuid).filter(|_| config.server.trust_client_uuid);
+            GameProfile::offline(login_start.name.into(), uuid)
+        });
 
         // Enable compression.
         self.enable_compression().await?;
 
         // Send login success packet.
         self.send_packet(LoginSuccess {
-            // Generate a random UUID if none was provided.
-            uuid: login_start.uuid.unwrap_or(uuid::Uuid::new_v4()),
-            username: login_start.name,
-            properties: vec![],
+            profile: profile.clone(),
         })
         .await?;
+        self.profile = Some(profile);
 
+        *self.client_state_mut() = DownstreamConnectionState::LoginAcknowledge;
         Ok(())
     }
-    pub async fn enable_encryption(&mut self) -> Result<(), Error> {
-        use crate::protocol::encryption::*;
+    /// Enable encryption and verify the player's session with the session server.
+    #[cfg(feature = "server")]
+    pub async fn authenticate(&mut self, username: &str) -> Result<GameProfile, Error> {
+        let shared_secret = self.enable_encryption(true).await?;
+
+        let config = Config::instance();
+        let server_hash = server_hash("", &shared_secret, &config.rsa_key_pair()?.0.serialize());
+        let profile =
+            auth::has_joined(&config.server.session_server, username, &server_hash).await?;
+        trace!(
+            "Authenticated {} ({}) on connection {}",
+            profile.username,
+            profile.uuid,
+            self.inner.id
+        );
+        Ok(profile)
+    }
+    /// Perform the encryption handshake and enable encryption on the connection.
+    ///
+    /// Returns the decrypted shared secret.
+    pub async fn enable_encryption(
+        &mut self,
+        use_mojang_authentication: bool,
+    ) -> Result<Vec<u8>, Error> {
         use packets::login::{clientbound::EncryptionRequest, serverbound::EncryptionResponse};
         use rand::{rngs::StdRng, Rng, SeedableRng};
 
         assert!(matches!(self.inner_state(), ClientState::Login));
 
-        // RSA keys were generated on startup.
+        // RSA keys are loaded or generated on first use.
         let config = Config::instance();
-        let (public_key, private_key) = &config.rsa_key_pair;
+        let (public_key, private_key) = config.rsa_key_pair()?;
         tracing::trace!(
             "{}",
             public_key
@@ -172,8 +371,7 @@ impl DownstreamConnection {
             server_id: "".into(),
             public_key: public_key.serialize(),
             verify_token: verify_token.to_vec(),
-            // TODO: Implement Mojang authentication.
-            use_mojang_authentication: false,
+            use_mojang_authentication,
         })
         .await?;
 
@@ -183,7 +381,7 @@ impl DownstreamConnection {
         // Verify the response.
         let decrypted_verify_token = private_key
             .decrypt(Pkcs1v15Encrypt, &encryption_response.verify_token)
-            .expect("failed to decrypt verify token");
+            .map_err(|_| Error::Invalid)?;
         if decrypted_verify_token != verify_token {
             return Err(Error::Invalid);
         }
@@ -191,38 +389,508 @@ impl DownstreamConnection {
         // Decrypt the shared secret.
         let shared_secret = private_key
             .decrypt(Pkcs1v15Encrypt, &encryption_response.shared_secret)
-            .expect("failed to decrypt shared secret");
+            .map_err(|_| Error::Invalid)?;
 
         // Enable encryption on the connection.
         trace!("Enabling encryption for connection {}", self.inner.id);
-        todo!("Fix AES encryption implementation");
-        let encryptor =
-            Aes128Cfb8Encryptor::new((&(*shared_secret)).into(), (&(*shared_secret)).into());
-        let decryptor =
-            Aes128Cfb8Decryptor::new((&(*shared_secret)).into(), (&(*shared_secret)).into());
-        self.inner.stream.codec_mut().aes_cipher = Some((encryptor, decryptor, 0));
+        self.inner.enable_encryption(&shared_secret)?;
+        Ok(shared_secret)
+    }
+    pub async fn enable_compression(&mut self) -> Result<(), Error> {
+        use packets::login::clientbound::SetCompression;
+
+        assert!(matches!(self.inner_state(), ClientState::Login));
 
+        let threshold = Config::instance().server.compression_threshold;
+        if threshold < 0 {
+            return Ok(());
+        }
+
+        // The codec switches to the compressed format after sending this packet.
+        trace!(
+            "Enabling compression for connection {} (threshold {})",
+            self.inner.id,
+            threshold
+        );
+        self.send_packet(SetCompression {
+            threshold: threshold.into(),
+        })
+        .await
+    }
+    /// Send a login plugin request and wait for the client's response.
+    ///
+    /// Returns `None` if the client didn't understand the channel.
+    pub async fn send_login_plugin_request(
+        &mut self,
+        channel: impl Into<String>,
+        data: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        use packets::login::clientbound::LoginPluginRequest;
+
+        assert!(matches!(self.inner_state(), ClientState::Login));
+
+        let message_id = self.next_plugin_message_id;
+        self.next_plugin_message_id = message_id.wrapping_add(1);
+        self.send_packet(LoginPluginRequest {
+            message_id: message_id.into(),
+            channel: channel.into(),
+            data,
+        })
+        .await?;
+        self.await_login_response(|c| c.plugin_responses.remove(&message_id))
+            .await
+    }
+    /// Ask the client for a cookie during login, such as one stored before a transfer.
+    ///
+    /// Returns `None` if the client doesn't have the cookie.
+    pub async fn request_cookie(
+        &mut self,
+        key: impl Into<String>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        use packets::login::clientbound::LoginCookieRequest;
+
+        assert!(matches!(self.inner_state(), ClientState::Login));
+
+        let key = key.into();
+        self.send_packet(LoginCookieRequest { key: key.clone() })
+            .await?;
+        self.await_login_response(|c| c.cookies.remove(&key)).await
+    }
+    /// Store a cookie on the client, which it keeps across transfers.
+    pub async fn store_cookie(
+        &mut self,
+        key: impl Into<String>,
+        payload: Vec<u8>,
+    ) -> Result<(), Error> {
+        use packets::{
+            configuration::clientbound::ConfigurationStoreCookie,
+            play::clientbound::PlayStoreCookie,
+        };
+
+        let key = key.into();
+        match self.inner_state() {
+            ClientState::Configuration => {
+                self.send_packet(ConfigurationStoreCookie { key, payload })
+                    .await
+            }
+            ClientState::Play => self.send_packet(PlayStoreCookie { key, payload }).await,
+            _ => Err(Error::Invalid),
+        }
+    }
+    /// Send the client to another server, which it will join with the Transfer intent.
+    ///
+    /// `cookies` are stored on the client first, so the other server can
+    /// request them during login to carry state across the transfer.
+    pub async fn transfer(
+        &mut self,
+        host: impl Into<String>,
+        port: u16,
+        cookies: Vec<(String, Vec<u8>)>,
+    ) -> Result<(), Error> {
+        use packets::{
+            configuration::clientbound::ConfigurationTransfer, play::clientbound::PlayTransfer,
+        };
+
+        for (key, payload) in cookies {
+            self.store_cookie(key, payload).await?;
+        }
+        let host = host.into();
+        let port = VarInt::from(port as i32);
+        match self.inner_state() {
+            ClientState::Configuration => {
+                self.send_packet(ConfigurationTransfer { host, port })
+                    .await?
+            }
+            ClientState::Play => self.send_packet(PlayTransfer { host, port }).await?,
+            _ => return Err(Error::Invalid),
+        }
+        // The client closes the connection itself once it starts the transfer.
+        *self.client_state_mut() = DownstreamConnectionState::Disconnected;
+        self.inner.disconnect().await
+    }
+    /// Read login responses until `take` finds the one being waited for.
+    async fn await_login_response(
+        &mut self,
+        mut take: impl FnMut(&mut Self) -> Option<Option<Vec<u8>>>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let responses = async {
+            loop {
+                if let Some(response) = take(self) {
+                    return Ok(response);
+                }
+                match self.read_packet().await.ok_or(Error::Unexpected)?? {
+                    Packet::LoginPluginResponse(response) => {
+                        let data = Some(response.data).filter(|_| response.successful);
+                        self.plugin_responses.insert(*response.message_id, data);
+                    }
+                    Packet::LoginCookieResponse(response) => {
+                        self.cookies.insert(response.key, response.payload.0);
+                    }
+                    _ => return Err(Error::Unexpected),
+                }
+            }
+        };
+        tokio::time::timeout(LOGIN_RESPONSE_TIMEOUT, responses)
+            .await
+            .map_err(|_| Error::Timeout)?
+    }
+    pub async fn handle_login_acknowledged(&mut self) -> Result<(), Error> {
+        use packets::login::serverbound::LoginAcknowledged;
+
+        // The codec switches to Configuration after reading this packet.
+        let _login_acknowledged = self.read_specific_packet::<LoginAcknowledged>().await?;
+        *self.client_state_mut() = DownstreamConnectionState::ConfigurationStart;
         Ok(())
     }
-    pub async fn enable_compression(&mut self) -> Result<(), Error> {
-        // TODO: Implement compression.
+    /// Send the server's brand, custom channels and feature flags, and offer the known packs.
+    pub async fn start_configuration(&mut self, channels: &PluginChannels) -> Result<(), Error> {
+        use packets::configuration::clientbound::{ClientboundKnownPacks, FeatureFlags};
+
+        assert!(matches!(self.inner_state(), ClientState::Configuration));
+
+        self.send_plugin_message(PluginMessage::new(
+            plugin::BRAND,
+            "Composition".to_owned().serialize(),
+        ))
+        .await?;
+        if let Some(register) = channels.register_message() {
+            self.send_plugin_message(register).await?;
+        }
+        self.send_packet(FeatureFlags {
+            feature_flags: vec!["minecraft:vanilla".into()],
+        })
+        .await?;
+
+        // Offer the core pack so the client can use its own copy of the registries.
+        self.send_packet(ClientboundKnownPacks {
+            known_packs: vec![KnownPack::core(self.protocol_version())],
+        })
+        .await?;
+
+        *self.client_state_mut() = DownstreamConnectionState::ConfigurationKnownPacks;
+        Ok(())
+    }
+    /// Send the registries and tags, then ask the client to finish configuration.
+    pub async fn send_registries(&mut self, known_packs: &[KnownPack]) -> Result<(), Error> {
+        use packets::configuration::clientbound::FinishConfiguration;
+
+        assert!(matches!(self.inner_state(), ClientState::Configuration));
+
+        let version = self.protocol_version();
+        for packet in
+            registry::registry_packets(version, registry::vanilla_for(version), known_packs)
+        {
+            self.send_packet(packet).await?;
+        }
+        self.send_packet(registry::tags_packet(registry::vanilla_tags_for(version)))
+            .await?;
+        self.send_packet(FinishConfiguration {}).await?;
+
+        *self.client_state_mut() = DownstreamConnectionState::ConfigurationFinish;
+        Ok(())
+    }
+    /// Read and respond to a packet from a client in Configuration.
+    pub async fn handle_configuration(&mut self, channels: &PluginChannels) -> Result<(), Error> {
+        let Some(packet) = self.read_idle_packet().await? else {
+            return Ok(());
+        };
+
+        match (self.client_state(), packet) {
+            (_, Packet::ConfigurationClientInformation(client_information)) => {
+                self.client_information = Some(client_information);
+            }
+            (_, Packet::ConfigurationServerboundKeepAlive(keep_alive)) => {
+                self.handle_keep_alive(keep_alive.payload)?;
+            }
+            (_, Packet::ConfigurationPong(pong)) => {
+                trace!("Connection {} ponged {}", self.inner.id, pong.payload);
+            }
+            (_, Packet::ConfigurationServerboundPluginMessage(plugin_message)) => {
+                self.handle_plugin_message(
+                    channels,
+                    PluginMessage::new(plugin_message.channel, plugin_message.data),
+                )
+                .await?;
+            }
+            (_, Packet::ConfigurationResourcePackResponse(response)) => {
+                trace!(
+                    "Connection {} resource pack {} result {}",
+                    self.inner.id,
+                    response.uuid,
+                    response.result
+                );
+            }
+            (
+                DownstreamConnectionState::ConfigurationKnownPacks,
+                Packet::ServerboundKnownPacks(known_packs),
+            ) => {
+                trace!(
+                    "Connection {} knows packs {:?}",
+                    self.inner.id,
+                    known_packs.known_packs
+                );
+                self.send_registries(&known_packs.known_packs).await?;
+            }
+            (
+                DownstreamConnectionState::ConfigurationFinish,
+                Packet::AcknowledgeFinishConfiguration(_),
+            ) => {
+                // The codec already switched to Play after reading this packet.
+                *self.client_state_mut() = DownstreamConnectionState::Play;
+                self.start_play().await?;
+            }
+            _ => return Err(Error::Unexpected),
+        }
+        Ok(())
+    }
+    /// Spawn the player in an empty overworld, so the client leaves the loading screen.
+    ///
+    /// There are no chunks to send yet, so the client stops waiting for them after its own timeout.
+    pub async fn start_play(&mut self) -> Result<(), Error> {
+        use packets::play::clientbound::{GameEvent, LoginPlay, SynchronizePlayerPosition};
+
+        assert!(matches!(self.inner_state(), ClientState::Play));
+
+        let dimension = "minecraft:overworld".to_owned();
+        let dimension_type = registry::vanilla_registry("minecraft:dimension_type")
+            .and_then(|registry| registry.index_of(&dimension))
+            .unwrap_or_default();
+        self.send_packet(LoginPlay {
+            entity_id: self.inner.id as i32,
+            is_hardcore: false,
+            dimension_names: vec![dimension.clone()],
+            // The client doesn't use this.
+            max_players: VarInt::from(0),
+            view_distance: VarInt::from(2),
+            simulation_distance: VarInt::from(2),
+            reduced_debug_info: false,
+            enable_respawn_screen: true,
+            do_limited_crafting: false,
+            dimension_type: VarInt::from(dimension_type),
+            dimension_name: dimension,
+            hashed_seed: 0,
+            // Spectator, so the player doesn't fall out of the empty world.
+            game_mode: 3,
+            previous_game_mode: -1,
+            is_debug: false,
+            is_flat: false,
+            death_location: None.into(),
+            portal_cooldown: VarInt::from(0),
+            sea_level: VarInt::from(63),
+            enforces_secure_chat: false,
+        })
+        .await?;
+        // Start waiting for level chunks.
+        self.send_packet(GameEvent {
+            event: 13,
+            value: 0.0,
+        })
+        .await?;
+        self.send_packet(SynchronizePlayerPosition {
+            teleport_id: VarInt::from(0),
+            position: EntityPosition {
+                x: 0.5,
+                y: 64.0,
+                z: 0.5,
+            },
+            velocity: (0.0, 0.0, 0.0),
+            yaw: 0.0,
+            pitch: 0.0,
+            flags: 0,
+        })
+        .await
+    }
+    /// Move a client in Play back into Configuration, such as to change registries or resource packs.
+    pub async fn start_reconfiguration(&mut self) -> Result<(), Error> {
+        use packets::play::clientbound::StartConfiguration;
+
+        assert!(matches!(self.inner_state(), ClientState::Play));
+
+        self.send_packet(StartConfiguration {}).await?;
+        *self.client_state_mut() = DownstreamConnectionState::ConfigurationAcknowledge;
+        Ok(())
+    }
+    /// Wait for a client to acknowledge reconfiguration.
+    pub async fn handle_reconfiguration(&mut self, channels: &PluginChannels) -> Result<(), Error> {
+        let Some(packet) = self.read_idle_packet().await? else {
+            return Ok(());
+        };
+        match packet {
+            Packet::AcknowledgeConfiguration(_) => {
+                // The codec switches to Configuration after reading this packet.
+                *self.client_state_mut() = DownstreamConnectionState::ConfigurationStart;
+            }
+            Packet::PlayServerboundKeepAlive(keep_alive) => {
+                self.handle_keep_alive(keep_alive.payload)?;
+            }
+            Packet::PlayServerboundPluginMessage(plugin_message) => {
+                self.handle_plugin_message(
+                    channels,
+                    PluginMessage::new(plugin_message.channel, plugin_message.data),
+                )
+                .await?;
+            }
+            // Play packets sent before the client saw `StartConfiguration` are dropped.
+            _ => {}
+        }
+        Ok(())
+    }
+    /// Read and respond to a packet from a client in Play.
+    pub async fn handle_play(&mut self, channels: &PluginChannels) -> Result<(), Error> {
+        let Some(packet) = self.read_idle_packet().await? else {
+            return Ok(());
+        };
+        match packet {
+            Packet::PlayServerboundKeepAlive(keep_alive) => {
+                self.handle_keep_alive(keep_alive.payload)?;
+            }
+            Packet::PlayServerboundPluginMessage(plugin_message) => {
+                self.handle_plugin_message(
+                    channels,
+                    PluginMessage::new(plugin_message.channel, plugin_message.data),
+                )
+                .await?;
+            }
+            // Gameplay isn't handled yet.
+            _ => {}
+        }
+        Ok(())
+    }
+    /// Track the client's channels and brand, then pass the message to its channel's handler.
+    ///
+    /// Any replies from the handler are sent back to the client.
+    pub async fn handle_plugin_message(
+        &mut self,
+        channels: &PluginChannels,
+        message: PluginMessage,
+    ) -> Result<(), Error> {
+        match message.channel.as_str() {
+            plugin::REGISTER => self
+                .client_channels
+                .extend(plugin::decode_channel_list(&message.data)),
+            plugin::UNREGISTER => {
+                for channel in plugin::decode_channel_list(&message.data) {
+                    self.client_channels.remove(&channel);
+                }
+            }
+            plugin::BRAND => {
+                let (_, brand) = String::parse(&message.data).map_err(|_| Error::Invalid)?;
+                self.client_brand = Some(brand);
+            }
+            _ => {}
+        }
+
+        let mut context =
+            PluginContext::new(self.inner.id, self.inner_state(), &self.client_channels);
+        if !channels.dispatch(&mut context, &message) {
+            trace!(
+                "Connection {} sent plugin message on unhandled channel {}",
+                self.inner.id,
+                message.channel
+            );
+        }
+        for response in context.into_responses() {
+            self.send_plugin_message(response).await?;
+        }
+        Ok(())
+    }
+    /// Send a message on a plugin channel to a client in Configuration or Play.
+    pub async fn send_plugin_message(&mut self, message: PluginMessage) -> Result<(), Error> {
+        use packets::{
+            configuration::clientbound::ConfigurationClientboundPluginMessage,
+            play::clientbound::PlayClientboundPluginMessage,
+        };
+
+        let PluginMessage { channel, data } = message;
+        match self.inner_state() {
+            ClientState::Configuration => {
+                self.send_packet(ConfigurationClientboundPluginMessage { channel, data })
+                    .await
+            }
+            ClientState::Play => {
+                self.send_packet(PlayClientboundPluginMessage { channel, data })
+                    .await
+            }
+            _ => Err(Error::Invalid),
+        }
+    }
+    /// Send a keep alive, which the client must echo back.
+    pub async fn send_keep_alive(&mut self) -> Result<(), Error> {
+        use packets::{
+            configuration::clientbound::ConfigurationClientboundKeepAlive,
+            play::clientbound::PlayClientboundKeepAlive,
+        };
+
+        let payload = crate::START_TIME
+            .get()
+            .map(|start| start.elapsed().as_millis() as i64)
+            .unwrap_or_default();
+        match self.inner_state() {
+            ClientState::Configuration => {
+                self.send_packet(ConfigurationClientboundKeepAlive { payload })
+                    .await?
+            }
+            ClientState::Play => {
+                self.send_packet(PlayClientboundKeepAlive { payload })
+                    .await?
+            }
+            _ => return Ok(()),
+        }
+        self.keep_alive = Some(payload);
+        self.last_keep_alive_time = Instant::now();
+        Ok(())
+    }
+    /// Send a keep alive when one is due, and disconnect the client
+    /// if it hasn't answered the last one in time.
+    pub async fn update_keep_alive(&mut self) -> Result<(), Error> {
+        let elapsed = self.last_keep_alive_time.elapsed();
+        if self.keep_alive.is_none() {
+            if elapsed >= KEEP_ALIVE_INTERVAL {
+                self.send_keep_alive().await?;
+            }
+        } else if elapsed > KEEP_ALIVE_TIMEOUT {
+            trace!(
+                "Connection {} didn't answer a keep alive in time",
+                self.inner.id
+            );
+            self.disconnect(Some(TextComponent::translatable(
+                "disconnect.timeout",
+                vec![],
+            )))
+            .await?;
+        }
+        Ok(())
+    }
+    /// Check the client's answer to the last keep alive.
+    ///
+    /// Wrong or unrequested answers are an error, so the client gets disconnected.
+    fn handle_keep_alive(&mut self, payload: i64) -> Result<(), Error> {
+        if self.keep_alive.take() != Some(payload) {
+            return Err(Error::Invalid);
+        }
         Ok(())
     }
     pub async fn read_packet(&mut self) -> Option<Result<Packet, Error>> {
         self.inner.read_packet().await
     }
+    /// Read a packet if the client sends one within `IDLE_READ_TIMEOUT`.
+    async fn read_idle_packet(&mut self) -> Result<Option<Packet>, Error> {
+        match tokio::time::timeout(IDLE_READ_TIMEOUT, self.read_packet()).await {
+            Ok(packet) => packet.ok_or(Error::Unexpected)?.map(Some),
+            // Reading frames is cancel safe, so a partly received packet is read next time.
+            Err(_) => Ok(None),
+        }
+    }
     pub async fn send_packet<P: Into<Packet>>(&mut self, packet: P) -> Result<(), Error> {
         self.inner.send_packet(packet).await
     }
-    pub async fn disconnect(&mut self, reason: Option<Chat>) -> Result<(), Error> {
+    pub async fn disconnect(&mut self, reason: Option<TextComponent>) -> Result<(), Error> {
         use packets::{
             configuration::clientbound::ConfigurationDisconnect,
             login::clientbound::LoginDisconnect, play::clientbound::PlayDisconnect,
         };
 
-        // let reason = reason.unwrap_or(serde_json::json!({
-        //     "text": "You have been disconnected!"
-        // }));
+        // let reason = reason.unwrap_or(TextComponent::text("You have been disconnected!"));
 
         if let Some(reason) = reason {
             match self.inner_state() {
@@ -230,7 +898,11 @@ impl DownstreamConnection {
                     // Impossible to send a disconnect in these states.
                 }
                 ClientState::Login => {
-                    let _ = self.send_packet(LoginDisconnect { reason }).await;
+                    let _ = self
+                        .send_packet(LoginDisconnect {
+                            reason: reason.into(),
+                        })
+                        .await;
                 }
                 ClientState::Configuration => {
                     let _ = self.send_packet(ConfigurationDisconnect { reason }).await;
@@ -9,23 +9,39 @@ pub use downstream::{
 pub use upstream::UpstreamConnection;
 
 use crate::{
-    net::{codec::PacketCodec, error::Error},
+    net::{
+        codec::{PacketCodec, MAX_BUNDLE_PACKETS},
+        error::Error,
+        stream::CipherStream,
+    },
     protocol::{
-        packets::{Packet, PacketDirection},
-        ClientState,
+        encryption::*,
+        legacy_ping::{LegacyPing, LEGACY_PING_ID},
+        packets::{Packet, PacketDirection, RawPacket},
+        ClientState, ProtocolVersion,
     },
 };
 use futures::{stream::StreamExt, SinkExt};
 use std::time::{Duration, Instant};
-use tokio::{io::BufStream, net::TcpStream};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
 use tokio_util::codec::{Decoder, Framed};
-use tracing::trace;
+use tracing::{debug, trace};
+
+/// How long to wait for the rest of a legacy ping once it has started.
+const LEGACY_PING_TIMEOUT: Duration = Duration::from_secs(2);
+/// How long to wait between checks for more of a legacy ping.
+const LEGACY_PING_POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// The most a legacy ping can take up, with a 255 character host name.
+const LEGACY_PING_MAX_LEN: usize = 1024;
 
 #[derive(Debug)]
 pub struct GenericConnection {
     /// The `GenericConnection`'s unique id.
     id: u128,
-    stream: Framed<BufStream<TcpStream>, PacketCodec>,
+    stream: Framed<CipherStream<TcpStream>, PacketCodec>,
     last_received_data_time: Instant,
     last_sent_data_time: Instant,
 }
@@ -35,7 +51,7 @@ impl GenericConnection {
 
         GenericConnection {
             id,
-            stream: codec.framed(BufStream::new(stream)),
+            stream: codec.framed(CipherStream::new(stream)),
             last_received_data_time: Instant::now(),
             last_sent_data_time: Instant::now(),
         }
@@ -49,26 +65,101 @@ impl GenericConnection {
     pub fn client_state_mut(&mut self) -> &mut ClientState {
         &mut self.stream.codec_mut().client_state
     }
+    /// The protocol version the connection speaks, as sent in its handshake.
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        self.stream.codec().protocol_version
+    }
     pub fn received_elapsed(&self) -> Duration {
         self.last_received_data_time.elapsed()
     }
     pub fn sent_elapsed(&self) -> Duration {
         self.last_sent_data_time.elapsed()
     }
-    pub async fn read_packet(&mut self) -> Option<Result<Packet, Error>> {
-        let packet = self.stream.next().await.map(|packet| {
-            packet.map_err(|mut e| {
-                // Set the codec error to something more descriptive.
-                if e.to_string() == "bytes remaining on stream" {
-                    e = Error::Io(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, e));
+    /// Encrypt the connection with AES-CFB8, using the shared secret as both key and IV.
+    pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> Result<(), Error> {
+        let shared_secret: &[u8; 16] = shared_secret.try_into().map_err(|_| Error::Invalid)?;
+        let encryptor = Aes128Cfb8Encryptor::new(shared_secret.into(), shared_secret.into());
+        let mut decryptor = Aes128Cfb8Decryptor::new(shared_secret.into(), shared_secret.into());
+
+        // Anything read but not yet decoded was sent after encryption started.
+        decrypt_in_place(&mut decryptor, self.stream.read_buffer_mut());
+        self.stream.get_mut().set_cipher(encryptor, decryptor);
+        Ok(())
+    }
+    /// Check for a pre-1.7 server list ping before any packets are framed.
+    ///
+    /// Waits for the client to send something, but consumes nothing unless it's a legacy ping.
+    /// Modern frame lengths can start with the same byte, so the data is only peeked
+    /// until the rest of the ping confirms it.
+    pub async fn read_legacy_ping(&mut self) -> Result<Option<LegacyPing>, Error> {
+        if !self.stream.read_buffer().is_empty() {
+            return Ok(None);
+        }
+        let stream = self.stream.get_mut().get_mut();
+        let started = Instant::now();
+        let mut data = vec![0u8; LEGACY_PING_MAX_LEN];
+        loop {
+            let n = stream.peek(&mut data).await?;
+            if n == 0 || data[0] != LEGACY_PING_ID {
+                return Ok(None);
+            }
+            match LegacyPing::parse(&data[..n]) {
+                Ok((rest, ping)) => {
+                    let mut ping_data = vec![0u8; n - rest.len()];
+                    stream.read_exact(&mut ping_data).await?;
+                    self.last_received_data_time = Instant::now();
+                    return Ok(Some(ping));
                 }
-                trace!("Error reading packet from connection {}: {:?}", self.id, e);
-                e
-            })
-        });
+                Err(nom::Err::Incomplete(_)) if n < data.len() => {
+                    if started.elapsed() > LEGACY_PING_TIMEOUT {
+                        return Err(Error::Timeout);
+                    }
+                    tokio::time::sleep(LEGACY_PING_POLL_INTERVAL).await;
+                }
+                // Anything else is a modern frame, which the codec reads as usual.
+                _ => return Ok(None),
+            }
+        }
+    }
+    /// Send a reply to a legacy ping, which isn't framed like a packet, then disconnect.
+    pub async fn send_legacy_response(&mut self, response: &[u8]) -> Result<(), Error> {
+        self.stream.get_mut().get_mut().write_all(response).await?;
+        self.last_sent_data_time = Instant::now();
+        self.disconnect().await
+    }
+    /// Log an error from reading a packet, making it more descriptive if needed.
+    fn read_error(&self, mut e: Error) -> Error {
+        // Set the codec error to something more descriptive.
+        if e.to_string() == "bytes remaining on stream" {
+            e = Error::Io(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, e));
+        }
+        if let Error::InvalidPacket(ref error) = e {
+            debug!(
+                "Received an invalid packet from connection {}: {}",
+                self.id, error
+            );
+        } else {
+            trace!("Error reading packet from connection {}: {:?}", self.id, e);
+        }
+        e
+    }
+    /// Read a packet without parsing its body, unless the connection needs to.
+    pub async fn read_raw_packet(&mut self) -> Option<Result<RawPacket, Error>> {
+        let packet = self
+            .stream
+            .next()
+            .await
+            .map(|packet| packet.map_err(|e| self.read_error(e)));
 
         if let Some(Ok(ref packet)) = packet {
-            trace!("Received packet from connection {}: {:?}", self.id, packet);
+            trace!(
+                "Received {:?} {:?} packet 0x{:02x} ({} bytes) from connection {}",
+                packet.client_state,
+                packet.direction,
+                packet.id,
+                packet.body.len(),
+                self.id
+            );
             self.last_received_data_time = Instant::now();
 
             if let Some(next_state) = packet.state_change() {
@@ -78,6 +169,17 @@ impl GenericConnection {
 
         packet
     }
+    pub async fn read_packet(&mut self) -> Option<Result<Packet, Error>> {
+        let packet = self.read_raw_packet().await?.and_then(|packet| {
+            packet
+                .into_packet()
+                .map_err(|e| self.read_error(Error::InvalidPacket(Box::new(e))))
+        });
+        if let Ok(ref packet) = packet {
+            trace!("Received packet from connection {}: {:?}", self.id, packet);
+        }
+        Some(packet)
+    }
     pub async fn read_specific_packet<P: TryFrom<Packet>>(&mut self) -> Result<P, Error> {
         let packet = self.read_packet().await.ok_or(Error::Unexpected)??;
         P::try_from(packet).map_err(|_| Error::Unexpected)
@@ -89,10 +191,151 @@ impl GenericConnection {
             trace!("Error sending packet to connection {}: {:?}", self.id, e);
         })
     }
+    /// Send Play packets between `BundleDelimiter`s, so the client applies them in the same tick.
+    ///
+    /// Bundles that are too long for the client are split up.
+    pub async fn send_bundle(&mut self, packets: Vec<Packet>) -> Result<(), Error> {
+        use crate::protocol::packets::play::clientbound::BundleDelimiter;
+
+        if self.client_state() != ClientState::Play {
+            return Err(Error::Invalid);
+        }
+        for bundle in packets.chunks(MAX_BUNDLE_PACKETS) {
+            trace!(
+                "Sending bundle of {} packets to connection {}",
+                bundle.len(),
+                self.id
+            );
+            self.stream.feed(Packet::from(BundleDelimiter {})).await?;
+            for packet in bundle {
+                self.stream.feed(packet.clone()).await?;
+            }
+            self.stream.feed(Packet::from(BundleDelimiter {})).await?;
+        }
+        SinkExt::<Packet>::flush(&mut self.stream).await
+    }
+    /// Send a packet read from another connection.
+    ///
+    /// Packets inside a bundle are held back until the bundle is closed,
+    /// so bundles are passed on whole.
+    pub async fn forward_packet(&mut self, packet: Packet) -> Result<(), Error> {
+        trace!("Forwarding packet to connection {}: {:?}", self.id, packet);
+        self.stream.feed(packet).await?;
+        if !self.stream.codec().is_writing_bundle() {
+            SinkExt::<Packet>::flush(&mut self.stream).await?;
+        }
+        Ok(())
+    }
+    /// Send a packet read from another connection without parsing it.
+    ///
+    /// Like `forward_packet`, bundles are passed on whole.
+    pub async fn forward_raw_packet(&mut self, packet: RawPacket) -> Result<(), Error> {
+        trace!(
+            "Forwarding {:?} {:?} packet 0x{:02x} ({} bytes) to connection {}",
+            packet.client_state,
+            packet.direction,
+            packet.id,
+            packet.body.len(),
+            self.id
+        );
+        self.stream.feed(packet).await?;
+        if !self.stream.codec().is_writing_bundle() {
+            SinkExt::<Packet>::flush(&mut self.stream).await?;
+        }
+        Ok(())
+    }
     pub async fn disconnect(&mut self) -> Result<(), Error> {
         trace!("Connection disconnected (id {})", self.id);
-        self.stream.flush().await?;
+        SinkExt::<Packet>::flush(&mut self.stream).await?;
         self.stream.codec_mut().client_state = ClientState::Disconnected;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{
+        packets::handshake::serverbound::Handshake, parsing::VarInt, HandshakeIntent,
+    };
+    use tokio::net::TcpListener;
+
+    /// Connect to a local listener, returning the client stream and the server's connection.
+    async fn connect() -> (TcpStream, GenericConnection) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+        (
+            client,
+            GenericConnection::new(0, PacketDirection::Serverbound, server),
+        )
+    }
+
+    #[tokio::test]
+    async fn legacy_ping_id_frame_length_is_a_handshake() {
+        let (mut client, mut connection) = connect().await;
+
+        // A host this long makes the frame length 254, which starts with 0xfe.
+        let handshake = Handshake {
+            protocol_version: VarInt::from(ProtocolVersion::LATEST.protocol_version()),
+            host: "a".repeat(246).into(),
+            port: 25565,
+            intent: HandshakeIntent::Status,
+        };
+        let (id, body) = Packet::from(handshake.clone()).serialize();
+        let mut frame = VarInt::from(id.serialize().len() + body.len()).serialize();
+        frame.extend(id.serialize());
+        frame.extend(body);
+        assert_eq!(frame[..2], [LEGACY_PING_ID, 0x01]);
+        client.write_all(&frame).await.unwrap();
+
+        assert_eq!(connection.read_legacy_ping().await.unwrap(), None);
+        assert_eq!(
+            connection.read_packet().await.unwrap().unwrap(),
+            Packet::Handshake(handshake)
+        );
+    }
+    #[tokio::test]
+    async fn legacy_pings_are_consumed() {
+        let (mut client, mut connection) = connect().await;
+
+        // The start of a 1.6 ping, with the rest arriving later.
+        client
+            .write_all(&[LEGACY_PING_ID, 0x01, 0xfa])
+            .await
+            .unwrap();
+        let mut rest = vec![];
+        for c in "MC|PingHost".encode_utf16() {
+            rest.extend(c.to_be_bytes());
+        }
+        let mut channel = (rest.len() as u16 / 2).to_be_bytes().to_vec();
+        channel.extend(rest);
+        let mut data = vec![78, 0x00, 0x01, 0x00, b'a'];
+        data.extend(25565i32.to_be_bytes());
+        channel.extend((data.len() as i16).to_be_bytes());
+        channel.extend(data);
+        let writer = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            client.write_all(&channel).await.unwrap();
+            client
+        });
+
+        assert_eq!(
+            connection.read_legacy_ping().await.unwrap(),
+            Some(LegacyPing::V1_6 {
+                protocol_version: 78,
+                host: "a".to_owned(),
+                port: 25565,
+            })
+        );
+        let mut client = writer.await.unwrap();
+        // Nothing after the ping was consumed.
+        client.write_all(&[0x7f]).await.unwrap();
+        let mut next = [0u8; 1];
+        let stream = connection.stream.get_mut().get_mut();
+        stream.read_exact(&mut next).await.unwrap();
+        assert_eq!(next, [0x7f]);
+    }
+}
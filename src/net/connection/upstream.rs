@@ -2,7 +2,9 @@ use crate::{
     net::{connection::GenericConnection, error::Error},
     protocol::{
         encryption::*,
-        packets::{self, Packet, PacketDirection},
+        packets::{
+            self, login::clientbound::EncryptionRequest, Packet, PacketDirection, RawPacket,
+        },
     },
 };
 use rand::{rngs::StdRng, Rng, SeedableRng};
@@ -22,53 +24,71 @@ impl UpstreamConnection {
     pub async fn read_packet(&mut self) -> Option<Result<Packet, Error>> {
         let packet = self.inner.read_packet().await?.ok()?;
 
-        match packet {
-            Packet::EncryptionRequest(ref packet) => {
-                // Extract the public key from the packet.
-                tracing::trace!(
-                    "{}",
-                    packet
-                        .public_key
-                        .iter()
-                        .map(|b| format!("{b:02X?}"))
-                        .collect::<Vec<String>>()
-                        .join("")
-                );
-                let public_key = rsa::RsaPublicKey::parse(&packet.public_key)
-                    .expect("Failed to parse RSA public key from packet")
-                    .1;
-
-                // Generate a shared secret.
-                let mut rng = StdRng::from_entropy();
-                let shared_secret: [u8; 16] = rng.gen();
-
-                // Create the AES stream cipher and initialize it with the shared secret.
-                let encryptor =
-                    Aes128Cfb8Encryptor::new((&shared_secret).into(), (&shared_secret).into());
-                let decryptor =
-                    Aes128Cfb8Decryptor::new((&shared_secret).into(), (&shared_secret).into());
+        if let Packet::EncryptionRequest(ref packet) = packet {
+            if let Err(e) = self.handle_encryption_request(packet).await {
+                return Some(Err(e));
+            }
+        }
 
-                // Send the encryption response packet.
-                self.send_packet(packets::login::serverbound::EncryptionResponse {
-                    shared_secret: public_key
-                        .encrypt(&mut rng, rsa::Pkcs1v15Encrypt, &shared_secret[..])
-                        .expect("Failed to encrypt shared secret"),
-                    verify_token: public_key
-                        .encrypt(&mut rng, rsa::Pkcs1v15Encrypt, &packet.verify_token[..])
-                        .expect("Failed to encrypt shared secret"),
-                })
-                .await
-                .expect("Failed to send encryption response");
+        Some(Ok(packet))
+    }
+    /// Read a packet without parsing its body, unless the connection needs to.
+    pub async fn read_raw_packet(&mut self) -> Option<Result<RawPacket, Error>> {
+        let mut packet = match self.inner.read_raw_packet().await? {
+            Ok(packet) => packet,
+            Err(e) => return Some(Err(e)),
+        };
 
-                // Enable encryption on the connection.
-                self.inner.stream.codec_mut().aes_cipher = Some((encryptor, decryptor, 0));
+        if packet.is::<EncryptionRequest>() {
+            let result = match packet.parse() {
+                Ok(Packet::EncryptionRequest(request)) => {
+                    self.handle_encryption_request(request).await
+                }
+                Ok(_) => unreachable!("checked the packet kind"),
+                Err(e) => Err(Error::InvalidPacket(Box::new(e))),
+            };
+            if let Err(e) = result {
+                return Some(Err(e));
             }
-            Packet::SetCompression(_) => todo!(),
-            _ => {}
         }
 
         Some(Ok(packet))
     }
+    /// Answer the server's encryption request and start encrypting the connection.
+    async fn handle_encryption_request(&mut self, packet: &EncryptionRequest) -> Result<(), Error> {
+        // Extract the public key from the packet.
+        tracing::trace!(
+            "{}",
+            packet
+                .public_key
+                .iter()
+                .map(|b| format!("{b:02X?}"))
+                .collect::<Vec<String>>()
+                .join("")
+        );
+        let public_key = rsa::RsaPublicKey::parse(&packet.public_key)
+            .expect("Failed to parse RSA public key from packet")
+            .1;
+
+        // Generate a shared secret.
+        let mut rng = StdRng::from_entropy();
+        let shared_secret: [u8; 16] = rng.gen();
+
+        // Send the encryption response packet.
+        self.send_packet(packets::login::serverbound::EncryptionResponse {
+            shared_secret: public_key
+                .encrypt(&mut rng, rsa::Pkcs1v15Encrypt, &shared_secret[..])
+                .expect("Failed to encrypt shared secret"),
+            verify_token: public_key
+                .encrypt(&mut rng, rsa::Pkcs1v15Encrypt, &packet.verify_token[..])
+                .expect("Failed to encrypt shared secret"),
+        })
+        .await
+        .expect("Failed to send encryption response");
+
+        // Enable encryption on the connection.
+        self.inner.enable_encryption(&shared_secret)
+    }
     pub async fn send_packet<P: Into<Packet>>(&mut self, packet: P) -> Result<(), Error> {
         self.inner.send_packet(packet).await
     }
@@ -1,3 +1,4 @@
+use crate::{config::ConfigError, protocol::error::ParseError};
 pub use std::io::Error as IoError;
 
 /// This type represents all possible errors that can occur in the network.
@@ -8,15 +9,30 @@ pub enum Error {
     Io(IoError),
     #[error("There was an error parsing data")]
     Parsing,
+    #[error("Could not parse {0}")]
+    InvalidPacket(Box<ParseError>),
     #[error("Received unexpected data")]
     Unexpected,
     #[error("Internal channel disconnected")]
     ConnectionChannelDisconnnection,
     #[error("Invalid response")]
     Invalid,
+    #[error("Packet does not exist in the connection's protocol version")]
+    UnsupportedPacket,
+    #[error("Timed out waiting for a response")]
+    Timeout,
+    #[error("Could not authenticate with the session server")]
+    Authentication,
+    #[error(transparent)]
+    Config(ConfigError),
 }
 impl From<std::io::Error> for Error {
     fn from(value: std::io::Error) -> Self {
         Error::Io(value)
     }
 }
+impl From<ConfigError> for Error {
+    fn from(value: ConfigError) -> Self {
+        Error::Config(value)
+    }
+}
@@ -1,5 +1,9 @@
 #![allow(dead_code)]
 
+#[cfg(feature = "server")]
+pub mod auth;
 pub mod codec;
 pub mod connection;
 pub mod error;
+pub mod plugin;
+pub mod stream;
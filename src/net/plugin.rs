@@ -0,0 +1,181 @@
+use crate::protocol::ClientState;
+use std::collections::{HashMap, HashSet};
+
+/// The channel a side uses to announce the channels it listens on.
+pub const REGISTER: &str = "minecraft:register";
+/// The channel a side uses to stop listening on channels.
+pub const UNREGISTER: &str = "minecraft:unregister";
+/// The channel the client and server announce their software's name on.
+pub const BRAND: &str = "minecraft:brand";
+
+/// A message sent on a plugin channel.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PluginMessage {
+    pub channel: String,
+    pub data: Vec<u8>,
+}
+impl PluginMessage {
+    pub fn new(channel: impl Into<String>, data: Vec<u8>) -> PluginMessage {
+        PluginMessage {
+            channel: normalize_channel(channel.into()),
+            data,
+        }
+    }
+}
+
+/// The connection a plugin message came from, passed to channel handlers.
+#[derive(Debug)]
+pub struct PluginContext<'a> {
+    pub connection_id: u128,
+    pub client_state: ClientState,
+    /// The channels the client has registered.
+    pub client_channels: &'a HashSet<String>,
+    responses: Vec<PluginMessage>,
+}
+impl<'a> PluginContext<'a> {
+    pub fn new(
+        connection_id: u128,
+        client_state: ClientState,
+        client_channels: &'a HashSet<String>,
+    ) -> Self {
+        PluginContext {
+            connection_id,
+            client_state,
+            client_channels,
+            responses: vec![],
+        }
+    }
+    /// Send a message back to the client once the handler returns.
+    pub fn reply(&mut self, channel: impl Into<String>, data: Vec<u8>) {
+        self.responses.push(PluginMessage::new(channel, data));
+    }
+    pub fn into_responses(self) -> Vec<PluginMessage> {
+        self.responses
+    }
+}
+
+type Handler = Box<dyn Fn(&mut PluginContext, &[u8]) + Send + Sync>;
+
+/// Handlers for the plugin channels the server listens on.
+///
+/// Messages are routed the same way in the Configuration and Play states.
+#[derive(Default)]
+pub struct PluginChannels {
+    handlers: HashMap<String, Handler>,
+}
+impl std::fmt::Debug for PluginChannels {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.handlers.keys()).finish()
+    }
+}
+impl PluginChannels {
+    /// Handle messages on `channel`, replacing any existing handler.
+    ///
+    /// Channels without a namespace are in the `minecraft` namespace.
+    pub fn register(
+        &mut self,
+        channel: impl Into<String>,
+        handler: impl Fn(&mut PluginContext, &[u8]) + Send + Sync + 'static,
+    ) {
+        self.handlers
+            .insert(normalize_channel(channel.into()), Box::new(handler));
+    }
+    /// Stop handling messages on `channel`, returning whether it was registered.
+    pub fn unregister(&mut self, channel: &str) -> bool {
+        self.handlers
+            .remove(&normalize_channel(channel.to_owned()))
+            .is_some()
+    }
+    pub fn is_registered(&self, channel: &str) -> bool {
+        self.handlers
+            .contains_key(&normalize_channel(channel.to_owned()))
+    }
+    /// The message announcing the server's custom channels to a client.
+    ///
+    /// Returns `None` if every channel is a vanilla one.
+    pub fn register_message(&self) -> Option<PluginMessage> {
+        let mut channels = self
+            .handlers
+            .keys()
+            .filter(|channel| !channel.starts_with("minecraft:"))
+            .collect::<Vec<_>>();
+        if channels.is_empty() {
+            return None;
+        }
+        channels.sort();
+        Some(PluginMessage::new(REGISTER, encode_channel_list(channels)))
+    }
+    /// Pass a message to its channel's handler, returning whether there was one.
+    pub fn dispatch(&self, context: &mut PluginContext, message: &PluginMessage) -> bool {
+        match self.handlers.get(&message.channel) {
+            Some(handler) => {
+                handler(context, &message.data);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Add the default `minecraft` namespace to a channel without one.
+fn normalize_channel(channel: String) -> String {
+    if channel.contains(':') {
+        channel
+    } else {
+        format!("minecraft:{channel}")
+    }
+}
+
+/// Encode the payload of a register or unregister message.
+pub fn encode_channel_list<S: AsRef<str>>(channels: impl IntoIterator<Item = S>) -> Vec<u8> {
+    channels
+        .into_iter()
+        .map(|channel| channel.as_ref().to_owned())
+        .collect::<Vec<_>>()
+        .join("\0")
+        .into_bytes()
+}
+/// Decode the payload of a register or unregister message.
+pub fn decode_channel_list(data: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(data)
+        .split('\0')
+        .filter(|channel| !channel.is_empty())
+        .map(|channel| normalize_channel(channel.to_owned()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channel_lists_work() {
+        let data = encode_channel_list(["example:a", "example:b"]);
+        assert_eq!(data, b"example:a\0example:b");
+        assert_eq!(decode_channel_list(&data), vec!["example:a", "example:b"]);
+        assert_eq!(decode_channel_list(b"brand\0\0"), vec![BRAND]);
+    }
+    #[test]
+    fn dispatch_works() {
+        let mut channels = PluginChannels::default();
+        channels.register("example:echo", |context, data| {
+            context.reply("example:echo", data.to_vec());
+        });
+        channels.register("brand", |_, _| {});
+        assert!(channels.is_registered(BRAND));
+        assert_eq!(
+            channels.register_message(),
+            Some(PluginMessage::new(REGISTER, b"example:echo".to_vec()))
+        );
+
+        let client_channels = HashSet::new();
+        let mut context = PluginContext::new(0, ClientState::Play, &client_channels);
+        let message = PluginMessage::new("example:echo", vec![1, 2]);
+        assert!(channels.dispatch(&mut context, &message));
+        assert!(!channels.dispatch(&mut context, &PluginMessage::new("example:none", vec![])));
+        assert_eq!(context.into_responses(), vec![message]);
+
+        assert!(channels.unregister("example:echo"));
+        assert_eq!(channels.register_message(), None);
+    }
+}
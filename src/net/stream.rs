@@ -0,0 +1,183 @@
+use crate::protocol::encryption::*;
+use std::{
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio_util::bytes::{Buf, BytesMut};
+
+/// Stop accepting new writes once this many encrypted bytes are waiting to be written.
+const MAX_PENDING_WRITE: usize = 64 * 1024;
+
+/// A byte stream which can be switched to AES-CFB8 encryption part way through.
+///
+/// Encryption sits underneath the packet framing, so `PacketCodec`
+/// only ever sees plaintext. Reads are decrypted in place in the caller's buffer.
+/// Writes are encrypted once into an internal buffer, which is drained into the inner stream.
+#[derive(Debug)]
+pub struct CipherStream<S> {
+    inner: S,
+    cipher: Option<(Aes128Cfb8Encryptor, Aes128Cfb8Decryptor)>,
+    /// Encrypted bytes that have not been written to `inner` yet.
+    pending_write: BytesMut,
+}
+impl<S> CipherStream<S> {
+    pub fn new(inner: S) -> Self {
+        CipherStream {
+            inner,
+            cipher: None,
+            pending_write: BytesMut::new(),
+        }
+    }
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+    pub fn is_encrypted(&self) -> bool {
+        self.cipher.is_some()
+    }
+    /// Encrypt and decrypt all data from now on.
+    ///
+    /// Any data already read from the inner stream stays as-is,
+    /// so the caller is responsible for decrypting its own buffers.
+    pub fn set_cipher(&mut self, encryptor: Aes128Cfb8Encryptor, decryptor: Aes128Cfb8Decryptor) {
+        self.cipher = Some((encryptor, decryptor));
+    }
+}
+impl<S: AsyncWrite + Unpin> CipherStream<S> {
+    /// Write all of the pending encrypted data to the inner stream.
+    fn poll_drain(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.pending_write.is_empty() {
+            let n = ready!(Pin::new(&mut self.inner).poll_write(cx, &self.pending_write))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.pending_write.advance(n);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+impl<S: AsyncRead + Unpin> AsyncRead for CipherStream<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let already_filled = buf.filled().len();
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+
+        if let Some((_, decryptor)) = &mut this.cipher {
+            decrypt_in_place(decryptor, &mut buf.filled_mut()[already_filled..]);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+impl<S: AsyncWrite + Unpin> AsyncWrite for CipherStream<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.cipher.is_none() {
+            return Pin::new(&mut this.inner).poll_write(cx, buf);
+        }
+        if this.pending_write.len() >= MAX_PENDING_WRITE {
+            ready!(this.poll_drain(cx))?;
+        }
+
+        // Encrypting advances the cipher, so once bytes are encrypted
+        // they have to be buffered until the inner stream accepts them.
+        let start = this.pending_write.len();
+        this.pending_write.extend_from_slice(buf);
+        if let Some((encryptor, _)) = &mut this.cipher {
+            encrypt_in_place(encryptor, &mut this.pending_write[start..]);
+        }
+
+        // Opportunistically start writing, the rest gets written on flush.
+        if let Poll::Ready(Err(e)) = this.poll_drain(cx) {
+            return Poll::Ready(Err(e));
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_drain(cx))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_drain(cx))?;
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes::cipher::AsyncStreamCipher;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    const SECRET: [u8; 16] = *b"composition-aes!";
+
+    fn cipher() -> (Aes128Cfb8Encryptor, Aes128Cfb8Decryptor) {
+        (
+            Aes128Cfb8Encryptor::new(&SECRET.into(), &SECRET.into()),
+            Aes128Cfb8Decryptor::new(&SECRET.into(), &SECRET.into()),
+        )
+    }
+    fn plaintext() -> Vec<u8> {
+        (0..100_000u32).map(|i| (i * 31 % 251) as u8).collect()
+    }
+
+    #[test]
+    fn in_place_matches_whole_buffer_encryption() {
+        let mut expected = plaintext();
+        cipher().0.encrypt(&mut expected);
+
+        // Encrypt in uneven chunks, carrying the cipher state across calls.
+        let (mut encryptor, mut decryptor) = cipher();
+        let mut data = plaintext();
+        for chunk in data.chunks_mut(777) {
+            encrypt_in_place(&mut encryptor, chunk);
+        }
+        assert_eq!(data, expected);
+
+        for chunk in data.chunks_mut(1000) {
+            decrypt_in_place(&mut decryptor, chunk);
+        }
+        assert_eq!(data, plaintext());
+    }
+    #[tokio::test]
+    async fn stream_round_trip_works() {
+        let (client, server) = tokio::io::duplex(4096);
+        let mut client = CipherStream::new(client);
+        let mut server = CipherStream::new(server);
+
+        // Unencrypted data passes straight through.
+        client.write_all(b"hello").await.unwrap();
+        client.flush().await.unwrap();
+        let mut hello = [0u8; 5];
+        server.read_exact(&mut hello).await.unwrap();
+        assert_eq!(&hello, b"hello");
+
+        let (encryptor, decryptor) = cipher();
+        client.set_cipher(encryptor, decryptor);
+        let (encryptor, decryptor) = cipher();
+        server.set_cipher(encryptor, decryptor);
+
+        let writer = tokio::spawn(async move {
+            client.write_all(&plaintext()).await.unwrap();
+            client.flush().await.unwrap();
+            client
+        });
+        let mut received = vec![0u8; plaintext().len()];
+        server.read_exact(&mut received).await.unwrap();
+        assert_eq!(received, plaintext());
+        assert!(writer.await.unwrap().pending_write.is_empty());
+    }
+}
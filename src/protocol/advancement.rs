@@ -0,0 +1,146 @@
+use crate::protocol::{
+    inventory::slot::Slot,
+    parsing::{BufMut, IResult, Parsable},
+    text::TextComponent,
+    types::{Identifier, PrefixedOptional},
+};
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Advancement {
+    pub parent: PrefixedOptional<Identifier>,
+    /// Advancements without a display aren't shown in the advancements screen.
+    pub display: PrefixedOptional<AdvancementDisplay>,
+    /// Names of criteria, where one criterion from each list must be met.
+    pub requirements: Vec<Vec<String>>,
+    pub sends_telemetry_event: bool,
+}
+impl Parsable for Advancement {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, parent) = PrefixedOptional::parse(data)?;
+        let (data, display) = PrefixedOptional::parse(data)?;
+        let (data, requirements) = Vec::parse(data)?;
+        let (data, sends_telemetry_event) = bool::parse(data)?;
+        Ok((
+            data,
+            Advancement {
+                parent,
+                display,
+                requirements,
+                sends_telemetry_event,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.parent.serialize_into(buf);
+        self.display.serialize_into(buf);
+        self.requirements.serialize_into(buf);
+        self.sends_telemetry_event.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.parent.encoded_len()
+            + self.display.encoded_len()
+            + self.requirements.encoded_len()
+            + self.sends_telemetry_event.encoded_len()
+    }
+}
+
+/// How an advancement is shown, represented in the protocol with the
+/// booleans and whether there is a background packed into a flags integer.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AdvancementDisplay {
+    pub title: TextComponent,
+    pub description: TextComponent,
+    pub icon: Slot,
+    pub frame: AdvancementFrame,
+    /// The texture behind a root advancement's tab.
+    pub background: Option<Identifier>,
+    pub show_toast: bool,
+    pub hidden: bool,
+    pub x: f32,
+    pub y: f32,
+}
+impl AdvancementDisplay {
+    const HAS_BACKGROUND: i32 = 0x01;
+    const SHOW_TOAST: i32 = 0x02;
+    const HIDDEN: i32 = 0x04;
+
+    fn flags(&self) -> i32 {
+        let mut flags = 0;
+        if self.background.is_some() {
+            flags |= AdvancementDisplay::HAS_BACKGROUND;
+        }
+        if self.show_toast {
+            flags |= AdvancementDisplay::SHOW_TOAST;
+        }
+        if self.hidden {
+            flags |= AdvancementDisplay::HIDDEN;
+        }
+        flags
+    }
+}
+impl Parsable for AdvancementDisplay {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, title) = TextComponent::parse(data)?;
+        let (data, description) = TextComponent::parse(data)?;
+        let (data, icon) = Slot::parse(data)?;
+        let (data, frame) = AdvancementFrame::parse(data)?;
+        let (data, flags) = i32::parse(data)?;
+        let (data, background) = if flags & AdvancementDisplay::HAS_BACKGROUND != 0 {
+            nom::combinator::map(Identifier::parse, Some)(data)?
+        } else {
+            (data, None)
+        };
+        let (data, x) = f32::parse(data)?;
+        let (data, y) = f32::parse(data)?;
+        Ok((
+            data,
+            AdvancementDisplay {
+                title,
+                description,
+                icon,
+                frame,
+                background,
+                show_toast: flags & AdvancementDisplay::SHOW_TOAST != 0,
+                hidden: flags & AdvancementDisplay::HIDDEN != 0,
+                x,
+                y,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.title.serialize_into(buf);
+        self.description.serialize_into(buf);
+        self.icon.serialize_into(buf);
+        self.frame.serialize_into(buf);
+        self.flags().serialize_into(buf);
+        self.background.serialize_into(buf);
+        self.x.serialize_into(buf);
+        self.y.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.title.encoded_len()
+            + self.description.encoded_len()
+            + self.icon.encoded_len()
+            + self.frame.encoded_len()
+            + self.flags().encoded_len()
+            + self.background.encoded_len()
+            + self.x.encoded_len()
+            + self.y.encoded_len()
+    }
+}
+
+crate::varint_enum! {
+    /// The shape of an advancement's icon frame.
+    #[derive(Default)]
+    pub enum AdvancementFrame {
+        #[default]
+        Task = 0,
+        Challenge = 1,
+        Goal = 2,
+    }
+}
+
+/// Pairs of criterion name and when it was met, in milliseconds since the Unix epoch.
+pub type AdvancementProgress = Vec<(String, PrefixedOptional<i64>)>;
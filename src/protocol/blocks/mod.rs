@@ -16,15 +16,17 @@ impl BlockPosition {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
-pub enum BlockFace {
-    Bottom = 0,
-    Top = 1,
-    #[default]
-    North = 2,
-    South = 3,
-    West = 4,
-    East = 5,
+crate::varint_enum! {
+    #[derive(Default)]
+    pub enum BlockFace {
+        Bottom = 0,
+        Top = 1,
+        #[default]
+        North = 2,
+        South = 3,
+        West = 4,
+        East = 5,
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
@@ -0,0 +1,301 @@
+use crate::protocol::{
+    parsing::{BufMut, IResult, Parsable},
+    types::{Identifier, VarInt},
+};
+
+/// A node in the command graph, represented in the protocol with the kind
+/// and which optional fields are present packed into a flags byte.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CommandNode {
+    pub kind: CommandNodeKind,
+    /// Whether the command can run when input ends at this node.
+    pub executable: bool,
+    /// Indices of the child nodes in the graph.
+    pub children: Vec<VarInt>,
+    /// Index of the node that parsing continues from, such as for `/execute run`.
+    pub redirect: Option<VarInt>,
+}
+impl CommandNode {
+    const KIND_MASK: u8 = 0x03;
+    const EXECUTABLE: u8 = 0x04;
+    const HAS_REDIRECT: u8 = 0x08;
+    const HAS_SUGGESTIONS: u8 = 0x10;
+}
+impl Parsable for CommandNode {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, flags) = u8::parse(data)?;
+        let (data, children) = Vec::parse(data)?;
+        let (data, redirect) = if flags & CommandNode::HAS_REDIRECT != 0 {
+            nom::combinator::map(VarInt::parse, Some)(data)?
+        } else {
+            (data, None)
+        };
+        let (data, kind) = match flags & CommandNode::KIND_MASK {
+            0 => (data, CommandNodeKind::Root),
+            1 => {
+                let (data, name) = String::parse(data)?;
+                (data, CommandNodeKind::Literal { name })
+            }
+            2 => {
+                let (data, name) = String::parse(data)?;
+                let (data, parser) = ArgumentParser::parse(data)?;
+                let (data, suggestions) = if flags & CommandNode::HAS_SUGGESTIONS != 0 {
+                    nom::combinator::map(Identifier::parse, Some)(data)?
+                } else {
+                    (data, None)
+                };
+                (
+                    data,
+                    CommandNodeKind::Argument {
+                        name,
+                        parser,
+                        suggestions,
+                    },
+                )
+            }
+            _ => return nom::combinator::fail(data),
+        };
+        Ok((
+            data,
+            CommandNode {
+                kind,
+                executable: flags & CommandNode::EXECUTABLE != 0,
+                children,
+                redirect,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        let mut flags = match &self.kind {
+            CommandNodeKind::Root => 0,
+            CommandNodeKind::Literal { .. } => 1,
+            CommandNodeKind::Argument { suggestions, .. } => {
+                if suggestions.is_some() {
+                    2 | CommandNode::HAS_SUGGESTIONS
+                } else {
+                    2
+                }
+            }
+        };
+        if self.executable {
+            flags |= CommandNode::EXECUTABLE;
+        }
+        if self.redirect.is_some() {
+            flags |= CommandNode::HAS_REDIRECT;
+        }
+
+        flags.serialize_into(buf);
+        self.children.serialize_into(buf);
+        self.redirect.serialize_into(buf);
+        match &self.kind {
+            CommandNodeKind::Root => {}
+            CommandNodeKind::Literal { name } => name.serialize_into(buf),
+            CommandNodeKind::Argument {
+                name,
+                parser,
+                suggestions,
+            } => {
+                name.serialize_into(buf);
+                parser.serialize_into(buf);
+                suggestions.serialize_into(buf);
+            }
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        1 + self.children.encoded_len()
+            + self.redirect.encoded_len()
+            + match &self.kind {
+                CommandNodeKind::Root => 0,
+                CommandNodeKind::Literal { name } => name.encoded_len(),
+                CommandNodeKind::Argument {
+                    name,
+                    parser,
+                    suggestions,
+                } => name.encoded_len() + parser.encoded_len() + suggestions.encoded_len(),
+            }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum CommandNodeKind {
+    #[default]
+    Root,
+    Literal {
+        name: String,
+    },
+    Argument {
+        name: String,
+        parser: ArgumentParser,
+        /// Where the client asks for suggestions, such as `minecraft:ask_server`.
+        suggestions: Option<Identifier>,
+    },
+}
+
+/// Define `ArgumentParser` from its variants and their ids in the
+/// `minecraft:command_argument_type` registry.
+macro_rules! argument_parsers {
+    ($($(#[$attr:meta])* $name:ident $({ $($field:ident: $ty:ty),* $(,)? })? = $id:literal,)*) => {
+        /// How an argument node is parsed, represented in the protocol as a
+        /// `VarInt` of the parser followed by its properties.
+        #[derive(Debug, Clone, PartialEq, Default)]
+        pub enum ArgumentParser {
+            $($(#[$attr])* $name $({ $($field: $ty),* })?,)*
+        }
+        impl ArgumentParser {
+            pub fn id(&self) -> VarInt {
+                match self {
+                    $(ArgumentParser::$name { .. } => VarInt::from($id),)*
+                }
+            }
+        }
+        impl Parsable for ArgumentParser {
+            #[tracing::instrument]
+            fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+                let (data, id) = VarInt::parse(data)?;
+                match *id {
+                    $($id => {
+                        $($(let (data, $field) = <$ty as Parsable>::parse(data)?;)*)?
+                        Ok((data, ArgumentParser::$name $({ $($field),* })?))
+                    })*
+                    _ => nom::combinator::fail(data),
+                }
+            }
+            fn serialize_into(&self, buf: &mut impl BufMut) {
+                self.id().serialize_into(buf);
+                match self {
+                    $(ArgumentParser::$name $({ $($field),* })? => {
+                        $($($field.serialize_into(buf);)*)?
+                    })*
+                }
+            }
+            fn encoded_len(&self) -> usize {
+                self.id().encoded_len()
+                    + match self {
+                        $(ArgumentParser::$name $({ $($field),* })? => {
+                            0 $($(+ $field.encoded_len())*)?
+                        })*
+                    }
+            }
+        }
+    };
+}
+
+argument_parsers! {
+    #[default]
+    Bool = 0,
+    Float { range: NumberRange<f32> } = 1,
+    Double { range: NumberRange<f64> } = 2,
+    Integer { range: NumberRange<i32> } = 3,
+    Long { range: NumberRange<i64> } = 4,
+    String { kind: StringKind } = 5,
+    /// 0x01: only one entity, 0x02: only players.
+    Entity { flags: u8 } = 6,
+    GameProfile = 7,
+    BlockPos = 8,
+    ColumnPos = 9,
+    Vec3 = 10,
+    Vec2 = 11,
+    BlockState = 12,
+    BlockPredicate = 13,
+    ItemStack = 14,
+    ItemPredicate = 15,
+    Color = 16,
+    Component = 17,
+    Style = 18,
+    Message = 19,
+    NbtCompoundTag = 20,
+    NbtTag = 21,
+    NbtPath = 22,
+    Objective = 23,
+    ObjectiveCriteria = 24,
+    Operation = 25,
+    Particle = 26,
+    Angle = 27,
+    Rotation = 28,
+    ScoreboardSlot = 29,
+    /// 0x01: allow multiple.
+    ScoreHolder { flags: u8 } = 30,
+    Swizzle = 31,
+    Team = 32,
+    ItemSlot = 33,
+    ItemSlots = 34,
+    ResourceLocation = 35,
+    Function = 36,
+    EntityAnchor = 37,
+    IntRange = 38,
+    FloatRange = 39,
+    Dimension = 40,
+    Gamemode = 41,
+    /// The minimum duration in ticks.
+    Time { min: i32 } = 42,
+    ResourceOrTag { registry: Identifier } = 43,
+    ResourceOrTagKey { registry: Identifier } = 44,
+    Resource { registry: Identifier } = 45,
+    ResourceKey { registry: Identifier } = 46,
+    TemplateMirror = 47,
+    TemplateRotation = 48,
+    Heightmap = 49,
+    LootTable = 50,
+    LootPredicate = 51,
+    LootModifier = 52,
+    Uuid = 53,
+}
+
+/// The bounds of a number argument, represented in the protocol as
+/// flags for which bounds are present followed by them.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct NumberRange<T> {
+    pub min: Option<T>,
+    pub max: Option<T>,
+}
+impl<T> NumberRange<T> {
+    const HAS_MIN: u8 = 0x01;
+    const HAS_MAX: u8 = 0x02;
+}
+impl<T: Parsable + std::fmt::Debug> Parsable for NumberRange<T> {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, flags) = u8::parse(data)?;
+        let (data, min) = if flags & Self::HAS_MIN != 0 {
+            nom::combinator::map(T::parse, Some)(data)?
+        } else {
+            (data, None)
+        };
+        let (data, max) = if flags & Self::HAS_MAX != 0 {
+            nom::combinator::map(T::parse, Some)(data)?
+        } else {
+            (data, None)
+        };
+        Ok((data, NumberRange { min, max }))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        let mut flags = 0;
+        if self.min.is_some() {
+            flags |= Self::HAS_MIN;
+        }
+        if self.max.is_some() {
+            flags |= Self::HAS_MAX;
+        }
+        flags.serialize_into(buf);
+        self.min.serialize_into(buf);
+        self.max.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        1 + self.min.encoded_len() + self.max.encoded_len()
+    }
+}
+
+crate::varint_enum! {
+    /// How much input a string argument takes.
+    #[derive(Default)]
+    pub enum StringKind {
+        /// A single word.
+        #[default]
+        SingleWord = 0,
+        /// A single word, or a quoted string that may contain spaces.
+        QuotablePhrase = 1,
+        /// The rest of the input.
+        GreedyPhrase = 2,
+    }
+}
@@ -1,16 +1,67 @@
 use der::Encode;
+use sha1::{Digest, Sha1};
 use spki::{DecodePublicKey, SubjectPublicKeyInfo};
 
 pub use crate::protocol::parsing::Parsable;
-pub use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
-pub use generic_array::{
-    typenum::{UInt, UTerm, B1},
-    GenericArray,
-};
+pub use aes::cipher::{inout::InOutBuf, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
 pub use rsa::{Pkcs1v15Encrypt, RsaPrivateKey, RsaPublicKey};
 pub type Aes128Cfb8Encryptor = cfb8::Encryptor<aes::Aes128>;
 pub type Aes128Cfb8Decryptor = cfb8::Decryptor<aes::Aes128>;
-pub type GenericCFB8BlockArray = GenericArray<u8, UInt<UTerm, B1>>;
+
+/// Encrypt `data` in place, advancing the cipher's state.
+///
+/// CFB8 has a block size of one byte, so any length of data can be encrypted
+/// and the cipher can be used as a stream cipher across multiple calls.
+pub fn encrypt_in_place(encryptor: &mut Aes128Cfb8Encryptor, data: &mut [u8]) {
+    let (blocks, _) = InOutBuf::from(data).into_chunks();
+    encryptor.encrypt_blocks_inout_mut(blocks);
+}
+/// Decrypt `data` in place, advancing the cipher's state.
+///
+/// See `encrypt_in_place`.
+pub fn decrypt_in_place(decryptor: &mut Aes128Cfb8Decryptor, data: &mut [u8]) {
+    let (blocks, _) = InOutBuf::from(data).into_chunks();
+    decryptor.decrypt_blocks_inout_mut(blocks);
+}
+
+/// Compute the server hash sent to the session server when authenticating a player.
+///
+/// This is the SHA-1 digest of the server id, shared secret, and DER-encoded public key,
+/// formatted with `minecraft_hex_digest`.
+pub fn server_hash(server_id: &str, shared_secret: &[u8], public_key: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(server_id.as_bytes());
+    hasher.update(shared_secret);
+    hasher.update(public_key);
+    minecraft_hex_digest(&hasher.finalize())
+}
+/// Format a digest the way Java's `new BigInteger(digest).toString(16)` does.
+///
+/// The digest is treated as a signed two's complement number,
+/// so negative digests get a leading `-` and leading zeros are stripped.
+pub fn minecraft_hex_digest(digest: &[u8]) -> String {
+    let negative = digest.first().is_some_and(|b| b & 0x80 != 0);
+    let mut digest = digest.to_vec();
+    if negative {
+        // Two's complement negation: invert every bit and add one.
+        let mut carry = true;
+        for byte in digest.iter_mut().rev() {
+            let (sum, overflowed) = (!*byte).overflowing_add(carry as u8);
+            *byte = sum;
+            carry = overflowed;
+        }
+    }
+    let hex = digest
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    let hex = hex.trim_start_matches('0');
+    match (negative, hex.is_empty()) {
+        (_, true) => "0".to_owned(),
+        (true, false) => format!("-{hex}"),
+        (false, false) => hex.to_owned(),
+    }
+}
 
 impl Parsable for RsaPublicKey {
     fn parse(data: &[u8]) -> nom::IResult<&[u8], Self> {
@@ -23,3 +74,19 @@ impl Parsable for RsaPublicKey {
             .unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minecraft_hex_digest_works() {
+        for (name, expected) in [
+            ("Notch", "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48"),
+            ("jeb_", "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1"),
+            ("simon", "88e16a1019277b15d58faf0541e11910eb756f6"),
+        ] {
+            assert_eq!(server_hash(name, &[], &[]), expected);
+        }
+    }
+}
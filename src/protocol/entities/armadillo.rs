@@ -0,0 +1,15 @@
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Armadillo {
+    pub state: ArmadilloState,
+}
+
+crate::varint_enum! {
+    #[derive(Default)]
+    pub enum ArmadilloState {
+        #[default]
+        Idle = 0,
+        Rolling = 1,
+        Scared = 2,
+        Unrolling = 3,
+    }
+}
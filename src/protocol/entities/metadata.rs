@@ -1,9 +1,57 @@
+use super::{
+    armadillo::ArmadilloState,
+    particle::Particle,
+    sniffer::SnifferState,
+    villager::{VillagerBiome, VillagerProfession},
+};
 use crate::protocol::{
     blocks::BlockFace,
-    types::{Chat, Position, Uuid, VarInt},
+    inventory::slot::Slot,
+    nbt::Tag,
+    parsing::{BufMut, IResult, Parsable},
+    text::TextComponent,
+    types::{
+        IdOr, Identifier, OptionalVarInt, PaintingVariant, Position, PrefixedOptional, Uuid,
+        VarInt, VarLong,
+    },
 };
 
-pub type EntityMetadata = Vec<EntityMetadataEntry>;
+/// Changes to an entity's metadata, represented in the protocol
+/// as the entries followed by an index of `0xFF`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct EntityMetadata(pub Vec<EntityMetadataEntry>);
+impl EntityMetadata {
+    const END: u8 = 0xFF;
+}
+impl Parsable for EntityMetadata {
+    #[tracing::instrument]
+    fn parse(mut data: &[u8]) -> IResult<&[u8], Self> {
+        let mut entries = vec![];
+        loop {
+            let (d, index) = u8::parse(data)?;
+            if index == EntityMetadata::END {
+                return Ok((d, EntityMetadata(entries)));
+            }
+            let (d, kind) = EntityMetadataEntryKind::parse(d)?;
+            data = d;
+            entries.push(EntityMetadataEntry { index, kind });
+        }
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        for entry in &self.0 {
+            entry.index.serialize_into(buf);
+            entry.kind.serialize_into(buf);
+        }
+        EntityMetadata::END.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.0
+            .iter()
+            .map(|entry| entry.index.encoded_len() + entry.kind.encoded_len())
+            .sum::<usize>()
+            + EntityMetadata::END.encoded_len()
+    }
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct EntityMetadataEntry {
@@ -11,81 +59,307 @@ pub struct EntityMetadataEntry {
     pub kind: EntityMetadataEntryKind,
 }
 
-#[repr(u8)]
+/// A metadata value, represented in the protocol as a `VarInt` of its
+/// type followed by the value.
 #[derive(Debug, Clone, PartialEq)]
 pub enum EntityMetadataEntryKind {
-    Byte(u8) = 0,
-    VarInt(VarInt) = 1,
-    // TODO: Add VarLong type
-    VarLong(VarInt) = 2,
-    Float(f32) = 3,
-    String(String) = 4,
-    Chat(Chat) = 5,
-    OptionalChat(Option<Chat>) = 6,
-    // TODO: Add Slot type
-    Slot(()) = 7,
-    Boolean(bool) = 8,
+    Byte(u8),
+    VarInt(VarInt),
+    VarLong(VarLong),
+    Float(f32),
+    String(String),
+    Chat(TextComponent),
+    OptionalChat(PrefixedOptional<TextComponent>),
+    Slot(Slot),
+    Boolean(bool),
     Rotation {
         x: f32,
         y: f32,
         z: f32,
-    } = 9,
-    Position(Position) = 10,
-    OptionalPosition(Option<Position>) = 11,
-    Direction(BlockFace) = 12,
-    OptionalUuid(Uuid) = 13,
-    BlockId(VarInt) = 14,
-    // 0 or None means air
-    OptionalBlockId(Option<VarInt>) = 15,
-    // TODO: Add NBT type
-    Nbt(()) = 16,
-    // TODO: Add Particle type
-    Particle(()) = 17,
+    },
+    Position(Position),
+    OptionalPosition(PrefixedOptional<Position>),
+    Direction(BlockFace),
+    OptionalUuid(PrefixedOptional<Uuid>),
+    BlockId(VarInt),
+    /// Represented in the protocol as a block state id, where 0 (air) means none.
+    OptionalBlockId(Option<VarInt>),
+    Nbt(Tag),
+    Particle(Particle),
+    Particles(Vec<Particle>),
     VillagerData {
-        biome: super::villager::VillagerBiome,
-        profession: super::villager::VillagerProfession,
+        biome: VillagerBiome,
+        profession: VillagerProfession,
         level: VarInt,
-    } = 18,
-    // Used for entity ids
-    OptionalVarInt(VarInt) = 19,
-    Pose(EntityPose) = 20,
-    CatVariant(super::cat::CatVariant) = 21,
-    FrogVariant(super::frog::FrogVariant) = 22,
-    // TODO: Add dimension id
-    OptionalGlobalPosition((), Position) = 23,
-    // TODO: Add painting variant
-    PaintingVariant(()) = 24,
-    #[cfg(feature = "update_1_20")]
-    SnifferState(super::sniffer::SnifferState) = 25,
+    },
+    OptionalVarInt(OptionalVarInt),
+    Pose(EntityPose),
+    CatVariant(VarInt),
+    CowVariant(VarInt),
+    WolfVariant(VarInt),
+    WolfSoundVariant(VarInt),
+    FrogVariant(VarInt),
+    PigVariant(VarInt),
+    ChickenVariant(VarInt),
+    /// Pair of dimension name and location.
+    OptionalGlobalPosition(PrefixedOptional<(Identifier, Position)>),
+    PaintingVariant(IdOr<Box<PaintingVariant>>),
+    SnifferState(SnifferState),
+    ArmadilloState(ArmadilloState),
     Vector3 {
         x: f32,
         y: f32,
         z: f32,
-    } = 26,
+    },
     Quaternion {
         x: f32,
         y: f32,
         z: f32,
         w: f32,
-    } = 27,
+    },
+}
+impl EntityMetadataEntryKind {
+    /// The id of the value's type in the protocol.
+    pub fn id(&self) -> VarInt {
+        use EntityMetadataEntryKind as Kind;
+        VarInt::from(match self {
+            Kind::Byte(_) => 0,
+            Kind::VarInt(_) => 1,
+            Kind::VarLong(_) => 2,
+            Kind::Float(_) => 3,
+            Kind::String(_) => 4,
+            Kind::Chat(_) => 5,
+            Kind::OptionalChat(_) => 6,
+            Kind::Slot(_) => 7,
+            Kind::Boolean(_) => 8,
+            Kind::Rotation { .. } => 9,
+            Kind::Position(_) => 10,
+            Kind::OptionalPosition(_) => 11,
+            Kind::Direction(_) => 12,
+            Kind::OptionalUuid(_) => 13,
+            Kind::BlockId(_) => 14,
+            Kind::OptionalBlockId(_) => 15,
+            Kind::Nbt(_) => 16,
+            Kind::Particle(_) => 17,
+            Kind::Particles(_) => 18,
+            Kind::VillagerData { .. } => 19,
+            Kind::OptionalVarInt(_) => 20,
+            Kind::Pose(_) => 21,
+            Kind::CatVariant(_) => 22,
+            Kind::CowVariant(_) => 23,
+            Kind::WolfVariant(_) => 24,
+            Kind::WolfSoundVariant(_) => 25,
+            Kind::FrogVariant(_) => 26,
+            Kind::PigVariant(_) => 27,
+            Kind::ChickenVariant(_) => 28,
+            Kind::OptionalGlobalPosition(_) => 29,
+            Kind::PaintingVariant(_) => 30,
+            Kind::SnifferState(_) => 31,
+            Kind::ArmadilloState(_) => 32,
+            Kind::Vector3 { .. } => 33,
+            Kind::Quaternion { .. } => 34,
+        })
+    }
+}
+impl Parsable for EntityMetadataEntryKind {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        use nom::combinator::map;
+        use EntityMetadataEntryKind as Kind;
+
+        let (data, kind) = VarInt::parse(data)?;
+        match *kind {
+            0 => map(u8::parse, Kind::Byte)(data),
+            1 => map(VarInt::parse, Kind::VarInt)(data),
+            2 => map(VarLong::parse, Kind::VarLong)(data),
+            3 => map(f32::parse, Kind::Float)(data),
+            4 => map(String::parse, Kind::String)(data),
+            5 => map(TextComponent::parse, Kind::Chat)(data),
+            6 => map(PrefixedOptional::parse, Kind::OptionalChat)(data),
+            7 => map(Slot::parse, Kind::Slot)(data),
+            8 => map(bool::parse, Kind::Boolean)(data),
+            9 => map(<(f32, f32, f32)>::parse, |(x, y, z)| Kind::Rotation {
+                x,
+                y,
+                z,
+            })(data),
+            10 => map(Position::parse, Kind::Position)(data),
+            11 => map(PrefixedOptional::parse, Kind::OptionalPosition)(data),
+            12 => map(BlockFace::parse, Kind::Direction)(data),
+            13 => map(PrefixedOptional::parse, Kind::OptionalUuid)(data),
+            14 => map(VarInt::parse, Kind::BlockId)(data),
+            15 => map(VarInt::parse, |id| {
+                Kind::OptionalBlockId((*id != 0).then_some(id))
+            })(data),
+            16 => map(Tag::parse, Kind::Nbt)(data),
+            17 => map(Particle::parse, Kind::Particle)(data),
+            18 => map(Vec::parse, Kind::Particles)(data),
+            19 => {
+                let (data, biome) = VillagerBiome::parse(data)?;
+                let (data, profession) = VillagerProfession::parse(data)?;
+                let (data, level) = VarInt::parse(data)?;
+                Ok((
+                    data,
+                    Kind::VillagerData {
+                        biome,
+                        profession,
+                        level,
+                    },
+                ))
+            }
+            20 => map(OptionalVarInt::parse, Kind::OptionalVarInt)(data),
+            21 => map(EntityPose::parse, Kind::Pose)(data),
+            22 => map(VarInt::parse, Kind::CatVariant)(data),
+            23 => map(VarInt::parse, Kind::CowVariant)(data),
+            24 => map(VarInt::parse, Kind::WolfVariant)(data),
+            25 => map(VarInt::parse, Kind::WolfSoundVariant)(data),
+            26 => map(VarInt::parse, Kind::FrogVariant)(data),
+            27 => map(VarInt::parse, Kind::PigVariant)(data),
+            28 => map(VarInt::parse, Kind::ChickenVariant)(data),
+            29 => map(PrefixedOptional::parse, Kind::OptionalGlobalPosition)(data),
+            30 => map(IdOr::parse, Kind::PaintingVariant)(data),
+            31 => map(SnifferState::parse, Kind::SnifferState)(data),
+            32 => map(ArmadilloState::parse, Kind::ArmadilloState)(data),
+            33 => map(<(f32, f32, f32)>::parse, |(x, y, z)| Kind::Vector3 {
+                x,
+                y,
+                z,
+            })(data),
+            34 => {
+                let (data, (x, y, z)) = <(f32, f32, f32)>::parse(data)?;
+                let (data, w) = f32::parse(data)?;
+                Ok((data, Kind::Quaternion { x, y, z, w }))
+            }
+            _ => nom::combinator::fail(data),
+        }
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        use EntityMetadataEntryKind as Kind;
+
+        self.id().serialize_into(buf);
+        match self {
+            Kind::Byte(value) => value.serialize_into(buf),
+            Kind::VarInt(value)
+            | Kind::BlockId(value)
+            | Kind::CatVariant(value)
+            | Kind::CowVariant(value)
+            | Kind::WolfVariant(value)
+            | Kind::WolfSoundVariant(value)
+            | Kind::FrogVariant(value)
+            | Kind::PigVariant(value)
+            | Kind::ChickenVariant(value) => value.serialize_into(buf),
+            Kind::VarLong(value) => value.serialize_into(buf),
+            Kind::Float(value) => value.serialize_into(buf),
+            Kind::String(value) => value.serialize_into(buf),
+            Kind::Chat(value) => value.serialize_into(buf),
+            Kind::OptionalChat(value) => value.serialize_into(buf),
+            Kind::Slot(value) => value.serialize_into(buf),
+            Kind::Boolean(value) => value.serialize_into(buf),
+            Kind::Rotation { x, y, z } | Kind::Vector3 { x, y, z } => {
+                (*x, *y, *z).serialize_into(buf)
+            }
+            Kind::Position(value) => value.serialize_into(buf),
+            Kind::OptionalPosition(value) => value.serialize_into(buf),
+            Kind::Direction(value) => value.serialize_into(buf),
+            Kind::OptionalUuid(value) => value.serialize_into(buf),
+            Kind::OptionalBlockId(value) => {
+                value.unwrap_or_default().serialize_into(buf);
+            }
+            Kind::Nbt(value) => value.serialize_into(buf),
+            Kind::Particle(value) => value.serialize_into(buf),
+            Kind::Particles(value) => value.serialize_into(buf),
+            Kind::VillagerData {
+                biome,
+                profession,
+                level,
+            } => {
+                biome.serialize_into(buf);
+                profession.serialize_into(buf);
+                level.serialize_into(buf);
+            }
+            Kind::OptionalVarInt(value) => value.serialize_into(buf),
+            Kind::Pose(value) => value.serialize_into(buf),
+            Kind::OptionalGlobalPosition(value) => value.serialize_into(buf),
+            Kind::PaintingVariant(value) => value.serialize_into(buf),
+            Kind::SnifferState(value) => value.serialize_into(buf),
+            Kind::ArmadilloState(value) => value.serialize_into(buf),
+            Kind::Quaternion { x, y, z, w } => {
+                (*x, *y, *z).serialize_into(buf);
+                w.serialize_into(buf);
+            }
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        use EntityMetadataEntryKind as Kind;
+
+        self.id().encoded_len()
+            + match self {
+                Kind::Byte(value) => value.encoded_len(),
+                Kind::VarInt(value)
+                | Kind::BlockId(value)
+                | Kind::CatVariant(value)
+                | Kind::CowVariant(value)
+                | Kind::WolfVariant(value)
+                | Kind::WolfSoundVariant(value)
+                | Kind::FrogVariant(value)
+                | Kind::PigVariant(value)
+                | Kind::ChickenVariant(value) => value.encoded_len(),
+                Kind::VarLong(value) => value.encoded_len(),
+                Kind::Float(value) => value.encoded_len(),
+                Kind::String(value) => value.encoded_len(),
+                Kind::Chat(value) => value.encoded_len(),
+                Kind::OptionalChat(value) => value.encoded_len(),
+                Kind::Slot(value) => value.encoded_len(),
+                Kind::Boolean(value) => value.encoded_len(),
+                Kind::Rotation { x, y, z } | Kind::Vector3 { x, y, z } => {
+                    (*x, *y, *z).encoded_len()
+                }
+                Kind::Position(value) => value.encoded_len(),
+                Kind::OptionalPosition(value) => value.encoded_len(),
+                Kind::Direction(value) => value.encoded_len(),
+                Kind::OptionalUuid(value) => value.encoded_len(),
+                Kind::OptionalBlockId(value) => value.unwrap_or_default().encoded_len(),
+                Kind::Nbt(value) => value.encoded_len(),
+                Kind::Particle(value) => value.encoded_len(),
+                Kind::Particles(value) => value.encoded_len(),
+                Kind::VillagerData {
+                    biome,
+                    profession,
+                    level,
+                } => biome.encoded_len() + profession.encoded_len() + level.encoded_len(),
+                Kind::OptionalVarInt(value) => value.encoded_len(),
+                Kind::Pose(value) => value.encoded_len(),
+                Kind::OptionalGlobalPosition(value) => value.encoded_len(),
+                Kind::PaintingVariant(value) => value.encoded_len(),
+                Kind::SnifferState(value) => value.encoded_len(),
+                Kind::ArmadilloState(value) => value.encoded_len(),
+                Kind::Quaternion { x, y, z, w } => (*x, *y, *z).encoded_len() + w.encoded_len(),
+            }
+    }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
-pub enum EntityPose {
-    #[default]
-    Standing = 0,
-    FallFlying = 1,
-    Sleeping = 2,
-    Swimming = 3,
-    SpinAttack = 4,
-    Sneaking = 5,
-    LongJumping = 6,
-    Dying = 7,
-    Croaking = 8,
-    UsingTongue = 9,
-    Sitting = 10,
-    Roaring = 11,
-    Sniffing = 12,
-    Emerging = 13,
-    Digging = 14,
+crate::varint_enum! {
+    #[derive(Default)]
+    pub enum EntityPose {
+        #[default]
+        Standing = 0,
+        FallFlying = 1,
+        Sleeping = 2,
+        Swimming = 3,
+        SpinAttack = 4,
+        Sneaking = 5,
+        LongJumping = 6,
+        Dying = 7,
+        Croaking = 8,
+        UsingTongue = 9,
+        Sitting = 10,
+        Roaring = 11,
+        Sniffing = 12,
+        Emerging = 13,
+        Digging = 14,
+        Sliding = 15,
+        Shooting = 16,
+        Inhaling = 17,
+    }
 }
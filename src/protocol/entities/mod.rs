@@ -1,16 +1,17 @@
+pub mod armadillo;
 pub mod cat;
 pub mod frog;
 pub mod metadata;
 pub mod particle;
 pub mod player;
-#[cfg(feature = "update_1_20")]
 pub mod sniffer;
 pub mod villager;
 
-use crate::protocol::parsing::{IResult, Parsable};
+use crate::protocol::parsing::{BufMut, IResult, Parsable};
 use crate::protocol::{
     blocks::BlockPosition,
-    types::{Chat, Uuid, VarInt},
+    text::TextComponent,
+    types::{Angle, Uuid, VarInt},
 };
 
 pub type EntityId = VarInt;
@@ -30,34 +31,34 @@ impl Parsable for EntityPosition {
         let (data, z) = f64::parse(data)?;
         Ok((data, EntityPosition { x, y, z }))
     }
-    #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        let mut output = vec![];
-        output.extend(self.x.serialize());
-        output.extend(self.y.serialize());
-        output.extend(self.z.serialize());
-        output
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.x.serialize_into(buf);
+        self.y.serialize_into(buf);
+        self.z.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.x.encoded_len() + self.y.encoded_len() + self.z.encoded_len()
     }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub struct EntityRotation {
-    pub pitch: u8,
-    pub yaw: u8,
+    pub pitch: Angle,
+    pub yaw: Angle,
 }
 impl Parsable for EntityRotation {
     #[tracing::instrument]
     fn parse(data: &[u8]) -> IResult<&[u8], Self> {
-        let (data, pitch) = u8::parse(data)?;
-        let (data, yaw) = u8::parse(data)?;
+        let (data, pitch) = Angle::parse(data)?;
+        let (data, yaw) = Angle::parse(data)?;
         Ok((data, EntityRotation { pitch, yaw }))
     }
-    #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        let mut output = vec![];
-        output.extend(self.pitch.serialize());
-        output.extend(self.yaw.serialize());
-        output
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.pitch.serialize_into(buf);
+        self.yaw.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.pitch.encoded_len() + self.yaw.encoded_len()
     }
 }
 
@@ -75,13 +76,148 @@ impl Parsable for EntityVelocity {
         let (data, z) = i16::parse(data)?;
         Ok((data, EntityVelocity { x, y, z }))
     }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.x.serialize_into(buf);
+        self.y.serialize_into(buf);
+        self.z.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.x.encoded_len() + self.y.encoded_len() + self.z.encoded_len()
+    }
+}
+
+/// One step of a minecart's movement along a track.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct MinecartStep {
+    pub position: EntityPosition,
+    pub velocity: (f64, f64, f64),
+    pub yaw: Angle,
+    pub pitch: Angle,
+    pub weight: f32,
+}
+impl Parsable for MinecartStep {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, position) = EntityPosition::parse(data)?;
+        let (data, velocity) = <(f64, f64, f64)>::parse(data)?;
+        let (data, yaw) = Angle::parse(data)?;
+        let (data, pitch) = Angle::parse(data)?;
+        let (data, weight) = f32::parse(data)?;
+        Ok((
+            data,
+            MinecartStep {
+                position,
+                velocity,
+                yaw,
+                pitch,
+                weight,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.position.serialize_into(buf);
+        self.velocity.serialize_into(buf);
+        self.yaw.serialize_into(buf);
+        self.pitch.serialize_into(buf);
+        self.weight.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.position.encoded_len()
+            + self.velocity.encoded_len()
+            + self.yaw.encoded_len()
+            + self.pitch.encoded_len()
+            + self.weight.encoded_len()
+    }
+}
+
+/// How a player interacted with an entity.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum InteractAction {
+    Interact {
+        hand: VarInt,
+    },
+    Attack,
+    InteractAt {
+        target_x: f32,
+        target_y: f32,
+        target_z: f32,
+        hand: VarInt,
+    },
+}
+impl Default for InteractAction {
+    fn default() -> Self {
+        InteractAction::Interact {
+            hand: VarInt::default(),
+        }
+    }
+}
+impl Parsable for InteractAction {
     #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        let mut output = vec![];
-        output.extend(self.x.serialize());
-        output.extend(self.y.serialize());
-        output.extend(self.z.serialize());
-        output
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, kind) = VarInt::parse(data)?;
+        match *kind {
+            0 => {
+                let (data, hand) = VarInt::parse(data)?;
+                Ok((data, InteractAction::Interact { hand }))
+            }
+            1 => Ok((data, InteractAction::Attack)),
+            2 => {
+                let (data, target_x) = f32::parse(data)?;
+                let (data, target_y) = f32::parse(data)?;
+                let (data, target_z) = f32::parse(data)?;
+                let (data, hand) = VarInt::parse(data)?;
+                Ok((
+                    data,
+                    InteractAction::InteractAt {
+                        target_x,
+                        target_y,
+                        target_z,
+                        hand,
+                    },
+                ))
+            }
+            _ => nom::combinator::fail(data),
+        }
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        match self {
+            InteractAction::Interact { hand } => {
+                VarInt::from(0).serialize_into(buf);
+                hand.serialize_into(buf);
+            }
+            InteractAction::Attack => {
+                VarInt::from(1).serialize_into(buf);
+            }
+            InteractAction::InteractAt {
+                target_x,
+                target_y,
+                target_z,
+                hand,
+            } => {
+                VarInt::from(2).serialize_into(buf);
+                target_x.serialize_into(buf);
+                target_y.serialize_into(buf);
+                target_z.serialize_into(buf);
+                hand.serialize_into(buf);
+            }
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        1 + match self {
+            InteractAction::Interact { hand } => hand.encoded_len(),
+            InteractAction::Attack => 0,
+            InteractAction::InteractAt {
+                target_x,
+                target_y,
+                target_z,
+                hand,
+            } => {
+                target_x.encoded_len()
+                    + target_y.encoded_len()
+                    + target_z.encoded_len()
+                    + hand.encoded_len()
+            }
+        }
     }
 }
 
@@ -96,7 +232,7 @@ pub struct Entity {
     pub is_invisible: bool,
     pub is_glowing: bool,
     pub is_elytra_flying: bool,
-    pub custom_name: Option<Chat>,
+    pub custom_name: Option<TextComponent>,
 }
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct LivingEntity {
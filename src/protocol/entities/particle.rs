@@ -1,132 +1,220 @@
 use super::EntityId;
-use crate::protocol::types::{Position, VarInt};
+use crate::protocol::{
+    inventory::slot::Slot,
+    parsing::{BufMut, IResult, Parsable},
+    types::{Position, VarInt},
+};
 
-#[derive(Debug, Copy, Clone, PartialEq)]
-pub struct Particle {
-    kind: ParticleKind,
+/// Define `Particle` from its variants and their ids in the `minecraft:particle_type` registry.
+macro_rules! particles {
+    ($($(#[$attr:meta])* $name:ident $({ $($field:ident: $ty:ty),* $(,)? })? = $id:literal,)*) => {
+        /// A particle and its options, represented in the protocol as a `VarInt`
+        /// of the particle type followed by the options.
+        #[derive(Debug, Clone, PartialEq, Default)]
+        pub enum Particle {
+            $($(#[$attr])* $name $({ $($field: $ty),* })?,)*
+        }
+        impl Particle {
+            pub fn id(&self) -> VarInt {
+                match self {
+                    $(Particle::$name { .. } => VarInt::from($id),)*
+                }
+            }
+        }
+        impl Parsable for Particle {
+            #[tracing::instrument]
+            fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+                let (data, id) = VarInt::parse(data)?;
+                match *id {
+                    $($id => {
+                        $($(let (data, $field) = <$ty as Parsable>::parse(data)?;)*)?
+                        Ok((data, Particle::$name $({ $($field),* })?))
+                    })*
+                    _ => nom::combinator::fail(data),
+                }
+            }
+            fn serialize_into(&self, buf: &mut impl BufMut) {
+                self.id().serialize_into(buf);
+                match self {
+                    $(Particle::$name $({ $($field),* })? => {
+                        $($($field.serialize_into(buf);)*)?
+                    })*
+                }
+            }
+            fn encoded_len(&self) -> usize {
+                self.id().encoded_len()
+                    + match self {
+                        $(Particle::$name $({ $($field),* })? => {
+                            0 $($(+ $field.encoded_len())*)?
+                        })*
+                    }
+            }
+        }
+    };
 }
 
-#[repr(u8)]
-#[derive(Debug, Copy, Clone, PartialEq)]
-pub enum ParticleKind {
-    AmbientEntityEffect = 0,
-    AngryVillager = 1,
-    // TODO: Add the block state
-    Block(()) = 2,
-    // TODO: Add the block state
-    BlockMarker(()) = 3,
-    Bubble = 4,
-    Cloud = 5,
-    Crit = 6,
-    DamageIndicator = 7,
-    DragonBreath = 8,
-    DrippingLava = 9,
-    FallingLava = 10,
-    LandingLava = 11,
-    DrippingWater = 12,
-    FallingWater = 13,
-    Dust {
-        rgb: [f32; 3],
-        scale: f32,
-    } = 14,
-    DustColorTransition {
-        from_rgb: [f32; 3],
-        scale: f32,
-        to_rgb: [f32; 3],
-    } = 15,
-    Effect = 16,
-    ElderGuardian = 17,
-    EnchantedHit = 18,
-    Enchant = 19,
-    EndRod = 20,
-    EntityEffect = 21,
-    ExplosionEmitter = 22,
-    Explosion = 23,
-    SonicBoom = 24,
-    // TODO: Add the block state
-    FallingDust(()) = 25,
-    Firework = 26,
-    Fishing = 27,
-    Flame = 28,
-    DrippingCherryLeaves = 29,
-    FallingCherryLeaves = 30,
-    LandingCherryLeaves = 31,
-    SkulkSoul = 32,
-    SkulkCharge {
-        roll: f32,
-    } = 33,
-    SkulkChargePop = 34,
-    SoulFireFlame = 35,
-    Soul = 36,
-    Flash = 37,
-    HappyVillager = 38,
-    Composter = 39,
-    Heart = 40,
-    InstantEffect = 41,
-    // TODO: Add the slot
-    Item(()) = 42,
-    Vibration {
-        source: VibrationParticleSource,
-        travel_duration_ticks: VarInt,
-    } = 43,
-    ItemSlime = 44,
-    ItemSnowball = 45,
-    LargeSmoke = 46,
-    Lava = 47,
-    Mycelium = 48,
-    Note = 49,
-    Poof = 50,
-    Portal = 51,
-    Rain = 52,
-    Smoke = 53,
-    Sneeze = 54,
-    Spit = 55,
-    SquidInk = 56,
-    SweepAttack = 57,
-    TotemOfUndying = 58,
-    Underwater = 59,
-    Splash = 60,
-    Witch = 61,
-    BubblePop = 62,
-    CurrentDown = 63,
-    BubbleColumnUp = 64,
-    Nautilus = 65,
-    Dolphin = 66,
-    CampfireCosySmoke = 67,
-    CampfireSignalSmoke = 68,
-    DrippingHoney = 69,
-    FallingHoney = 70,
-    LandingHoney = 71,
-    FallingNectar = 72,
-    FallingSporeBlossom = 73,
-    Ash = 74,
-    CrimsonSpore = 75,
-    WarpedSpore = 76,
-    SporeBlossomAir = 77,
-    DrippingObsidianTear = 78,
-    FallingObsidianTear = 79,
-    LandingObsidianTear = 80,
-    ReversePortal = 81,
-    WhiteAsh = 82,
-    SmallFlame = 83,
-    Snowflake = 84,
-    DrippingDripstoneLava = 85,
-    FallingDripstoneLava = 86,
-    DrippingDripstoneWater = 87,
-    FallingDripstoneWater = 88,
-    GlowSquidInk = 89,
-    Glow = 90,
-    WaxOn = 91,
-    WaxOff = 92,
-    ElectricSpark = 93,
-    Scrape = 94,
-    Shriek {
-        delay_ticks: VarInt,
-    } = 95,
+particles! {
+    #[default]
+    AngryVillager = 0,
+    Block { block_state: VarInt } = 1,
+    BlockMarker { block_state: VarInt } = 2,
+    Bubble = 3,
+    Cloud = 4,
+    Crit = 5,
+    DamageIndicator = 6,
+    DragonBreath = 7,
+    DrippingLava = 8,
+    FallingLava = 9,
+    LandingLava = 10,
+    DrippingWater = 11,
+    FallingWater = 12,
+    Dust { color: i32, scale: f32 } = 13,
+    DustColorTransition { from_color: i32, to_color: i32, scale: f32 } = 14,
+    Effect { color: i32, power: f32 } = 15,
+    ElderGuardian = 16,
+    EnchantedHit = 17,
+    Enchant = 18,
+    EndRod = 19,
+    EntityEffect { color: i32 } = 20,
+    ExplosionEmitter = 21,
+    Explosion = 22,
+    Gust = 23,
+    SmallGust = 24,
+    GustEmitterLarge = 25,
+    GustEmitterSmall = 26,
+    SonicBoom = 27,
+    FallingDust { block_state: VarInt } = 28,
+    Firework = 29,
+    Fishing = 30,
+    Flame = 31,
+    Infested = 32,
+    CherryLeaves = 33,
+    PaleOakLeaves = 34,
+    TintedLeaves { color: i32 } = 35,
+    SculkSoul = 36,
+    SculkCharge { roll: f32 } = 37,
+    SculkChargePop = 38,
+    SoulFireFlame = 39,
+    Soul = 40,
+    Flash = 41,
+    HappyVillager = 42,
+    Composter = 43,
+    Heart = 44,
+    InstantEffect { color: i32, power: f32 } = 45,
+    Item { item: Slot } = 46,
+    Vibration { source: VibrationParticleSource, travel_duration_ticks: VarInt } = 47,
+    Trail { target: (f64, f64, f64), color: i32, duration_ticks: VarInt } = 48,
+    ItemSlime = 49,
+    ItemCobweb = 50,
+    ItemSnowball = 51,
+    LargeSmoke = 52,
+    Lava = 53,
+    Mycelium = 54,
+    Note = 55,
+    Poof = 56,
+    Portal = 57,
+    Rain = 58,
+    Smoke = 59,
+    WhiteSmoke = 60,
+    Sneeze = 61,
+    Spit = 62,
+    SquidInk = 63,
+    SweepAttack = 64,
+    TotemOfUndying = 65,
+    Underwater = 66,
+    Splash = 67,
+    Witch = 68,
+    BubblePop = 69,
+    CurrentDown = 70,
+    BubbleColumnUp = 71,
+    Nautilus = 72,
+    Dolphin = 73,
+    CampfireCosySmoke = 74,
+    CampfireSignalSmoke = 75,
+    DrippingHoney = 76,
+    FallingHoney = 77,
+    LandingHoney = 78,
+    FallingNectar = 79,
+    FallingSporeBlossom = 80,
+    Ash = 81,
+    CrimsonSpore = 82,
+    WarpedSpore = 83,
+    SporeBlossomAir = 84,
+    DrippingObsidianTear = 85,
+    FallingObsidianTear = 86,
+    LandingObsidianTear = 87,
+    ReversePortal = 88,
+    WhiteAsh = 89,
+    SmallFlame = 90,
+    Snowflake = 91,
+    DrippingDripstoneLava = 92,
+    FallingDripstoneLava = 93,
+    DrippingDripstoneWater = 94,
+    FallingDripstoneWater = 95,
+    GlowSquidInk = 96,
+    Glow = 97,
+    WaxOn = 98,
+    WaxOff = 99,
+    ElectricSpark = 100,
+    Scrape = 101,
+    Shriek { delay_ticks: VarInt } = 102,
+    EggCrack = 103,
+    DustPlume = 104,
+    TrialSpawnerDetection = 105,
+    TrialSpawnerDetectionOminous = 106,
+    VaultConnection = 107,
+    DustPillar { block_state: VarInt } = 108,
+    OminousSpawning = 109,
+    RaidOmen = 110,
+    TrialOmen = 111,
+    BlockCrumble { block_state: VarInt } = 112,
+    Firefly = 113,
 }
 
+/// Where a vibration particle travels to.
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum VibrationParticleSource {
     Block(Position),
     Entity { id: EntityId, eye_height: f32 },
 }
+impl Default for VibrationParticleSource {
+    fn default() -> Self {
+        VibrationParticleSource::Block(Position::default())
+    }
+}
+impl Parsable for VibrationParticleSource {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, kind) = VarInt::parse(data)?;
+        match *kind {
+            0 => nom::combinator::map(Position::parse, VibrationParticleSource::Block)(data),
+            1 => {
+                let (data, id) = VarInt::parse(data)?;
+                let (data, eye_height) = f32::parse(data)?;
+                Ok((data, VibrationParticleSource::Entity { id, eye_height }))
+            }
+            _ => nom::combinator::fail(data),
+        }
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        match self {
+            VibrationParticleSource::Block(position) => {
+                VarInt::from(0).serialize_into(buf);
+                position.serialize_into(buf);
+            }
+            VibrationParticleSource::Entity { id, eye_height } => {
+                VarInt::from(1).serialize_into(buf);
+                id.serialize_into(buf);
+                eye_height.serialize_into(buf);
+            }
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        match self {
+            VibrationParticleSource::Block(position) => 1 + position.encoded_len(),
+            VibrationParticleSource::Entity { id, eye_height } => {
+                1 + id.encoded_len() + eye_height.encoded_len()
+            }
+        }
+    }
+}
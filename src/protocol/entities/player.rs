@@ -1,4 +1,4 @@
-use crate::protocol::types::VarInt;
+use crate::protocol::{nbt::Tag, types::VarInt};
 
 #[derive(Clone, Debug, PartialEq, Default)]
 pub struct Player {
@@ -6,10 +6,8 @@ pub struct Player {
     pub score: VarInt,
     pub skin_parts: PlayerSkinParts,
     pub right_handed: bool,
-    // TODO: NBT data
-    pub left_shoulder_entity: (),
-    // TODO: NBT data
-    pub right_shoulder_entity: (),
+    pub left_shoulder_entity: Tag,
+    pub right_shoulder_entity: Tag,
 }
 
 #[derive(Clone, Debug, PartialEq, Default)]
@@ -6,14 +6,16 @@ pub struct Sniffer {
     pub seed_drop_ticks: VarInt,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
-pub enum SnifferState {
-    #[default]
-    Idling = 0,
-    FeelingHappy = 1,
-    Scenting = 2,
-    Sniffing = 3,
-    Searching = 4,
-    Digging = 5,
-    Rising = 6,
+crate::varint_enum! {
+    #[derive(Default)]
+    pub enum SnifferState {
+        #[default]
+        Idling = 0,
+        FeelingHappy = 1,
+        Scenting = 2,
+        Sniffing = 3,
+        Searching = 4,
+        Digging = 5,
+        Rising = 6,
+    }
 }
@@ -1,3 +1,5 @@
+use crate::protocol::{packets::PacketDirection, ClientState};
+
 /// This type represents all possible errors that can occur in the Minecraft protocol.
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -20,3 +22,88 @@ pub enum Error {
 
 /// Alias for a Result with the error type `composition_protocol::Error`.
 pub type Result<T> = std::result::Result<T, Error>;
+
+/// Why a packet failed to parse.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The packet id doesn't exist in the connection's state, direction and version.
+    UnknownPacketId,
+    /// A field had a value that isn't allowed.
+    InvalidData,
+    /// The frame ended in the middle of a field.
+    UnexpectedEnd,
+    /// Every field parsed, but this many bytes were left over.
+    TrailingBytes(usize),
+}
+impl<E> From<&nom::Err<E>> for ParseErrorKind {
+    fn from(value: &nom::Err<E>) -> Self {
+        match value {
+            nom::Err::Incomplete(_) => ParseErrorKind::UnexpectedEnd,
+            nom::Err::Error(_) | nom::Err::Failure(_) => ParseErrorKind::InvalidData,
+        }
+    }
+}
+impl std::fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseErrorKind::UnknownPacketId => write!(f, "unknown packet id"),
+            ParseErrorKind::InvalidData => write!(f, "invalid data"),
+            ParseErrorKind::UnexpectedEnd => write!(f, "unexpected end of packet"),
+            ParseErrorKind::TrailingBytes(len) => write!(f, "{len} bytes left over"),
+        }
+    }
+}
+
+/// Where and why a packet failed to parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub client_state: ClientState,
+    pub direction: PacketDirection,
+    /// The packet id as it was sent, if it could be read.
+    pub packet_id: Option<i32>,
+    /// The packet's name, if the id is known.
+    pub packet_name: Option<&'static str>,
+    /// The field that failed to parse, if the packet is known.
+    pub field: Option<&'static str>,
+    /// How far into the frame parsing failed, counting the packet id but not the length prefix.
+    pub offset: usize,
+    pub kind: ParseErrorKind,
+}
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} {:?} packet", self.client_state, self.direction)?;
+        if let Some(packet_id) = self.packet_id {
+            write!(f, " 0x{packet_id:02x}")?;
+        }
+        if let Some(packet_name) = self.packet_name {
+            write!(f, " ({packet_name})")?;
+        }
+        if let Some(field) = self.field {
+            write!(f, " field `{field}`")?;
+        }
+        write!(f, " at byte {}: {}", self.offset, self.kind)
+    }
+}
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_errors_display() {
+        let error = ParseError {
+            client_state: ClientState::Play,
+            direction: PacketDirection::Serverbound,
+            packet_id: Some(0x08),
+            packet_name: Some("ChatMessage"),
+            field: Some("timestamp"),
+            offset: 4,
+            kind: ParseErrorKind::UnexpectedEnd,
+        };
+        assert_eq!(
+            error.to_string(),
+            "Play Serverbound packet 0x08 (ChatMessage) field `timestamp` at byte 4: unexpected end of packet"
+        );
+    }
+}
@@ -0,0 +1,286 @@
+use crate::protocol::{
+    nbt::Tag,
+    parsing::{BufMut, IResult, Parsable, PrefixedOptional, VarInt},
+    text::TextComponent,
+    types::{IdOr, Identifier, SoundEvent},
+};
+
+use super::slot::Slot;
+
+/// Define `ItemComponent` from its variants, where each variant
+/// is either a unit or wraps the component's data.
+macro_rules! item_components {
+    ($($(#[$attr:meta])* $name:ident $(($ty:ty))? = $id:literal,)*) => {
+        /// A data component of an item stack, with the id it has in
+        /// the `minecraft:data_component_type` registry.
+        ///
+        /// Component data isn't length-prefixed, so a stack with a component
+        /// that has no variant here can't be parsed.
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum ItemComponent {
+            $($(#[$attr])* $name $(($ty))?,)*
+        }
+        impl ItemComponent {
+            pub fn id(&self) -> VarInt {
+                match self {
+                    $(item_components!(@bind _value, $name $($ty)?) => VarInt::from($id),)*
+                }
+            }
+        }
+        impl Parsable for ItemComponent {
+            #[tracing::instrument]
+            fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+                let (data, id) = VarInt::parse(data)?;
+                match *id {
+                    $($id => item_components!(@parse data, $name $($ty)?),)*
+                    _ => nom::combinator::fail(data),
+                }
+            }
+            fn serialize_into(&self, buf: &mut impl BufMut) {
+                self.id().serialize_into(buf);
+                match self {
+                    $(item_components!(@bind value, $name $($ty)?) => {
+                        item_components!(@serialize buf, value $($ty)?);
+                    })*
+                }
+            }
+            fn encoded_len(&self) -> usize {
+                self.id().encoded_len()
+                    + match self {
+                        $(item_components!(@bind value, $name $($ty)?) => {
+                            item_components!(@encoded_len value $($ty)?)
+                        })*
+                    }
+            }
+        }
+    };
+    (@bind $value:ident, $name:ident) => { ItemComponent::$name };
+    (@bind $value:ident, $name:ident $ty:ty) => { ItemComponent::$name($value) };
+    (@parse $data:ident, $name:ident) => { Ok(($data, ItemComponent::$name)) };
+    (@parse $data:ident, $name:ident $ty:ty) => {
+        nom::combinator::map(<$ty as Parsable>::parse, ItemComponent::$name)($data)
+    };
+    (@serialize $buf:ident, $value:ident) => {};
+    (@serialize $buf:ident, $value:ident $ty:ty) => { $value.serialize_into($buf) };
+    (@encoded_len $value:ident) => { 0 };
+    (@encoded_len $value:ident $ty:ty) => { $value.encoded_len() };
+}
+
+item_components! {
+    CustomData(Tag) = 0,
+    MaxStackSize(VarInt) = 1,
+    MaxDamage(VarInt) = 2,
+    Damage(VarInt) = 3,
+    Unbreakable = 4,
+    CustomName(TextComponent) = 5,
+    ItemName(TextComponent) = 6,
+    ItemModel(Identifier) = 7,
+    Lore(Vec<TextComponent>) = 8,
+    Rarity(Rarity) = 9,
+    /// Pairs of enchantment id and level.
+    Enchantments(Vec<(VarInt, VarInt)>) = 10,
+    CustomModelData(CustomModelData) = 14,
+    TooltipDisplay(TooltipDisplay) = 15,
+    RepairCost(VarInt) = 16,
+    CreativeSlotLock = 17,
+    EnchantmentGlintOverride(bool) = 18,
+    Food(Food) = 20,
+    UseRemainder(Slot) = 22,
+    UseCooldown(UseCooldown) = 23,
+    /// The damage type tag that the item is immune to.
+    DamageResistant(Identifier) = 24,
+    Weapon(Weapon) = 26,
+    Enchantable(VarInt) = 27,
+    Glider = 30,
+    TooltipStyle(Identifier) = 31,
+    /// Pairs of enchantment id and level.
+    StoredEnchantments(Vec<(VarInt, VarInt)>) = 34,
+    DyedColor(i32) = 35,
+    MapColor(i32) = 36,
+    MapId(VarInt) = 37,
+    MapPostProcessing(VarInt) = 39,
+    ChargedProjectiles(Vec<Slot>) = 40,
+    BundleContents(Vec<Slot>) = 41,
+    PotionDurationScale(f32) = 43,
+    /// Pairs of effect id and duration in ticks.
+    SuspiciousStewEffects(Vec<(VarInt, VarInt)>) = 44,
+    EntityData(Tag) = 49,
+    BucketEntityData(Tag) = 50,
+    BlockEntityData(Tag) = 51,
+    OminousBottleAmplifier(VarInt) = 54,
+    NoteBlockSound(Identifier) = 62,
+    BaseColor(VarInt) = 64,
+    /// Item ids of the sherds on each side of a decorated pot.
+    PotDecorations(Vec<VarInt>) = 65,
+    Container(Vec<Slot>) = 66,
+    /// Pairs of block state property name and value.
+    BlockState(Vec<(String, String)>) = 67,
+    BreakSound(IdOr<SoundEvent>) = 71,
+}
+
+crate::varint_enum! {
+    /// The color of an item's name.
+    #[derive(Default)]
+    pub enum Rarity {
+        #[default]
+        Common = 0,
+        Uncommon = 1,
+        Rare = 2,
+        Epic = 3,
+    }
+}
+
+/// Values that an item model can select between.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CustomModelData {
+    pub floats: Vec<f32>,
+    pub flags: Vec<bool>,
+    pub strings: Vec<String>,
+    pub colors: Vec<i32>,
+}
+impl Parsable for CustomModelData {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, floats) = Vec::parse(data)?;
+        let (data, flags) = Vec::parse(data)?;
+        let (data, strings) = Vec::parse(data)?;
+        let (data, colors) = Vec::parse(data)?;
+        Ok((
+            data,
+            CustomModelData {
+                floats,
+                flags,
+                strings,
+                colors,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.floats.serialize_into(buf);
+        self.flags.serialize_into(buf);
+        self.strings.serialize_into(buf);
+        self.colors.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.floats.encoded_len()
+            + self.flags.encoded_len()
+            + self.strings.encoded_len()
+            + self.colors.encoded_len()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TooltipDisplay {
+    pub hide_tooltip: bool,
+    /// Ids of the components that don't add lines to the tooltip.
+    pub hidden_components: Vec<VarInt>,
+}
+impl Parsable for TooltipDisplay {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, hide_tooltip) = bool::parse(data)?;
+        let (data, hidden_components) = Vec::parse(data)?;
+        Ok((
+            data,
+            TooltipDisplay {
+                hide_tooltip,
+                hidden_components,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.hide_tooltip.serialize_into(buf);
+        self.hidden_components.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.hide_tooltip.encoded_len() + self.hidden_components.encoded_len()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Food {
+    pub nutrition: VarInt,
+    pub saturation_modifier: f32,
+    pub can_always_eat: bool,
+}
+impl Parsable for Food {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, nutrition) = VarInt::parse(data)?;
+        let (data, saturation_modifier) = f32::parse(data)?;
+        let (data, can_always_eat) = bool::parse(data)?;
+        Ok((
+            data,
+            Food {
+                nutrition,
+                saturation_modifier,
+                can_always_eat,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.nutrition.serialize_into(buf);
+        self.saturation_modifier.serialize_into(buf);
+        self.can_always_eat.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.nutrition.encoded_len()
+            + self.saturation_modifier.encoded_len()
+            + self.can_always_eat.encoded_len()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct UseCooldown {
+    pub seconds: f32,
+    /// Items in the same group share a cooldown, otherwise it's per item.
+    pub cooldown_group: PrefixedOptional<Identifier>,
+}
+impl Parsable for UseCooldown {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, seconds) = f32::parse(data)?;
+        let (data, cooldown_group) = PrefixedOptional::parse(data)?;
+        Ok((
+            data,
+            UseCooldown {
+                seconds,
+                cooldown_group,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.seconds.serialize_into(buf);
+        self.cooldown_group.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.seconds.encoded_len() + self.cooldown_group.encoded_len()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Weapon {
+    pub item_damage_per_attack: VarInt,
+    pub disable_blocking_for_seconds: f32,
+}
+impl Parsable for Weapon {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, item_damage_per_attack) = VarInt::parse(data)?;
+        let (data, disable_blocking_for_seconds) = f32::parse(data)?;
+        Ok((
+            data,
+            Weapon {
+                item_damage_per_attack,
+                disable_blocking_for_seconds,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.item_damage_per_attack.serialize_into(buf);
+        self.disable_blocking_for_seconds.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.item_damage_per_attack.encoded_len() + self.disable_blocking_for_seconds.encoded_len()
+    }
+}
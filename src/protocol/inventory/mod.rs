@@ -1,5 +1,9 @@
+pub mod component;
+pub mod recipe;
 pub mod slot;
+pub mod trade;
 
+use crate::protocol::parsing::{BufMut, IResult, Parsable};
 use slot::Slot;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
@@ -25,9 +29,6 @@ pub enum InventoryKind {
     Loom = 17,
     Merchant = 18,
     ShulkerBox = 19,
-    #[cfg(not(feature = "update_1_20"))]
-    LegacySmithing = 20,
-    #[cfg(feature = "update_1_20")]
     Smithing = 21,
     Smoker = 22,
     Cartography = 23,
@@ -56,9 +57,6 @@ impl InventoryKind {
             InventoryKind::Loom => "minecraft:loom",
             InventoryKind::Merchant => "minecraft:merchant",
             InventoryKind::ShulkerBox => "mincraft:shulker_box",
-            #[cfg(not(feature = "update_1_20"))]
-            InventoryKind::LegacySmithing => "minecraft:legacy_smithing",
-            #[cfg(feature = "update_1_20")]
             InventoryKind::Smithing => "minecraft:smithing",
             InventoryKind::Smoker => "minecraft:smoker",
             InventoryKind::Cartography => "minecraft:cartography",
@@ -578,6 +576,8 @@ impl Shulker {
     }
 }
 
+// Every variant holds a full inventory, so boxing the larger ones would gain nothing.
+#[allow(clippy::large_enum_variant)]
 pub enum Llama {
     Unchested([Slot; 38]),
     Strength1([Slot; 41]),
@@ -758,6 +758,8 @@ impl Horse {
     }
 }
 
+// Every variant holds a full inventory, so boxing the larger ones would gain nothing.
+#[allow(clippy::large_enum_variant)]
 pub enum Donkey {
     Unchested([Slot; 38]),
     Chested([Slot; 53]),
@@ -1000,51 +1002,7 @@ impl Stonecutter {
     }
 }
 
-#[cfg(not(feature = "update_1_20"))]
-pub struct LegacySmithingTable([Slot; 39]);
-#[cfg(not(feature = "update_1_20"))]
-impl LegacySmithingTable {
-    pub const INVENTORY_KIND: InventoryKind = InventoryKind::LegacySmithing;
-
-    pub fn base_item(&self) -> &Slot {
-        &self.0[0]
-    }
-    pub fn base_item_mut(&mut self) -> &mut Slot {
-        &mut self.0[0]
-    }
-
-    pub fn additional_item(&self) -> &Slot {
-        &self.0[1]
-    }
-    pub fn additional_item_mut(&mut self) -> &mut Slot {
-        &mut self.0[1]
-    }
-
-    pub fn output(&self) -> &Slot {
-        &self.0[2]
-    }
-    pub fn output_mut(&mut self) -> &mut Slot {
-        &mut self.0[2]
-    }
-
-    pub fn main_inventory(&self) -> &[Slot; 27] {
-        <&[Slot; 27]>::try_from(&self.0[3..30]).unwrap()
-    }
-    pub fn main_inventory_mut(&mut self) -> &mut [Slot; 27] {
-        <&mut [Slot; 27]>::try_from(&mut self.0[3..30]).unwrap()
-    }
-
-    pub fn hotbar(&self) -> &[Slot; 9] {
-        <&[Slot; 9]>::try_from(&self.0[30..39]).unwrap()
-    }
-    pub fn hotbar_mut(&mut self) -> &mut [Slot; 9] {
-        <&mut [Slot; 9]>::try_from(&mut self.0[30..39]).unwrap()
-    }
-}
-
-#[cfg(feature = "update_1_20")]
 pub struct SmithingTable([Slot; 40]);
-#[cfg(feature = "update_1_20")]
 impl SmithingTable {
     pub const INVENTORY_KIND: InventoryKind = InventoryKind::Smithing;
 
@@ -1090,3 +1048,73 @@ impl SmithingTable {
         <&mut [Slot; 9]>::try_from(&mut self.0[31..40]).unwrap()
     }
 }
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum EquipmentSlot {
+    #[default]
+    MainHand = 0,
+    OffHand = 1,
+    Feet = 2,
+    Legs = 3,
+    Chest = 4,
+    Head = 5,
+    Body = 6,
+    Saddle = 7,
+}
+impl TryFrom<u8> for EquipmentSlot {
+    type Error = u8;
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(EquipmentSlot::MainHand),
+            1 => Ok(EquipmentSlot::OffHand),
+            2 => Ok(EquipmentSlot::Feet),
+            3 => Ok(EquipmentSlot::Legs),
+            4 => Ok(EquipmentSlot::Chest),
+            5 => Ok(EquipmentSlot::Head),
+            6 => Ok(EquipmentSlot::Body),
+            7 => Ok(EquipmentSlot::Saddle),
+            _ => Err(value),
+        }
+    }
+}
+
+/// The items an entity has equipped, represented in the protocol as
+/// pairs of slot and item where the slot's top bit is set if another pair follows.
+///
+/// There must be at least one pair.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Equipment(pub Vec<(EquipmentSlot, Slot)>);
+impl Equipment {
+    const HAS_MORE: u8 = 0x80;
+}
+impl Parsable for Equipment {
+    #[tracing::instrument]
+    fn parse(mut data: &[u8]) -> IResult<&[u8], Self> {
+        let mut equipment = vec![];
+        loop {
+            let (d, slot) = u8::parse(data)?;
+            let Ok(kind) = EquipmentSlot::try_from(slot & !Equipment::HAS_MORE) else {
+                return nom::combinator::fail(data);
+            };
+            let (d, item) = Slot::parse(d)?;
+            data = d;
+            equipment.push((kind, item));
+            if slot & Equipment::HAS_MORE == 0 {
+                return Ok((data, Equipment(equipment)));
+            }
+        }
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        for (i, (kind, item)) in self.0.iter().enumerate() {
+            let mut slot = *kind as u8;
+            if i + 1 < self.0.len() {
+                slot |= Equipment::HAS_MORE;
+            }
+            slot.serialize_into(buf);
+            item.serialize_into(buf);
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        self.0.iter().map(|(_, item)| 1 + item.encoded_len()).sum()
+    }
+}
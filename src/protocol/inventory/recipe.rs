@@ -0,0 +1,492 @@
+use super::slot::Slot;
+use crate::protocol::{
+    parsing::{BufMut, IResult, Parsable},
+    text::TextComponent,
+    types::{IdOr, IdSet, Identifier, OptionalVarInt, PrefixedOptional, VarInt},
+};
+
+/// How the client shows the items that can go in a recipe slot,
+/// represented in the protocol as a `VarInt` of the kind followed by its fields.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum SlotDisplay {
+    #[default]
+    Empty,
+    AnyFuel,
+    Item(VarInt),
+    ItemStack(Slot),
+    Tag(Identifier),
+    SmithingTrim {
+        base: Box<SlotDisplay>,
+        material: Box<SlotDisplay>,
+        pattern: IdOr<Box<TrimPattern>>,
+    },
+    WithRemainder {
+        input: Box<SlotDisplay>,
+        remainder: Box<SlotDisplay>,
+    },
+    /// Cycles through each of the displays.
+    Composite(Vec<SlotDisplay>),
+}
+impl Parsable for SlotDisplay {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        use nom::combinator::map;
+
+        let (data, kind) = VarInt::parse(data)?;
+        match *kind {
+            0 => Ok((data, SlotDisplay::Empty)),
+            1 => Ok((data, SlotDisplay::AnyFuel)),
+            2 => map(VarInt::parse, SlotDisplay::Item)(data),
+            3 => map(Slot::parse, SlotDisplay::ItemStack)(data),
+            4 => map(Identifier::parse, SlotDisplay::Tag)(data),
+            5 => {
+                let (data, base) = Box::parse(data)?;
+                let (data, material) = Box::parse(data)?;
+                let (data, pattern) = IdOr::parse(data)?;
+                Ok((
+                    data,
+                    SlotDisplay::SmithingTrim {
+                        base,
+                        material,
+                        pattern,
+                    },
+                ))
+            }
+            6 => {
+                let (data, input) = Box::parse(data)?;
+                let (data, remainder) = Box::parse(data)?;
+                Ok((data, SlotDisplay::WithRemainder { input, remainder }))
+            }
+            7 => map(Vec::parse, SlotDisplay::Composite)(data),
+            _ => nom::combinator::fail(data),
+        }
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        match self {
+            SlotDisplay::Empty => VarInt::from(0).serialize_into(buf),
+            SlotDisplay::AnyFuel => VarInt::from(1).serialize_into(buf),
+            SlotDisplay::Item(item_id) => {
+                VarInt::from(2).serialize_into(buf);
+                item_id.serialize_into(buf);
+            }
+            SlotDisplay::ItemStack(stack) => {
+                VarInt::from(3).serialize_into(buf);
+                stack.serialize_into(buf);
+            }
+            SlotDisplay::Tag(tag) => {
+                VarInt::from(4).serialize_into(buf);
+                tag.serialize_into(buf);
+            }
+            SlotDisplay::SmithingTrim {
+                base,
+                material,
+                pattern,
+            } => {
+                VarInt::from(5).serialize_into(buf);
+                base.serialize_into(buf);
+                material.serialize_into(buf);
+                pattern.serialize_into(buf);
+            }
+            SlotDisplay::WithRemainder { input, remainder } => {
+                VarInt::from(6).serialize_into(buf);
+                input.serialize_into(buf);
+                remainder.serialize_into(buf);
+            }
+            SlotDisplay::Composite(displays) => {
+                VarInt::from(7).serialize_into(buf);
+                displays.serialize_into(buf);
+            }
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        1 + match self {
+            SlotDisplay::Empty => 0,
+            SlotDisplay::AnyFuel => 0,
+            SlotDisplay::Item(item_id) => item_id.encoded_len(),
+            SlotDisplay::ItemStack(stack) => stack.encoded_len(),
+            SlotDisplay::Tag(tag) => tag.encoded_len(),
+            SlotDisplay::SmithingTrim {
+                base,
+                material,
+                pattern,
+            } => base.encoded_len() + material.encoded_len() + pattern.encoded_len(),
+            SlotDisplay::WithRemainder { input, remainder } => {
+                input.encoded_len() + remainder.encoded_len()
+            }
+            SlotDisplay::Composite(displays) => displays.encoded_len(),
+        }
+    }
+}
+
+/// An armor trim pattern that isn't in the client's trim pattern registry.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TrimPattern {
+    pub asset_id: Identifier,
+    pub description: TextComponent,
+    pub decal: bool,
+}
+impl Parsable for TrimPattern {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, asset_id) = Identifier::parse(data)?;
+        let (data, description) = TextComponent::parse(data)?;
+        let (data, decal) = bool::parse(data)?;
+        Ok((
+            data,
+            TrimPattern {
+                asset_id,
+                description,
+                decal,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.asset_id.serialize_into(buf);
+        self.description.serialize_into(buf);
+        self.decal.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.asset_id.encoded_len() + self.description.encoded_len() + self.decal.encoded_len()
+    }
+}
+
+/// How the client shows a recipe in the recipe book,
+/// represented in the protocol as a `VarInt` of the kind followed by its fields.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecipeDisplay {
+    CraftingShapeless {
+        ingredients: Vec<SlotDisplay>,
+        result: SlotDisplay,
+        crafting_station: SlotDisplay,
+    },
+    CraftingShaped {
+        width: VarInt,
+        height: VarInt,
+        /// Ingredients in row-major order.
+        ingredients: Vec<SlotDisplay>,
+        result: SlotDisplay,
+        crafting_station: SlotDisplay,
+    },
+    Furnace {
+        ingredient: SlotDisplay,
+        fuel: SlotDisplay,
+        result: SlotDisplay,
+        crafting_station: SlotDisplay,
+        cooking_time: VarInt,
+        experience: f32,
+    },
+    Stonecutter {
+        input: SlotDisplay,
+        result: SlotDisplay,
+        crafting_station: SlotDisplay,
+    },
+    Smithing {
+        template: SlotDisplay,
+        base: SlotDisplay,
+        addition: SlotDisplay,
+        result: SlotDisplay,
+        crafting_station: SlotDisplay,
+    },
+}
+impl Default for RecipeDisplay {
+    fn default() -> Self {
+        RecipeDisplay::CraftingShapeless {
+            ingredients: vec![],
+            result: SlotDisplay::default(),
+            crafting_station: SlotDisplay::default(),
+        }
+    }
+}
+impl Parsable for RecipeDisplay {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, kind) = VarInt::parse(data)?;
+        match *kind {
+            0 => {
+                let (data, ingredients) = Vec::parse(data)?;
+                let (data, result) = SlotDisplay::parse(data)?;
+                let (data, crafting_station) = SlotDisplay::parse(data)?;
+                Ok((
+                    data,
+                    RecipeDisplay::CraftingShapeless {
+                        ingredients,
+                        result,
+                        crafting_station,
+                    },
+                ))
+            }
+            1 => {
+                let (data, width) = VarInt::parse(data)?;
+                let (data, height) = VarInt::parse(data)?;
+                let (data, ingredients) = Vec::parse(data)?;
+                let (data, result) = SlotDisplay::parse(data)?;
+                let (data, crafting_station) = SlotDisplay::parse(data)?;
+                Ok((
+                    data,
+                    RecipeDisplay::CraftingShaped {
+                        width,
+                        height,
+                        ingredients,
+                        result,
+                        crafting_station,
+                    },
+                ))
+            }
+            2 => {
+                let (data, ingredient) = SlotDisplay::parse(data)?;
+                let (data, fuel) = SlotDisplay::parse(data)?;
+                let (data, result) = SlotDisplay::parse(data)?;
+                let (data, crafting_station) = SlotDisplay::parse(data)?;
+                let (data, cooking_time) = VarInt::parse(data)?;
+                let (data, experience) = f32::parse(data)?;
+                Ok((
+                    data,
+                    RecipeDisplay::Furnace {
+                        ingredient,
+                        fuel,
+                        result,
+                        crafting_station,
+                        cooking_time,
+                        experience,
+                    },
+                ))
+            }
+            3 => {
+                let (data, input) = SlotDisplay::parse(data)?;
+                let (data, result) = SlotDisplay::parse(data)?;
+                let (data, crafting_station) = SlotDisplay::parse(data)?;
+                Ok((
+                    data,
+                    RecipeDisplay::Stonecutter {
+                        input,
+                        result,
+                        crafting_station,
+                    },
+                ))
+            }
+            4 => {
+                let (data, template) = SlotDisplay::parse(data)?;
+                let (data, base) = SlotDisplay::parse(data)?;
+                let (data, addition) = SlotDisplay::parse(data)?;
+                let (data, result) = SlotDisplay::parse(data)?;
+                let (data, crafting_station) = SlotDisplay::parse(data)?;
+                Ok((
+                    data,
+                    RecipeDisplay::Smithing {
+                        template,
+                        base,
+                        addition,
+                        result,
+                        crafting_station,
+                    },
+                ))
+            }
+            _ => nom::combinator::fail(data),
+        }
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        match self {
+            RecipeDisplay::CraftingShapeless {
+                ingredients,
+                result,
+                crafting_station,
+            } => {
+                VarInt::from(0).serialize_into(buf);
+                ingredients.serialize_into(buf);
+                result.serialize_into(buf);
+                crafting_station.serialize_into(buf);
+            }
+            RecipeDisplay::CraftingShaped {
+                width,
+                height,
+                ingredients,
+                result,
+                crafting_station,
+            } => {
+                VarInt::from(1).serialize_into(buf);
+                width.serialize_into(buf);
+                height.serialize_into(buf);
+                ingredients.serialize_into(buf);
+                result.serialize_into(buf);
+                crafting_station.serialize_into(buf);
+            }
+            RecipeDisplay::Furnace {
+                ingredient,
+                fuel,
+                result,
+                crafting_station,
+                cooking_time,
+                experience,
+            } => {
+                VarInt::from(2).serialize_into(buf);
+                ingredient.serialize_into(buf);
+                fuel.serialize_into(buf);
+                result.serialize_into(buf);
+                crafting_station.serialize_into(buf);
+                cooking_time.serialize_into(buf);
+                experience.serialize_into(buf);
+            }
+            RecipeDisplay::Stonecutter {
+                input,
+                result,
+                crafting_station,
+            } => {
+                VarInt::from(3).serialize_into(buf);
+                input.serialize_into(buf);
+                result.serialize_into(buf);
+                crafting_station.serialize_into(buf);
+            }
+            RecipeDisplay::Smithing {
+                template,
+                base,
+                addition,
+                result,
+                crafting_station,
+            } => {
+                VarInt::from(4).serialize_into(buf);
+                template.serialize_into(buf);
+                base.serialize_into(buf);
+                addition.serialize_into(buf);
+                result.serialize_into(buf);
+                crafting_station.serialize_into(buf);
+            }
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        1 + match self {
+            RecipeDisplay::CraftingShapeless {
+                ingredients,
+                result,
+                crafting_station,
+            } => ingredients.encoded_len() + result.encoded_len() + crafting_station.encoded_len(),
+            RecipeDisplay::CraftingShaped {
+                width,
+                height,
+                ingredients,
+                result,
+                crafting_station,
+            } => {
+                width.encoded_len()
+                    + height.encoded_len()
+                    + ingredients.encoded_len()
+                    + result.encoded_len()
+                    + crafting_station.encoded_len()
+            }
+            RecipeDisplay::Furnace {
+                ingredient,
+                fuel,
+                result,
+                crafting_station,
+                cooking_time,
+                experience,
+            } => {
+                ingredient.encoded_len()
+                    + fuel.encoded_len()
+                    + result.encoded_len()
+                    + crafting_station.encoded_len()
+                    + cooking_time.encoded_len()
+                    + experience.encoded_len()
+            }
+            RecipeDisplay::Stonecutter {
+                input,
+                result,
+                crafting_station,
+            } => input.encoded_len() + result.encoded_len() + crafting_station.encoded_len(),
+            RecipeDisplay::Smithing {
+                template,
+                base,
+                addition,
+                result,
+                crafting_station,
+            } => {
+                template.encoded_len()
+                    + base.encoded_len()
+                    + addition.encoded_len()
+                    + result.encoded_len()
+                    + crafting_station.encoded_len()
+            }
+        }
+    }
+}
+
+/// A recipe unlocked in the player's recipe book.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RecipeBookEntry {
+    /// The id the server uses to refer to this recipe.
+    pub id: VarInt,
+    pub display: RecipeDisplay,
+    /// Recipes in the same group are shown together.
+    pub group: OptionalVarInt,
+    /// Id in the `minecraft:recipe_book_category` registry.
+    pub category: VarInt,
+    /// The items needed for each ingredient, used to check whether the player can craft it.
+    pub crafting_requirements: PrefixedOptional<Vec<IdSet>>,
+    /// 0x01: show notification, 0x02: highlight as new.
+    pub flags: u8,
+}
+impl Parsable for RecipeBookEntry {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, id) = VarInt::parse(data)?;
+        let (data, display) = RecipeDisplay::parse(data)?;
+        let (data, group) = OptionalVarInt::parse(data)?;
+        let (data, category) = VarInt::parse(data)?;
+        let (data, crafting_requirements) = PrefixedOptional::parse(data)?;
+        let (data, flags) = u8::parse(data)?;
+        Ok((
+            data,
+            RecipeBookEntry {
+                id,
+                display,
+                group,
+                category,
+                crafting_requirements,
+                flags,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.id.serialize_into(buf);
+        self.display.serialize_into(buf);
+        self.group.serialize_into(buf);
+        self.category.serialize_into(buf);
+        self.crafting_requirements.serialize_into(buf);
+        self.flags.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.id.encoded_len()
+            + self.display.encoded_len()
+            + self.group.encoded_len()
+            + self.category.encoded_len()
+            + self.crafting_requirements.encoded_len()
+            + self.flags.encoded_len()
+    }
+}
+
+/// A stonecutter recipe, which the client needs to fill the stonecutter screen.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StonecutterRecipe {
+    pub ingredients: IdSet,
+    pub result: SlotDisplay,
+}
+impl Parsable for StonecutterRecipe {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, ingredients) = IdSet::parse(data)?;
+        let (data, result) = SlotDisplay::parse(data)?;
+        Ok((
+            data,
+            StonecutterRecipe {
+                ingredients,
+                result,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.ingredients.serialize_into(buf);
+        self.result.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.ingredients.encoded_len() + self.result.encoded_len()
+    }
+}
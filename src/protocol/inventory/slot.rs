@@ -1,13 +1,246 @@
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+use crate::protocol::{
+    inventory::component::ItemComponent,
+    parsing::{BufMut, IResult, Parsable, PrefixedOptional, VarInt},
+};
+
+/// An inventory slot, represented in the protocol as a `VarInt` item count
+/// followed by the rest of the item stack if the count isn't 0.
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct Slot {
     pub contents: Option<ItemStack>,
 }
+impl Slot {
+    pub fn empty() -> Self {
+        Slot { contents: None }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.contents.is_none()
+    }
+}
+impl From<ItemStack> for Slot {
+    fn from(stack: ItemStack) -> Self {
+        Slot {
+            contents: Some(stack),
+        }
+    }
+}
+impl Parsable for Slot {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, count) = VarInt::parse(data)?;
+        if *count <= 0 {
+            return Ok((data, Slot::empty()));
+        }
+        let (data, item_id) = VarInt::parse(data)?;
+        let (data, add_count) = VarInt::parse_usize(data)?;
+        let (data, remove_count) = VarInt::parse_usize(data)?;
+        let (data, components_to_add) = ItemComponent::parse_repeated(add_count, data)?;
+        let (data, components_to_remove) = VarInt::parse_repeated(remove_count, data)?;
+        Ok((
+            data,
+            Slot::from(ItemStack {
+                item_id,
+                count,
+                components_to_add,
+                components_to_remove,
+            }),
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        let Some(stack) = &self.contents else {
+            VarInt::from(0).serialize_into(buf);
+            return;
+        };
+        stack.count.serialize_into(buf);
+        stack.item_id.serialize_into(buf);
+        VarInt::from(stack.components_to_add.len()).serialize_into(buf);
+        VarInt::from(stack.components_to_remove.len()).serialize_into(buf);
+        for component in &stack.components_to_add {
+            component.serialize_into(buf);
+        }
+        for kind in &stack.components_to_remove {
+            kind.serialize_into(buf);
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        let Some(stack) = &self.contents else {
+            return 1;
+        };
+        stack.count.encoded_len()
+            + stack.item_id.encoded_len()
+            + VarInt::from(stack.components_to_add.len()).encoded_len()
+            + VarInt::from(stack.components_to_remove.len()).encoded_len()
+            + stack
+                .components_to_add
+                .iter()
+                .map(Parsable::encoded_len)
+                .sum::<usize>()
+            + stack
+                .components_to_remove
+                .iter()
+                .map(Parsable::encoded_len)
+                .sum::<usize>()
+    }
+}
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+/// A non-empty stack of items.
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct ItemStack {
-    // TODO: Item ID
-    pub id: (),
-    pub count: u8,
-    // TODO: NBT
-    pub nbt: (),
+    pub item_id: VarInt,
+    pub count: VarInt,
+    /// Components that differ from the item's defaults.
+    pub components_to_add: Vec<ItemComponent>,
+    /// Ids of default components that the stack doesn't have.
+    pub components_to_remove: Vec<VarInt>,
+}
+impl ItemStack {
+    pub fn new(item_id: VarInt, count: VarInt) -> Self {
+        ItemStack {
+            item_id,
+            count,
+            ..Default::default()
+        }
+    }
+}
+
+/// The price of a trade, which can't have components removed.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ItemCost {
+    pub item_id: VarInt,
+    pub count: VarInt,
+    /// Components the offered item must have.
+    pub components: Vec<ItemComponent>,
+}
+impl Parsable for ItemCost {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, item_id) = VarInt::parse(data)?;
+        let (data, count) = VarInt::parse(data)?;
+        let (data, components) = Vec::parse(data)?;
+        Ok((
+            data,
+            ItemCost {
+                item_id,
+                count,
+                components,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.item_id.serialize_into(buf);
+        self.count.serialize_into(buf);
+        self.components.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.item_id.encoded_len() + self.count.encoded_len() + self.components.encoded_len()
+    }
+}
+
+/// A slot as sent by the client, where item components are replaced by their hashes.
+///
+/// The server compares these against its own copy of the slot.
+pub type HashedSlot = PrefixedOptional<HashedItemStack>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct HashedItemStack {
+    pub item_id: VarInt,
+    pub count: VarInt,
+    /// Pairs of component type and CRC32C hash of the component's data.
+    pub components_to_add: Vec<(VarInt, i32)>,
+    pub components_to_remove: Vec<VarInt>,
+}
+impl Parsable for HashedItemStack {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, item_id) = VarInt::parse(data)?;
+        let (data, count) = VarInt::parse(data)?;
+        let (data, components_to_add) = Vec::parse(data)?;
+        let (data, components_to_remove) = Vec::parse(data)?;
+        Ok((
+            data,
+            HashedItemStack {
+                item_id,
+                count,
+                components_to_add,
+                components_to_remove,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.item_id.serialize_into(buf);
+        self.count.serialize_into(buf);
+        self.components_to_add.serialize_into(buf);
+        self.components_to_remove.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.item_id.encoded_len()
+            + self.count.encoded_len()
+            + self.components_to_add.encoded_len()
+            + self.components_to_remove.encoded_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::text::TextComponent;
+
+    #[test]
+    fn empty_slot_works() {
+        assert_eq!(Slot::empty().serialize(), [0x00]);
+        assert_eq!(
+            Slot::parse(&[0x00, 0x01]).unwrap(),
+            (&[0x01][..], Slot::empty())
+        );
+    }
+    #[test]
+    fn slot_components_work() {
+        let slot = Slot::from(ItemStack {
+            item_id: VarInt::from(900),
+            count: VarInt::from(1),
+            components_to_add: vec![
+                ItemComponent::CustomName(TextComponent::text("Box")),
+                ItemComponent::Container(vec![
+                    Slot::empty(),
+                    ItemStack::new(VarInt::from(1), VarInt::from(64)).into(),
+                ]),
+            ],
+            components_to_remove: vec![VarInt::from(9)],
+        });
+        let bytes = slot.serialize();
+        assert_eq!(
+            bytes[..6],
+            [
+                0x01, // count: VarInt
+                0x84, 0x07, // item_id: VarInt
+                0x02, // number of components added
+                0x01, // number of components removed
+                0x05, // minecraft:custom_name
+            ]
+        );
+        assert!(bytes.ends_with(&[
+            0x42, // minecraft:container
+            0x02, // number of slots
+            0x00, // empty slot
+            0x40, 0x01, 0x00, 0x00, // 64 of item 1, without changed components
+            0x09, // removed minecraft:rarity
+        ]));
+        assert_eq!(Slot::parse(&bytes).unwrap(), (&[][..], slot));
+    }
+    #[test]
+    fn unknown_components_fail() {
+        // minecraft:can_place_on isn't modelled, and its data can't be skipped.
+        assert!(Slot::parse(&[0x01, 0x01, 0x01, 0x00, 0x0b, 0x00]).is_err());
+    }
+    #[test]
+    fn item_cost_works() {
+        let cost = ItemCost {
+            item_id: VarInt::from(5),
+            count: VarInt::from(3),
+            components: vec![ItemComponent::Damage(VarInt::from(0))],
+        };
+        let bytes = [0x05, 0x03, 0x01, 0x03, 0x00];
+        assert_eq!(cost.serialize(), bytes);
+        assert_eq!(ItemCost::parse(&bytes).unwrap(), (&[][..], cost));
+    }
 }
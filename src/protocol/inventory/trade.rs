@@ -0,0 +1,73 @@
+use super::slot::{ItemCost, Slot};
+use crate::protocol::parsing::{BufMut, IResult, Parsable, PrefixedOptional};
+
+/// A trade offered by a villager or wandering trader.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MerchantOffer {
+    pub input_1: ItemCost,
+    pub output: Slot,
+    pub input_2: PrefixedOptional<ItemCost>,
+    /// Whether the trade has been used up until the merchant restocks.
+    pub disabled: bool,
+    pub uses: i32,
+    pub max_uses: i32,
+    pub experience: i32,
+    /// Added to the count of the first input, such as a discount for curing a zombie villager.
+    pub special_price: i32,
+    pub price_multiplier: f32,
+    pub demand: i32,
+}
+impl Parsable for MerchantOffer {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, input_1) = ItemCost::parse(data)?;
+        let (data, output) = Slot::parse(data)?;
+        let (data, input_2) = PrefixedOptional::parse(data)?;
+        let (data, disabled) = bool::parse(data)?;
+        let (data, uses) = i32::parse(data)?;
+        let (data, max_uses) = i32::parse(data)?;
+        let (data, experience) = i32::parse(data)?;
+        let (data, special_price) = i32::parse(data)?;
+        let (data, price_multiplier) = f32::parse(data)?;
+        let (data, demand) = i32::parse(data)?;
+        Ok((
+            data,
+            MerchantOffer {
+                input_1,
+                output,
+                input_2,
+                disabled,
+                uses,
+                max_uses,
+                experience,
+                special_price,
+                price_multiplier,
+                demand,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.input_1.serialize_into(buf);
+        self.output.serialize_into(buf);
+        self.input_2.serialize_into(buf);
+        self.disabled.serialize_into(buf);
+        self.uses.serialize_into(buf);
+        self.max_uses.serialize_into(buf);
+        self.experience.serialize_into(buf);
+        self.special_price.serialize_into(buf);
+        self.price_multiplier.serialize_into(buf);
+        self.demand.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.input_1.encoded_len()
+            + self.output.encoded_len()
+            + self.input_2.encoded_len()
+            + self.disabled.encoded_len()
+            + self.uses.encoded_len()
+            + self.max_uses.encoded_len()
+            + self.experience.encoded_len()
+            + self.special_price.encoded_len()
+            + self.price_multiplier.encoded_len()
+            + self.demand.encoded_len()
+    }
+}
@@ -0,0 +1,181 @@
+use nom::{
+    bytes::streaming::{tag, take},
+    combinator::map_res,
+    multi::count,
+    number::streaming::{be_i16, be_i32, be_u16, u8 as parse_u8},
+    IResult,
+};
+
+/// The first byte of every legacy ping.
+///
+/// A modern handshake's frame length also starts with this byte when its low seven bits
+/// are 0x7e, so only the bytes after it tell the two apart.
+pub const LEGACY_PING_ID: u8 = 0xfe;
+/// The first byte of the response, which old clients read as a kick packet.
+const KICK_PACKET_ID: u8 = 0xff;
+/// The protocol version sent to legacy clients, which none of them speak.
+///
+/// Clients show the version name as outdated instead of trying to join.
+const LEGACY_PROTOCOL_VERSION: i32 = 127;
+
+/// A server list ping in one of the formats used before 1.7.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LegacyPing {
+    /// Beta 1.8 to 1.3 send only the packet id.
+    Beta,
+    /// 1.4 and 1.5 add a payload byte.
+    V1_4,
+    /// 1.6 adds a plugin message with the address the client connected to.
+    V1_6 {
+        protocol_version: u8,
+        host: String,
+        port: i32,
+    },
+}
+impl LegacyPing {
+    /// Parse a ping from everything the client has sent so far.
+    ///
+    /// Like vanilla, a lone packet id is taken to be a beta ping,
+    /// and returns `Incomplete` only while the 1.6 plugin message is arriving.
+    pub fn parse(input: &[u8]) -> IResult<&[u8], LegacyPing> {
+        let (input, _) = tag([LEGACY_PING_ID])(input)?;
+        if input.is_empty() {
+            return Ok((input, LegacyPing::Beta));
+        }
+        let (input, _) = tag([0x01])(input)?;
+        if input.is_empty() {
+            return Ok((input, LegacyPing::V1_4));
+        }
+        let (input, _) = tag([0xfa])(input)?;
+        let (input, _channel) = map_res(parse_utf16, |channel| {
+            if channel == "MC|PingHost" {
+                Ok(channel)
+            } else {
+                Err(())
+            }
+        })(input)?;
+        let (input, data_len) = be_i16(input)?;
+        let (input, data) = take(data_len.max(0) as usize)(input)?;
+        let (data, protocol_version) = parse_u8(data)?;
+        let (data, host) = parse_utf16(data)?;
+        let (_, port) = be_i32(data)?;
+        Ok((
+            input,
+            LegacyPing::V1_6 {
+                protocol_version,
+                host,
+                port,
+            },
+        ))
+    }
+}
+
+/// The server list information sent in reply to a legacy ping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegacyStatus {
+    pub game_version: String,
+    pub motd: String,
+    pub online_players: usize,
+    pub max_players: usize,
+}
+impl LegacyStatus {
+    /// Serialize the kick packet that answers `ping`.
+    pub fn serialize(&self, ping: &LegacyPing) -> Vec<u8> {
+        let response = match ping {
+            // Beta clients split on `§`, so it can't appear in the motd.
+            LegacyPing::Beta => format!(
+                "{}§{}§{}",
+                self.motd.replace('§', ""),
+                self.online_players,
+                self.max_players
+            ),
+            LegacyPing::V1_4 | LegacyPing::V1_6 { .. } => format!(
+                "§1\0{}\0{}\0{}\0{}\0{}",
+                LEGACY_PROTOCOL_VERSION,
+                self.game_version,
+                self.motd,
+                self.online_players,
+                self.max_players
+            ),
+        };
+        let mut output = vec![KICK_PACKET_ID];
+        output.extend(serialize_utf16(&response));
+        output
+    }
+}
+
+/// Legacy strings are a big-endian count of UTF-16 code units, followed by the units.
+fn parse_utf16(input: &[u8]) -> IResult<&[u8], String> {
+    let (input, len) = be_i16(input)?;
+    map_res(count(be_u16, len.max(0) as usize), |units| {
+        String::from_utf16(&units)
+    })(input)
+}
+fn serialize_utf16(value: &str) -> Vec<u8> {
+    let units = value.encode_utf16().collect::<Vec<_>>();
+    let mut output = (units.len() as i16).to_be_bytes().to_vec();
+    output.extend(units.iter().flat_map(|unit| unit.to_be_bytes()));
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status() -> LegacyStatus {
+        LegacyStatus {
+            game_version: "1.21.5".to_owned(),
+            motd: "A §6server".to_owned(),
+            online_players: 3,
+            max_players: 20,
+        }
+    }
+
+    #[test]
+    fn legacy_pings_parse() {
+        assert_eq!(LegacyPing::parse(&[0xfe]).unwrap().1, LegacyPing::Beta);
+        assert_eq!(
+            LegacyPing::parse(&[0xfe, 0x01]).unwrap().1,
+            LegacyPing::V1_4
+        );
+
+        let mut ping = vec![0xfe, 0x01, 0xfa];
+        ping.extend(serialize_utf16("MC|PingHost"));
+        let mut data = vec![78];
+        data.extend(serialize_utf16("localhost"));
+        data.extend(25565i32.to_be_bytes());
+        ping.extend((data.len() as i16).to_be_bytes());
+        ping.extend(data);
+        assert_eq!(
+            LegacyPing::parse(&ping).unwrap(),
+            (
+                &[][..],
+                LegacyPing::V1_6 {
+                    protocol_version: 78,
+                    host: "localhost".to_owned(),
+                    port: 25565,
+                }
+            )
+        );
+        assert!(matches!(
+            LegacyPing::parse(&ping[..ping.len() - 1]),
+            Err(nom::Err::Incomplete(_))
+        ));
+        assert!(LegacyPing::parse(&[0x10, 0x00]).is_err());
+    }
+    #[test]
+    fn legacy_responses_work() {
+        let beta = status().serialize(&LegacyPing::Beta);
+        assert_eq!(beta[0], KICK_PACKET_ID);
+        assert_eq!(parse_utf16(&beta[1..]).unwrap().1, "A 6server§3§20");
+
+        let response = status().serialize(&LegacyPing::V1_4);
+        assert_eq!(
+            parse_utf16(&response[1..]).unwrap(),
+            (
+                &[][..],
+                "§1\u{0}127\u{0}1.21.5\u{0}A §6server\u{0}3\u{0}20".to_owned()
+            )
+        );
+    }
+}
@@ -1,5 +1,9 @@
+/// Advancements and their progress.
+pub mod advancement;
 /// Implementation of Minecraft's blocks.
 pub mod blocks;
+/// The command graph sent to clients for parsing and suggestions.
+pub mod commands;
 /// Protocol encryption.
 pub mod encryption;
 /// Implementation of Minecraft's entities.
@@ -8,6 +12,10 @@ pub mod entities;
 pub mod error;
 /// Implementation of Minecraft's items and inventories.
 pub mod inventory;
+/// The server list ping used by clients before 1.7.
+pub mod legacy_ping;
+/// Named Binary Tag, Minecraft's binary data format.
+pub mod nbt;
 /// Network packets.
 ///
 /// Packet names are as found on [wiki.vg](https://wiki.vg/Protocol)
@@ -15,11 +23,19 @@ pub mod inventory;
 pub mod packets;
 /// Useful shared parsing functions.
 pub mod parsing;
+/// Registries synchronized with the client during configuration.
+pub mod registry;
+/// Scoreboard objectives and teams.
+pub mod scoreboard;
+/// Formatted text, as used in chat and disconnect messages.
+pub mod text;
 /// Useful types for representing the Minecraft protocol.
 pub mod types;
+/// Protocol versions supported at runtime.
+pub mod version;
 
 pub use error::{Error, Result};
-use types::VarInt;
+pub use version::ProtocolVersion;
 
 /// Enum representation of the connection's current state.
 ///
@@ -47,23 +63,24 @@ pub enum ClientState {
     /// The client has disconnected, and the connection struct should be removed. No packets should be sent or received.
     Disconnected,
 }
-impl parsing::Parsable for ClientState {
-    fn parse(data: &[u8]) -> nom::IResult<&[u8], Self>
-    where
-        Self: Sized,
-    {
-        nom::combinator::map_res(VarInt::parse, |next_state: VarInt| match *next_state {
-            1 => Ok(ClientState::Status),
-            2 => Ok(ClientState::Login),
-            _ => Err(()),
-        })(data)
+
+crate::varint_enum! {
+    /// Why the client connected, as sent in its handshake.
+    #[derive(Default)]
+    pub enum HandshakeIntent {
+        #[default]
+        Status = 1,
+        Login = 2,
+        /// The client is logging in because another server transferred it here.
+        Transfer = 3,
     }
-    fn serialize(&self) -> Vec<u8> {
-        let byte = match &self {
-            ClientState::Status => 1,
-            ClientState::Login => 2,
-            _ => 0,
-        };
-        vec![byte]
+}
+impl HandshakeIntent {
+    /// The state the connection moves to after the handshake.
+    pub fn next_state(&self) -> ClientState {
+        match self {
+            HandshakeIntent::Status => ClientState::Status,
+            HandshakeIntent::Login | HandshakeIntent::Transfer => ClientState::Login,
+        }
     }
 }
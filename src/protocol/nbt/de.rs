@@ -0,0 +1,299 @@
+use super::{Compound, Error, Tag, BYTE_ARRAY, INT_ARRAY, LONG_ARRAY};
+use serde::de::{
+    self,
+    value::{MapDeserializer, SeqDeserializer},
+    DeserializeOwned, Deserializer as _, IntoDeserializer, Visitor,
+};
+
+/// Newtype name used by `Tag`'s `Deserialize` impl to ask for arrays to be kept as arrays.
+const TAG: &str = "__nbt_tag";
+
+/// Convert an NBT tag into a Rust value.
+///
+/// Any numeric tag can be read into a numeric type it fits in,
+/// and byte tags can be read as booleans.
+pub fn from_tag<T: DeserializeOwned>(tag: Tag) -> Result<T, Error> {
+    T::deserialize(Deserializer { tag, raw: false })
+}
+
+pub struct Deserializer {
+    tag: Tag,
+    /// Whether arrays should be marked so that a `Tag` can tell them apart from lists.
+    raw: bool,
+}
+impl Deserializer {
+    fn visit_array<'de, V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        list: Vec<Tag>,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        if self.raw {
+            visit_map(std::iter::once((name.to_owned(), Tag::List(list))), visitor)
+        } else {
+            visit_seq(list, visitor)
+        }
+    }
+}
+impl<'de> de::Deserializer<'de> for Deserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.tag {
+            Tag::End => visitor.visit_unit(),
+            Tag::Byte(v) => visitor.visit_i8(v),
+            Tag::Short(v) => visitor.visit_i16(v),
+            Tag::Int(v) => visitor.visit_i32(v),
+            Tag::Long(v) => visitor.visit_i64(v),
+            Tag::Float(v) => visitor.visit_f32(v),
+            Tag::Double(v) => visitor.visit_f64(v),
+            Tag::String(v) => visitor.visit_string(v),
+            Tag::List(v) => visit_seq(v, visitor),
+            Tag::Compound(v) => visit_map(v.into_iter(), visitor),
+            Tag::ByteArray(ref v) => {
+                let list = v.iter().copied().map(Tag::Byte).collect();
+                self.visit_array(BYTE_ARRAY, list, visitor)
+            }
+            Tag::IntArray(ref v) => {
+                let list = v.iter().copied().map(Tag::Int).collect();
+                self.visit_array(INT_ARRAY, list, visitor)
+            }
+            Tag::LongArray(ref v) => {
+                let list = v.iter().copied().map(Tag::Long).collect();
+                self.visit_array(LONG_ARRAY, list, visitor)
+            }
+        }
+    }
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.tag {
+            Tag::Byte(v) => visitor.visit_bool(v != 0),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.tag {
+            Tag::Byte(v) => visitor.visit_u8(v as u8),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.tag {
+            Tag::Short(v) => visitor.visit_u16(v as u16),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.tag {
+            Tag::Int(v) => visitor.visit_u32(v as u32),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.tag {
+            Tag::Long(v) => visitor.visit_u64(v as u64),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.tag {
+            Tag::ByteArray(v) => visitor.visit_byte_buf(v.into_iter().map(|b| b as u8).collect()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_bytes(visitor)
+    }
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.tag {
+            Tag::End => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(Deserializer {
+            tag: self.tag,
+            raw: name == TAG,
+        })
+    }
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.tag {
+            Tag::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Tag::Compound(compound) if compound.len() == 1 => {
+                let (variant, value) = compound.into_iter().next().expect("compound has an entry");
+                visitor.visit_enum(EnumDeserializer { variant, value })
+            }
+            other => Err(Error::UnexpectedTag {
+                expected: "enum",
+                found: other.id(),
+            }),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+impl<'de> IntoDeserializer<'de, Error> for Tag {
+    type Deserializer = Deserializer;
+    fn into_deserializer(self) -> Deserializer {
+        Deserializer {
+            tag: self,
+            raw: false,
+        }
+    }
+}
+
+fn visit_seq<'de, V: Visitor<'de>>(list: Vec<Tag>, visitor: V) -> Result<V::Value, Error> {
+    let mut seq = SeqDeserializer::new(list.into_iter());
+    let value = visitor.visit_seq(&mut seq)?;
+    seq.end()?;
+    Ok(value)
+}
+fn visit_map<'de, V: Visitor<'de>>(
+    entries: impl Iterator<Item = (String, Tag)>,
+    visitor: V,
+) -> Result<V::Value, Error> {
+    let mut map = MapDeserializer::new(entries);
+    let value = visitor.visit_map(&mut map)?;
+    map.end()?;
+    Ok(value)
+}
+
+/// Enum variants with data are stored as a compound with a single entry.
+struct EnumDeserializer {
+    variant: String,
+    value: Tag,
+}
+impl<'de> de::EnumAccess<'de> for EnumDeserializer {
+    type Error = Error;
+    type Variant = Tag;
+    fn variant_seed<S: de::DeserializeSeed<'de>>(self, seed: S) -> Result<(S::Value, Tag), Error> {
+        let variant =
+            seed.deserialize(IntoDeserializer::<Error>::into_deserializer(self.variant))?;
+        Ok((variant, self.value))
+    }
+}
+impl<'de> de::VariantAccess<'de> for Tag {
+    type Error = Error;
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+    fn newtype_variant_seed<S: de::DeserializeSeed<'de>>(self, seed: S) -> Result<S::Value, Error> {
+        seed.deserialize(self.into_deserializer())
+    }
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.into_deserializer().deserialize_seq(visitor)
+    }
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.into_deserializer().deserialize_map(visitor)
+    }
+}
+
+impl<'de> de::Deserialize<'de> for Tag {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Tag, D::Error> {
+        deserializer.deserialize_newtype_struct(TAG, TagVisitor)
+    }
+}
+struct TagVisitor;
+impl<'de> Visitor<'de> for TagVisitor {
+    type Value = Tag;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("an nbt tag")
+    }
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Tag, E> {
+        Ok(Tag::Byte(v as i8))
+    }
+    fn visit_i8<E: de::Error>(self, v: i8) -> Result<Tag, E> {
+        Ok(Tag::Byte(v))
+    }
+    fn visit_i16<E: de::Error>(self, v: i16) -> Result<Tag, E> {
+        Ok(Tag::Short(v))
+    }
+    fn visit_i32<E: de::Error>(self, v: i32) -> Result<Tag, E> {
+        Ok(Tag::Int(v))
+    }
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Tag, E> {
+        Ok(Tag::Long(v))
+    }
+    fn visit_u8<E: de::Error>(self, v: u8) -> Result<Tag, E> {
+        Ok(Tag::Byte(v as i8))
+    }
+    fn visit_u16<E: de::Error>(self, v: u16) -> Result<Tag, E> {
+        Ok(Tag::Short(v as i16))
+    }
+    fn visit_u32<E: de::Error>(self, v: u32) -> Result<Tag, E> {
+        Ok(Tag::Int(v as i32))
+    }
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Tag, E> {
+        Ok(Tag::Long(v as i64))
+    }
+    fn visit_f32<E: de::Error>(self, v: f32) -> Result<Tag, E> {
+        Ok(Tag::Float(v))
+    }
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Tag, E> {
+        Ok(Tag::Double(v))
+    }
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Tag, E> {
+        Ok(Tag::String(v.to_owned()))
+    }
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Tag, E> {
+        Ok(Tag::String(v))
+    }
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Tag, E> {
+        Ok(Tag::ByteArray(v.iter().map(|b| *b as i8).collect()))
+    }
+    fn visit_none<E: de::Error>(self) -> Result<Tag, E> {
+        Ok(Tag::End)
+    }
+    fn visit_unit<E: de::Error>(self) -> Result<Tag, E> {
+        Ok(Tag::End)
+    }
+    fn visit_some<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Tag, D::Error> {
+        de::Deserialize::deserialize(deserializer)
+    }
+    fn visit_newtype_struct<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Tag, D::Error> {
+        deserializer.deserialize_any(TagVisitor)
+    }
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Tag, A::Error> {
+        let mut list: Vec<Tag> = vec![];
+        while let Some(element) = seq.next_element::<Tag>()? {
+            if list.first().is_some_and(|first| first.id() != element.id()) {
+                return Err(de::Error::custom(Error::MixedList));
+            }
+            list.push(element);
+        }
+        Ok(Tag::List(list))
+    }
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Tag, A::Error> {
+        let mut compound = Compound::new();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                BYTE_ARRAY => return Ok(Tag::ByteArray(map.next_value()?)),
+                INT_ARRAY => return Ok(Tag::IntArray(map.next_value()?)),
+                LONG_ARRAY => return Ok(Tag::LongArray(map.next_value()?)),
+                _ => {
+                    compound.insert(key, map.next_value()?);
+                }
+            }
+        }
+        Ok(Tag::Compound(compound))
+    }
+}
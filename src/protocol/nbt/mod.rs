@@ -0,0 +1,625 @@
+/// Converting NBT into Rust types.
+mod de;
+/// Java's modified UTF-8, used for NBT strings.
+///
+/// It differs from UTF-8 in that the null character is encoded as two bytes
+/// and characters outside the BMP are encoded as two three-byte surrogates.
+mod mutf8;
+/// Converting Rust types into NBT.
+mod ser;
+/// Stringified NBT, the text format used in commands and data packs.
+mod snbt;
+
+pub use de::from_tag;
+pub use ser::to_tag;
+pub use snbt::{from_snbt, SnbtError};
+
+use crate::protocol::parsing::{BufMut, IResult, Parsable};
+use flate2::{
+    read::{GzDecoder, ZlibDecoder},
+    write::{GzEncoder, ZlibEncoder},
+};
+use indexmap::IndexMap;
+use nom::{bytes::streaming::take, combinator::fail};
+use std::io::{Read, Write};
+
+/// The deepest that lists and compounds can be nested, matching vanilla.
+pub const MAX_DEPTH: usize = 512;
+
+/// Newtype names that tell the serializer to write an array instead of a list.
+///
+/// These are also reserved as compound keys when deserializing a `Tag`.
+const BYTE_ARRAY: &str = "__nbt_byte_array";
+const INT_ARRAY: &str = "__nbt_int_array";
+const LONG_ARRAY: &str = "__nbt_long_array";
+
+/// Errors from converting NBT to or from files and Rust types.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    /// A custom error from a `Serialize` or `Deserialize` implementation.
+    #[error("{0}")]
+    Message(String),
+    /// The data was not valid NBT.
+    #[error("invalid nbt data")]
+    Parsing,
+    /// The file could not be compressed or decompressed.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// A list contained tags of more than one type.
+    #[error("list elements must all be the same type")]
+    MixedList,
+    /// A compound key was not a string.
+    #[error("compound keys must be strings")]
+    KeyNotString,
+    /// The tag was not the type that was expected.
+    #[error("expected {expected}, found tag type {found}")]
+    UnexpectedTag { expected: &'static str, found: u8 },
+}
+impl serde::ser::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Error::Message(msg.to_string())
+    }
+}
+impl serde::de::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Error::Message(msg.to_string())
+    }
+}
+
+/// The entries of a compound tag, in insertion order.
+pub type Compound = IndexMap<String, Tag>;
+
+/// A single NBT value.
+///
+/// Lists must only contain tags of a single type.
+/// `End` is only valid as the root of a network tag, where it means "no data".
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Tag {
+    #[default]
+    End,
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(Compound),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+impl Tag {
+    /// The type id that precedes this tag on the wire.
+    pub fn id(&self) -> u8 {
+        match self {
+            Tag::End => 0,
+            Tag::Byte(_) => 1,
+            Tag::Short(_) => 2,
+            Tag::Int(_) => 3,
+            Tag::Long(_) => 4,
+            Tag::Float(_) => 5,
+            Tag::Double(_) => 6,
+            Tag::ByteArray(_) => 7,
+            Tag::String(_) => 8,
+            Tag::List(_) => 9,
+            Tag::Compound(_) => 10,
+            Tag::IntArray(_) => 11,
+            Tag::LongArray(_) => 12,
+        }
+    }
+    pub fn as_compound(&self) -> Option<&Compound> {
+        match self {
+            Tag::Compound(compound) => Some(compound),
+            _ => None,
+        }
+    }
+    pub fn as_compound_mut(&mut self) -> Option<&mut Compound> {
+        match self {
+            Tag::Compound(compound) => Some(compound),
+            _ => None,
+        }
+    }
+
+    /// Parse the payload of a tag with the given type id.
+    ///
+    /// Only lists and compounds are handled here, to keep the recursive stack frames small.
+    fn parse_payload(id: u8, depth: usize, data: &[u8]) -> IResult<&[u8], Tag> {
+        if depth > MAX_DEPTH {
+            return fail(data);
+        }
+        match id {
+            9 => {
+                let (mut data, element_id) = u8::parse(data)?;
+                let (d, len) = parse_length(data)?;
+                data = d;
+                if element_id == 0 && len > 0 {
+                    return fail(data);
+                }
+                let mut list = vec![];
+                for _ in 0..len {
+                    let (d, element) = Tag::parse_payload(element_id, depth + 1, data)?;
+                    data = d;
+                    list.push(element);
+                }
+                Ok((data, Tag::List(list)))
+            }
+            10 => {
+                let mut data = data;
+                let mut compound = Compound::new();
+                loop {
+                    let (d, entry_id) = u8::parse(data)?;
+                    if entry_id == 0 {
+                        return Ok((d, Tag::Compound(compound)));
+                    }
+                    let (d, name) = parse_string(d)?;
+                    let (d, entry) = Tag::parse_payload(entry_id, depth + 1, d)?;
+                    data = d;
+                    compound.insert(name, entry);
+                }
+            }
+            _ => Tag::parse_leaf(id, data),
+        }
+    }
+    /// Parse the payload of a tag that can't contain other tags.
+    fn parse_leaf(id: u8, data: &[u8]) -> IResult<&[u8], Tag> {
+        match id {
+            1 => nom::combinator::map(i8::parse, Tag::Byte)(data),
+            2 => nom::combinator::map(i16::parse, Tag::Short)(data),
+            3 => nom::combinator::map(i32::parse, Tag::Int)(data),
+            4 => nom::combinator::map(i64::parse, Tag::Long)(data),
+            5 => nom::combinator::map(f32::parse, Tag::Float)(data),
+            6 => nom::combinator::map(f64::parse, Tag::Double)(data),
+            7 => {
+                let (data, len) = parse_length(data)?;
+                let (data, bytes) = take(len)(data)?;
+                Ok((
+                    data,
+                    Tag::ByteArray(bytes.iter().map(|b| *b as i8).collect()),
+                ))
+            }
+            8 => nom::combinator::map(parse_string, Tag::String)(data),
+            11 => {
+                let (data, len) = parse_length(data)?;
+                let (data, ints) = i32::parse_repeated(len, data)?;
+                Ok((data, Tag::IntArray(ints)))
+            }
+            12 => {
+                let (data, len) = parse_length(data)?;
+                let (data, longs) = i64::parse_repeated(len, data)?;
+                Ok((data, Tag::LongArray(longs)))
+            }
+            _ => fail(data),
+        }
+    }
+    /// Serialize the payload of the tag, without its type id.
+    fn serialize_payload(&self, buf: &mut impl BufMut) {
+        match self {
+            Tag::List(list) => {
+                buf.put_u8(list.first().map(Tag::id).unwrap_or(0));
+                (list.len() as i32).serialize_into(buf);
+                for element in list {
+                    element.serialize_payload(buf);
+                }
+            }
+            Tag::Compound(compound) => {
+                for (name, entry) in compound {
+                    buf.put_u8(entry.id());
+                    serialize_string(name, buf);
+                    entry.serialize_payload(buf);
+                }
+                buf.put_u8(0);
+            }
+            _ => self.serialize_leaf(buf),
+        }
+    }
+    /// Serialize the payload of a tag that can't contain other tags.
+    fn serialize_leaf(&self, buf: &mut impl BufMut) {
+        match self {
+            Tag::End | Tag::List(_) | Tag::Compound(_) => {}
+            Tag::Byte(v) => v.serialize_into(buf),
+            Tag::Short(v) => v.serialize_into(buf),
+            Tag::Int(v) => v.serialize_into(buf),
+            Tag::Long(v) => v.serialize_into(buf),
+            Tag::Float(v) => v.serialize_into(buf),
+            Tag::Double(v) => v.serialize_into(buf),
+            Tag::ByteArray(bytes) => {
+                (bytes.len() as i32).serialize_into(buf);
+                for b in bytes {
+                    b.serialize_into(buf);
+                }
+            }
+            Tag::String(s) => serialize_string(s, buf),
+            Tag::IntArray(ints) => {
+                (ints.len() as i32).serialize_into(buf);
+                for v in ints {
+                    v.serialize_into(buf);
+                }
+            }
+            Tag::LongArray(longs) => {
+                (longs.len() as i32).serialize_into(buf);
+                for v in longs {
+                    v.serialize_into(buf);
+                }
+            }
+        }
+    }
+    /// The number of bytes that `serialize_payload` produces.
+    fn payload_len(&self) -> usize {
+        match self {
+            Tag::End => 0,
+            Tag::Byte(_) => 1,
+            Tag::Short(_) => 2,
+            Tag::Int(_) | Tag::Float(_) => 4,
+            Tag::Long(_) | Tag::Double(_) => 8,
+            Tag::ByteArray(bytes) => 4 + bytes.len(),
+            Tag::String(s) => string_len(s),
+            Tag::List(list) => 1 + 4 + list.iter().map(Tag::payload_len).sum::<usize>(),
+            Tag::Compound(compound) => {
+                compound
+                    .iter()
+                    .map(|(name, entry)| 1 + string_len(name) + entry.payload_len())
+                    .sum::<usize>()
+                    + 1
+            }
+            Tag::IntArray(ints) => 4 + 4 * ints.len(),
+            Tag::LongArray(longs) => 4 + 8 * longs.len(),
+        }
+    }
+}
+/// The network form used since 1.20.2: a type id followed by the payload, with no root name.
+impl Parsable for Tag {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, id) = u8::parse(data)?;
+        if id == 0 {
+            return Ok((data, Tag::End));
+        }
+        Tag::parse_payload(id, 0, data)
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        buf.put_u8(self.id());
+        self.serialize_payload(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        1 + self.payload_len()
+    }
+}
+impl From<Compound> for Tag {
+    fn from(value: Compound) -> Self {
+        Tag::Compound(value)
+    }
+}
+impl From<String> for Tag {
+    fn from(value: String) -> Self {
+        Tag::String(value)
+    }
+}
+impl From<&str> for Tag {
+    fn from(value: &str) -> Self {
+        Tag::String(value.to_owned())
+    }
+}
+impl From<bool> for Tag {
+    fn from(value: bool) -> Self {
+        Tag::Byte(value as i8)
+    }
+}
+impl From<i8> for Tag {
+    fn from(value: i8) -> Self {
+        Tag::Byte(value)
+    }
+}
+impl From<i16> for Tag {
+    fn from(value: i16) -> Self {
+        Tag::Short(value)
+    }
+}
+impl From<i32> for Tag {
+    fn from(value: i32) -> Self {
+        Tag::Int(value)
+    }
+}
+impl From<i64> for Tag {
+    fn from(value: i64) -> Self {
+        Tag::Long(value)
+    }
+}
+impl From<f32> for Tag {
+    fn from(value: f32) -> Self {
+        Tag::Float(value)
+    }
+}
+impl From<f64> for Tag {
+    fn from(value: f64) -> Self {
+        Tag::Double(value)
+    }
+}
+
+/// A `Vec<i8>` that is serialized as a byte array tag instead of a list.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ByteArray(pub Vec<i8>);
+impl serde::Serialize for ByteArray {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(BYTE_ARRAY, &self.0)
+    }
+}
+impl<'de> serde::Deserialize<'de> for ByteArray {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(ByteArray)
+    }
+}
+/// A `Vec<i32>` that is serialized as an int array tag instead of a list.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct IntArray(pub Vec<i32>);
+impl serde::Serialize for IntArray {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(INT_ARRAY, &self.0)
+    }
+}
+impl<'de> serde::Deserialize<'de> for IntArray {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(IntArray)
+    }
+}
+/// A `Vec<i64>` that is serialized as a long array tag instead of a list.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LongArray(pub Vec<i64>);
+impl serde::Serialize for LongArray {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(LONG_ARRAY, &self.0)
+    }
+}
+impl<'de> serde::Deserialize<'de> for LongArray {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(LongArray)
+    }
+}
+
+/// How an NBT file is compressed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Compression {
+    None,
+    #[default]
+    Gzip,
+    Zlib,
+}
+
+/// A tag with a name, as used in files and in the network form before 1.20.2.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NamedTag {
+    pub name: String,
+    pub tag: Tag,
+}
+impl NamedTag {
+    pub fn new(name: impl Into<String>, tag: impl Into<Tag>) -> NamedTag {
+        NamedTag {
+            name: name.into(),
+            tag: tag.into(),
+        }
+    }
+    /// Read an NBT file, detecting gzip or zlib compression from its header.
+    pub fn from_file(data: &[u8]) -> Result<NamedTag, Error> {
+        let mut decompressed = vec![];
+        let data = match data {
+            [0x1f, 0x8b, ..] => {
+                GzDecoder::new(data).read_to_end(&mut decompressed)?;
+                &decompressed[..]
+            }
+            [0x78, _, ..] => {
+                ZlibDecoder::new(data).read_to_end(&mut decompressed)?;
+                &decompressed[..]
+            }
+            _ => data,
+        };
+        match NamedTag::parse(data) {
+            Ok((_, named_tag)) => Ok(named_tag),
+            Err(_) => Err(Error::Parsing),
+        }
+    }
+    /// Write an NBT file with the given compression.
+    pub fn to_file(&self, compression: Compression) -> Result<Vec<u8>, Error> {
+        let data = self.serialize();
+        match compression {
+            Compression::None => Ok(data),
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(vec![], flate2::Compression::default());
+                encoder.write_all(&data)?;
+                Ok(encoder.finish()?)
+            }
+            Compression::Zlib => {
+                let mut encoder = ZlibEncoder::new(vec![], flate2::Compression::default());
+                encoder.write_all(&data)?;
+                Ok(encoder.finish()?)
+            }
+        }
+    }
+}
+impl Parsable for NamedTag {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, id) = u8::parse(data)?;
+        if id == 0 {
+            return Ok((data, NamedTag::default()));
+        }
+        let (data, name) = parse_string(data)?;
+        let (data, tag) = Tag::parse_payload(id, 0, data)?;
+        Ok((data, NamedTag { name, tag }))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        buf.put_u8(self.tag.id());
+        if self.tag != Tag::End {
+            serialize_string(&self.name, buf);
+            self.tag.serialize_payload(buf);
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        match self.tag {
+            Tag::End => 1,
+            _ => 1 + string_len(&self.name) + self.tag.payload_len(),
+        }
+    }
+}
+
+/// Parse the signed 32-bit length of an array or list.
+fn parse_length(data: &[u8]) -> IResult<&[u8], usize> {
+    nom::combinator::map_res(i32::parse, usize::try_from)(data)
+}
+/// Parse a string in Java's modified UTF-8, prefixed with its length as a u16.
+fn parse_string(data: &[u8]) -> IResult<&[u8], String> {
+    let (data, len) = u16::parse(data)?;
+    let (data, bytes) = take(len)(data)?;
+    match mutf8::decode(bytes) {
+        Some(s) => Ok((data, s)),
+        None => fail(data),
+    }
+}
+/// Serialize a string in Java's modified UTF-8, prefixed with its length as a u16.
+pub(crate) fn serialize_string(s: &str, buf: &mut impl BufMut) {
+    let bytes = mutf8::encode(s);
+    (bytes.len() as u16).serialize_into(buf);
+    buf.put_slice(&bytes);
+}
+/// The number of bytes that `serialize_string` produces.
+pub(crate) fn string_len(s: &str) -> usize {
+    2 + mutf8::encoded_len(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_hello_world() -> (NamedTag, &'static [u8]) {
+        (
+            NamedTag::new(
+                "hello world",
+                Compound::from([("name".to_owned(), Tag::from("Bananrama"))]),
+            ),
+            &[
+                0x0a, 0x00, 0x0b, 0x68, 0x65, 0x6c, 0x6c, 0x6f, 0x20, 0x77, 0x6f, 0x72, 0x6c, 0x64,
+                0x08, 0x00, 0x04, 0x6e, 0x61, 0x6d, 0x65, 0x00, 0x09, 0x42, 0x61, 0x6e, 0x61, 0x6e,
+                0x72, 0x61, 0x6d, 0x61, 0x00,
+            ],
+        )
+    }
+
+    #[test]
+    fn named_tag_works() {
+        let (named_tag, bytes) = get_hello_world();
+        assert_eq!(
+            NamedTag::parse(bytes).unwrap(),
+            (&[][..], named_tag.clone())
+        );
+        assert_eq!(named_tag.serialize(), bytes);
+        assert_eq!(named_tag.encoded_len(), bytes.len());
+    }
+    #[test]
+    fn network_tag_omits_root_name() {
+        let (named_tag, bytes) = get_hello_world();
+        let mut network = vec![0x0a];
+        network.extend(&bytes[14..]);
+        assert_eq!(named_tag.tag.serialize(), network);
+        assert_eq!(Tag::parse(&network).unwrap(), (&[][..], named_tag.tag));
+        assert_eq!(Tag::parse(&[0x00]).unwrap(), (&[][..], Tag::End));
+    }
+    #[test]
+    fn every_tag_type_round_trips() {
+        let tag = Tag::Compound(Compound::from([
+            ("byte".to_owned(), Tag::Byte(-1)),
+            ("short".to_owned(), Tag::Short(300)),
+            ("int".to_owned(), Tag::Int(-70000)),
+            ("long".to_owned(), Tag::Long(1 << 40)),
+            ("float".to_owned(), Tag::Float(0.5)),
+            ("double".to_owned(), Tag::Double(-2.25)),
+            ("byte_array".to_owned(), Tag::ByteArray(vec![1, -2, 3])),
+            ("string".to_owned(), Tag::from("nul\0 and 🍌")),
+            (
+                "list".to_owned(),
+                Tag::List(vec![Tag::List(vec![]), Tag::List(vec![Tag::Int(1)])]),
+            ),
+            ("compound".to_owned(), Tag::Compound(Compound::new())),
+            ("int_array".to_owned(), Tag::IntArray(vec![i32::MIN, 0])),
+            ("long_array".to_owned(), Tag::LongArray(vec![i64::MAX])),
+        ]));
+        let bytes = tag.serialize();
+        assert_eq!(tag.encoded_len(), bytes.len());
+        assert_eq!(Tag::parse(&bytes).unwrap(), (&[][..], tag));
+    }
+    #[test]
+    fn nesting_is_limited() {
+        let mut tag = Tag::Int(0);
+        for _ in 0..=MAX_DEPTH {
+            tag = Tag::List(vec![tag]);
+        }
+        assert!(Tag::parse(&tag.serialize()).is_err());
+    }
+    #[test]
+    fn serde_round_trip_works() {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        enum Shape {
+            Point,
+            Circle { radius: f32 },
+        }
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Example {
+            name: String,
+            enabled: bool,
+            count: u8,
+            shapes: Vec<Shape>,
+            heights: LongArray,
+            missing: Option<i32>,
+        }
+
+        let example = Example {
+            name: "example".to_owned(),
+            enabled: true,
+            count: 200,
+            shapes: vec![Shape::Point, Shape::Point],
+            heights: LongArray(vec![1, 2]),
+            missing: None,
+        };
+        let tag = to_tag(&example).unwrap();
+        assert_eq!(
+            tag,
+            Tag::Compound(Compound::from([
+                ("name".to_owned(), Tag::from("example")),
+                ("enabled".to_owned(), Tag::Byte(1)),
+                ("count".to_owned(), Tag::Byte(-56)),
+                (
+                    "shapes".to_owned(),
+                    Tag::List(vec![Tag::from("Point"), Tag::from("Point")])
+                ),
+                ("heights".to_owned(), Tag::LongArray(vec![1, 2])),
+            ]))
+        );
+        assert_eq!(from_tag::<Example>(tag).unwrap(), example);
+
+        let circle = to_tag(&Shape::Circle { radius: 0.5 }).unwrap();
+        assert_eq!(
+            from_tag::<Shape>(circle).unwrap(),
+            Shape::Circle { radius: 0.5 }
+        );
+        assert!(matches!(
+            to_tag(&vec![Shape::Point, Shape::Circle { radius: 1.0 }]),
+            Err(Error::MixedList)
+        ));
+    }
+    #[test]
+    fn tags_keep_their_types_through_serde() {
+        let tag = Tag::Compound(Compound::from([
+            ("ints".to_owned(), Tag::IntArray(vec![1, 2])),
+            ("list".to_owned(), Tag::List(vec![Tag::Int(1), Tag::Int(2)])),
+        ]));
+        assert_eq!(to_tag(&tag).unwrap(), tag);
+        assert_eq!(from_tag::<Tag>(tag.clone()).unwrap(), tag);
+    }
+    #[test]
+    fn compressed_files_work() {
+        let (named_tag, _) = get_hello_world();
+        for compression in [Compression::None, Compression::Gzip, Compression::Zlib] {
+            let file = named_tag.to_file(compression).unwrap();
+            assert_eq!(NamedTag::from_file(&file).unwrap(), named_tag);
+        }
+    }
+}
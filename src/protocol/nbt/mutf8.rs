@@ -0,0 +1,97 @@
+use std::borrow::Cow;
+
+/// Encode a string as modified UTF-8, borrowing it if it's plain ASCII.
+pub fn encode(s: &str) -> Cow<'_, [u8]> {
+    if is_ascii(s) {
+        return Cow::Borrowed(s.as_bytes());
+    }
+    let mut output = Vec::with_capacity(s.len());
+    for unit in s.encode_utf16() {
+        match unit {
+            0x0001..=0x007f => output.push(unit as u8),
+            0x0000 | 0x0080..=0x07ff => {
+                output.push(0xc0 | (unit >> 6) as u8);
+                output.push(0x80 | (unit & 0x3f) as u8);
+            }
+            _ => {
+                output.push(0xe0 | (unit >> 12) as u8);
+                output.push(0x80 | ((unit >> 6) & 0x3f) as u8);
+                output.push(0x80 | (unit & 0x3f) as u8);
+            }
+        }
+    }
+    Cow::Owned(output)
+}
+/// The number of bytes that `encode` produces.
+pub fn encoded_len(s: &str) -> usize {
+    if is_ascii(s) {
+        return s.len();
+    }
+    s.encode_utf16()
+        .map(|unit| match unit {
+            0x0001..=0x007f => 1,
+            0x0000 | 0x0080..=0x07ff => 2,
+            _ => 3,
+        })
+        .sum()
+}
+/// Whether `s` is encoded the same in UTF-8 and modified UTF-8.
+fn is_ascii(s: &str) -> bool {
+    s.bytes().all(|b| b != 0 && b < 0x80)
+}
+
+/// Decode modified UTF-8, returning `None` if it is malformed.
+///
+/// Unpaired surrogates, which Java allows, are replaced with U+FFFD.
+pub fn decode(bytes: &[u8]) -> Option<String> {
+    if bytes.iter().all(|b| *b != 0 && *b < 0x80) {
+        return String::from_utf8(bytes.to_vec()).ok();
+    }
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut bytes = bytes.iter().copied();
+    while let Some(first) = bytes.next() {
+        let unit = match first {
+            0x01..=0x7f => first as u16,
+            0xc0..=0xdf => {
+                let second = continuation(bytes.next())?;
+                ((first as u16 & 0x1f) << 6) | second
+            }
+            0xe0..=0xef => {
+                let second = continuation(bytes.next())?;
+                let third = continuation(bytes.next())?;
+                ((first as u16 & 0x0f) << 12) | (second << 6) | third
+            }
+            _ => return None,
+        };
+        units.push(unit);
+    }
+    Some(String::from_utf16_lossy(&units))
+}
+fn continuation(byte: Option<u8>) -> Option<u16> {
+    match byte {
+        Some(b) if b & 0xc0 == 0x80 => Some(b as u16 & 0x3f),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mutf8_works() {
+        let cases: [(&str, &[u8]); 4] = [
+            ("abc", b"abc"),
+            ("\0", &[0xc0, 0x80]),
+            ("é", &[0xc3, 0xa9]),
+            ("🍌", &[0xed, 0xa0, 0xbc, 0xed, 0xbd, 0x8c]),
+        ];
+        for (s, bytes) in cases {
+            assert_eq!(encode(s), bytes);
+            assert_eq!(encoded_len(s), bytes.len());
+            assert_eq!(decode(bytes).as_deref(), Some(s));
+        }
+        assert_eq!(decode(&[0x00]), None);
+        assert_eq!(decode(&[0xc3]), None);
+    }
+}
@@ -0,0 +1,348 @@
+use super::{Compound, Error, Tag, BYTE_ARRAY, INT_ARRAY, LONG_ARRAY};
+use serde::ser::{self, Serialize};
+
+/// Convert a Rust value into an NBT tag.
+///
+/// Structs and maps become compounds, sequences become lists,
+/// and `None` fields are left out of their compound.
+/// Unsigned integers are stored in the signed tag of the same width.
+pub fn to_tag<T: Serialize + ?Sized>(value: &T) -> Result<Tag, Error> {
+    value.serialize(Serializer)
+}
+
+struct Serializer;
+impl ser::Serializer for Serializer {
+    type Ok = Tag;
+    type Error = Error;
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeVariant<SerializeList>;
+    type SerializeMap = SerializeCompound;
+    type SerializeStruct = SerializeCompound;
+    type SerializeStructVariant = SerializeVariant<SerializeCompound>;
+
+    fn serialize_bool(self, v: bool) -> Result<Tag, Error> {
+        Ok(Tag::Byte(v as i8))
+    }
+    fn serialize_i8(self, v: i8) -> Result<Tag, Error> {
+        Ok(Tag::Byte(v))
+    }
+    fn serialize_i16(self, v: i16) -> Result<Tag, Error> {
+        Ok(Tag::Short(v))
+    }
+    fn serialize_i32(self, v: i32) -> Result<Tag, Error> {
+        Ok(Tag::Int(v))
+    }
+    fn serialize_i64(self, v: i64) -> Result<Tag, Error> {
+        Ok(Tag::Long(v))
+    }
+    fn serialize_u8(self, v: u8) -> Result<Tag, Error> {
+        Ok(Tag::Byte(v as i8))
+    }
+    fn serialize_u16(self, v: u16) -> Result<Tag, Error> {
+        Ok(Tag::Short(v as i16))
+    }
+    fn serialize_u32(self, v: u32) -> Result<Tag, Error> {
+        Ok(Tag::Int(v as i32))
+    }
+    fn serialize_u64(self, v: u64) -> Result<Tag, Error> {
+        Ok(Tag::Long(v as i64))
+    }
+    fn serialize_f32(self, v: f32) -> Result<Tag, Error> {
+        Ok(Tag::Float(v))
+    }
+    fn serialize_f64(self, v: f64) -> Result<Tag, Error> {
+        Ok(Tag::Double(v))
+    }
+    fn serialize_char(self, v: char) -> Result<Tag, Error> {
+        Ok(Tag::String(v.to_string()))
+    }
+    fn serialize_str(self, v: &str) -> Result<Tag, Error> {
+        Ok(Tag::String(v.to_owned()))
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<Tag, Error> {
+        Ok(Tag::ByteArray(v.iter().map(|b| *b as i8).collect()))
+    }
+    fn serialize_none(self) -> Result<Tag, Error> {
+        Ok(Tag::End)
+    }
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Tag, Error> {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<Tag, Error> {
+        Ok(Tag::End)
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Tag, Error> {
+        Ok(Tag::Compound(Compound::new()))
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Tag, Error> {
+        Ok(Tag::String(variant.to_owned()))
+    }
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Tag, Error> {
+        let tag = value.serialize(self)?;
+        let Tag::List(list) = tag else {
+            return Ok(tag);
+        };
+        let list = list.into_iter();
+        match name {
+            BYTE_ARRAY => Ok(Tag::ByteArray(list.map(as_byte).collect::<Result<_, _>>()?)),
+            INT_ARRAY => Ok(Tag::IntArray(list.map(as_int).collect::<Result<_, _>>()?)),
+            LONG_ARRAY => Ok(Tag::LongArray(list.map(as_long).collect::<Result<_, _>>()?)),
+            _ => Ok(Tag::List(list.collect())),
+        }
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Tag, Error> {
+        Ok(wrap_variant(variant, to_tag(value)?))
+    }
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList, Error> {
+        Ok(SerializeList {
+            list: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList, Error> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeList, Error> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeList>, Error> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeCompound, Error> {
+        Ok(SerializeCompound::default())
+    }
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<SerializeCompound, Error> {
+        Ok(SerializeCompound::default())
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeVariant<SerializeCompound>, Error> {
+        Ok(SerializeVariant {
+            variant,
+            inner: SerializeCompound::default(),
+        })
+    }
+}
+
+fn as_byte(tag: Tag) -> Result<i8, Error> {
+    match tag {
+        Tag::Byte(v) => Ok(v),
+        other => Err(unexpected("byte", &other)),
+    }
+}
+fn as_int(tag: Tag) -> Result<i32, Error> {
+    match tag {
+        Tag::Int(v) => Ok(v),
+        other => Err(unexpected("int", &other)),
+    }
+}
+fn as_long(tag: Tag) -> Result<i64, Error> {
+    match tag {
+        Tag::Long(v) => Ok(v),
+        other => Err(unexpected("long", &other)),
+    }
+}
+fn unexpected(expected: &'static str, found: &Tag) -> Error {
+    Error::UnexpectedTag {
+        expected,
+        found: found.id(),
+    }
+}
+
+struct SerializeList {
+    list: Vec<Tag>,
+}
+impl SerializeList {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let tag = to_tag(value)?;
+        if tag == Tag::End {
+            return Err(unexpected("list element", &tag));
+        }
+        if self
+            .list
+            .first()
+            .is_some_and(|first| first.id() != tag.id())
+        {
+            return Err(Error::MixedList);
+        }
+        self.list.push(tag);
+        Ok(())
+    }
+}
+impl ser::SerializeSeq for SerializeList {
+    type Ok = Tag;
+    type Error = Error;
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+    fn end(self) -> Result<Tag, Error> {
+        Ok(Tag::List(self.list))
+    }
+}
+impl ser::SerializeTuple for SerializeList {
+    type Ok = Tag;
+    type Error = Error;
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+    fn end(self) -> Result<Tag, Error> {
+        Ok(Tag::List(self.list))
+    }
+}
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = Tag;
+    type Error = Error;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+    fn end(self) -> Result<Tag, Error> {
+        Ok(Tag::List(self.list))
+    }
+}
+
+#[derive(Default)]
+struct SerializeCompound {
+    compound: Compound,
+    key: Option<String>,
+}
+impl SerializeCompound {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<(), Error> {
+        let tag = to_tag(value)?;
+        if tag != Tag::End {
+            self.compound.insert(key, tag);
+        }
+        Ok(())
+    }
+}
+impl ser::SerializeMap for SerializeCompound {
+    type Ok = Tag;
+    type Error = Error;
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        match to_tag(key)? {
+            Tag::String(key) => {
+                self.key = Some(key);
+                Ok(())
+            }
+            _ => Err(Error::KeyNotString),
+        }
+    }
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::Message("value serialized before key".to_owned()))?;
+        self.insert(key, value)
+    }
+    fn end(self) -> Result<Tag, Error> {
+        Ok(Tag::Compound(self.compound))
+    }
+}
+impl ser::SerializeStruct for SerializeCompound {
+    type Ok = Tag;
+    type Error = Error;
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.insert(key.to_owned(), value)
+    }
+    fn end(self) -> Result<Tag, Error> {
+        Ok(Tag::Compound(self.compound))
+    }
+}
+
+/// Enum variants with data are stored as a compound with a single entry.
+struct SerializeVariant<S> {
+    variant: &'static str,
+    inner: S,
+}
+fn wrap_variant(variant: &'static str, tag: Tag) -> Tag {
+    let mut compound = Compound::new();
+    compound.insert(variant.to_owned(), tag);
+    Tag::Compound(compound)
+}
+impl ser::SerializeTupleVariant for SerializeVariant<SerializeList> {
+    type Ok = Tag;
+    type Error = Error;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.inner.push(value)
+    }
+    fn end(self) -> Result<Tag, Error> {
+        Ok(wrap_variant(self.variant, Tag::List(self.inner.list)))
+    }
+}
+impl ser::SerializeStructVariant for SerializeVariant<SerializeCompound> {
+    type Ok = Tag;
+    type Error = Error;
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.inner.insert(key.to_owned(), value)
+    }
+    fn end(self) -> Result<Tag, Error> {
+        Ok(wrap_variant(
+            self.variant,
+            Tag::Compound(self.inner.compound),
+        ))
+    }
+}
+
+impl Serialize for Tag {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Tag::End => serializer.serialize_unit(),
+            Tag::Byte(v) => serializer.serialize_i8(*v),
+            Tag::Short(v) => serializer.serialize_i16(*v),
+            Tag::Int(v) => serializer.serialize_i32(*v),
+            Tag::Long(v) => serializer.serialize_i64(*v),
+            Tag::Float(v) => serializer.serialize_f32(*v),
+            Tag::Double(v) => serializer.serialize_f64(*v),
+            Tag::ByteArray(v) => serializer.serialize_newtype_struct(BYTE_ARRAY, v),
+            Tag::String(v) => serializer.serialize_str(v),
+            Tag::List(v) => serializer.collect_seq(v),
+            Tag::Compound(v) => serializer.collect_map(v),
+            Tag::IntArray(v) => serializer.serialize_newtype_struct(INT_ARRAY, v),
+            Tag::LongArray(v) => serializer.serialize_newtype_struct(LONG_ARRAY, v),
+        }
+    }
+}
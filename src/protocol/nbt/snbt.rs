@@ -0,0 +1,483 @@
+use super::{Compound, Tag, MAX_DEPTH};
+use nom::{
+    branch::alt,
+    bytes::complete::take_while1,
+    character::complete::{char, multispace0, one_of},
+    combinator::{map, opt},
+    sequence::terminated,
+    IResult,
+};
+use std::fmt::{self, Write as _};
+
+/// Where and why parsing SNBT failed.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("expected {expected} at line {line}, column {column}")]
+pub struct SnbtError {
+    /// What the parser was looking for.
+    pub expected: &'static str,
+    /// The byte offset into the input.
+    pub offset: usize,
+    /// The line of the error, starting at 1.
+    pub line: usize,
+    /// The column of the error in characters, starting at 1.
+    pub column: usize,
+}
+impl SnbtError {
+    fn new(source: &str, remaining: &str, expected: &'static str) -> SnbtError {
+        let offset = source.len() - remaining.len();
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        SnbtError {
+            expected,
+            offset,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+/// Parse stringified NBT, as used in commands and data packs.
+pub fn from_snbt(source: &str) -> Result<Tag, SnbtError> {
+    let result = terminated(|i| parse_value(i, 0), multispace0)(source);
+    match result {
+        Ok(("", tag)) => Ok(tag),
+        Ok((rest, _)) => Err(SnbtError::new(source, rest, "end of input")),
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+            Err(SnbtError::new(source, e.input, e.expected))
+        }
+        Err(nom::Err::Incomplete(_)) => Err(SnbtError::new(source, "", "more input")),
+    }
+}
+impl std::str::FromStr for Tag {
+    type Err = SnbtError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        from_snbt(s)
+    }
+}
+
+/// The error used while parsing, which remembers what was expected where.
+#[derive(Debug)]
+struct ParseError<'a> {
+    input: &'a str,
+    expected: &'static str,
+}
+impl<'a> nom::error::ParseError<&'a str> for ParseError<'a> {
+    fn from_error_kind(input: &'a str, _kind: nom::error::ErrorKind) -> Self {
+        ParseError {
+            input,
+            expected: "a value",
+        }
+    }
+    fn append(_input: &'a str, _kind: nom::error::ErrorKind, other: Self) -> Self {
+        other
+    }
+    fn or(self, other: Self) -> Self {
+        // Keep whichever error made it further into the input.
+        if other.input.len() <= self.input.len() {
+            other
+        } else {
+            self
+        }
+    }
+}
+type PResult<'a, T> = IResult<&'a str, T, ParseError<'a>>;
+
+fn error<'a, T>(input: &'a str, expected: &'static str) -> PResult<'a, T> {
+    Err(nom::Err::Failure(ParseError { input, expected }))
+}
+/// Run `parser`, reporting `expected` at the current position if it fails.
+fn expect<'a, T>(
+    expected: &'static str,
+    mut parser: impl FnMut(&'a str) -> PResult<'a, T>,
+) -> impl FnMut(&'a str) -> PResult<'a, T> {
+    move |input| match parser(input) {
+        Err(nom::Err::Error(_)) => error(input, expected),
+        result => result,
+    }
+}
+
+fn is_unquoted_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+}
+
+fn parse_value(input: &str, depth: usize) -> PResult<'_, Tag> {
+    let (input, _) = multispace0(input)?;
+    if depth > MAX_DEPTH {
+        return error(input, "less nesting");
+    }
+    match input.chars().next() {
+        Some('{') => parse_compound(input, depth),
+        Some('[') => parse_list(input, depth),
+        Some('"' | '\'') => map(parse_quoted, Tag::String)(input),
+        _ => map(
+            expect("a value", take_while1(is_unquoted_char)),
+            classify_unquoted,
+        )(input),
+    }
+}
+
+fn parse_compound(input: &str, depth: usize) -> PResult<'_, Tag> {
+    let (mut input, _) = terminated(char('{'), multispace0)(input)?;
+    let mut compound = Compound::new();
+    if let Ok((rest, _)) = char::<_, ParseError>('}')(input) {
+        return Ok((rest, Tag::Compound(compound)));
+    }
+    loop {
+        let (rest, key) = expect("a key", parse_key)(input)?;
+        let (rest, _) = multispace0(rest)?;
+        let (rest, _) = expect("':'", char(':'))(rest)?;
+        let (rest, value) = parse_value(rest, depth + 1)?;
+        compound.insert(key, value);
+        let (rest, _) = multispace0(rest)?;
+        let (rest, separator) = expect("',' or '}'", one_of(",}"))(rest)?;
+        input = rest;
+        if separator == '}' {
+            return Ok((input, Tag::Compound(compound)));
+        }
+        let (rest, _) = multispace0(input)?;
+        input = rest;
+    }
+}
+fn parse_key(input: &str) -> PResult<'_, String> {
+    alt((
+        parse_quoted,
+        map(take_while1(is_unquoted_char), str::to_owned),
+    ))(input)
+}
+
+fn parse_list(input: &str, depth: usize) -> PResult<'_, Tag> {
+    let (input, _) = char('[')(input)?;
+    let (input, array_type) = opt(terminated(one_of("BIL"), char(';')))(input)?;
+    let (mut input, _) = multispace0(input)?;
+    let mut list = vec![];
+    if let Ok((rest, _)) = char::<_, ParseError>(']')(input) {
+        input = rest;
+    } else {
+        loop {
+            let (rest, element) = parse_value(input, depth + 1)?;
+            let element_start = multispace0::<_, ParseError>(input)?.0;
+            let expected_id = match array_type {
+                Some('B') => Some((Tag::Byte(0).id(), "a byte")),
+                Some('I') => Some((Tag::Int(0).id(), "an int")),
+                Some('L') => Some((Tag::Long(0).id(), "a long")),
+                _ => list
+                    .first()
+                    .map(|first: &Tag| (first.id(), "an element of the same type")),
+            };
+            if let Some((id, expected)) = expected_id {
+                if element.id() != id {
+                    return error(element_start, expected);
+                }
+            }
+            list.push(element);
+            let (rest, _) = multispace0(rest)?;
+            let (rest, separator) = expect("',' or ']'", one_of(",]"))(rest)?;
+            input = rest;
+            if separator == ']' {
+                break;
+            }
+        }
+    }
+    let tag = match array_type {
+        Some('B') => Tag::ByteArray(list.into_iter().map(|t| as_i64(&t) as i8).collect()),
+        Some('I') => Tag::IntArray(list.into_iter().map(|t| as_i64(&t) as i32).collect()),
+        Some('L') => Tag::LongArray(list.into_iter().map(|t| as_i64(&t)).collect()),
+        _ => Tag::List(list),
+    };
+    Ok((input, tag))
+}
+fn as_i64(tag: &Tag) -> i64 {
+    match tag {
+        Tag::Byte(v) => *v as i64,
+        Tag::Int(v) => *v as i64,
+        Tag::Long(v) => *v,
+        _ => unreachable!("array elements were checked while parsing"),
+    }
+}
+
+/// Parse a string in single or double quotes.
+fn parse_quoted(input: &str) -> PResult<'_, String> {
+    let (mut input, quote) = one_of("\"'")(input)?;
+    let mut output = String::new();
+    loop {
+        let mut chars = input.chars();
+        match chars.next() {
+            None => return error(input, "a closing quote"),
+            Some(c) if c == quote => return Ok((chars.as_str(), output)),
+            Some('\\') => {
+                let (rest, c) = parse_escape(chars.as_str())?;
+                output.push(c);
+                input = rest;
+            }
+            Some(c) => {
+                output.push(c);
+                input = chars.as_str();
+            }
+        }
+    }
+}
+fn parse_escape(input: &str) -> PResult<'_, char> {
+    let mut chars = input.chars();
+    let c = match chars.next() {
+        Some(c @ ('\\' | '"' | '\'')) => c,
+        Some('n') => '\n',
+        Some('r') => '\r',
+        Some('t') => '\t',
+        Some('b') => '\u{8}',
+        Some('f') => '\u{c}',
+        Some('s') => ' ',
+        Some('u') => {
+            let rest = chars.as_str();
+            return match rest
+                .get(..4)
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .and_then(char::from_u32)
+            {
+                Some(c) => Ok((&rest[4..], c)),
+                None => error(rest, "four hex digits"),
+            };
+        }
+        _ => return error(input, "a valid escape sequence"),
+    };
+    Ok((chars.as_str(), c))
+}
+
+/// Work out whether an unquoted token is a number, a boolean or a plain string.
+fn classify_unquoted(token: &str) -> Tag {
+    if token.eq_ignore_ascii_case("true") {
+        return Tag::Byte(1);
+    } else if token.eq_ignore_ascii_case("false") {
+        return Tag::Byte(0);
+    }
+    let (body, suffix) = match token.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&token[..i], Some(c.to_ascii_lowercase())),
+        _ => (token, None),
+    };
+    let number = match suffix {
+        Some('b') if is_integer(body) => body.parse().ok().map(Tag::Byte),
+        Some('s') if is_integer(body) => body.parse().ok().map(Tag::Short),
+        Some('l') if is_integer(body) => body.parse().ok().map(Tag::Long),
+        Some('f') if is_float(body) => body.parse().ok().map(Tag::Float),
+        Some('d') if is_float(body) => body.parse().ok().map(Tag::Double),
+        None if is_integer(body) => body.parse().ok().map(Tag::Int),
+        None if is_float(body) && body.contains('.') => body.parse().ok().map(Tag::Double),
+        _ => None,
+    };
+    number.unwrap_or_else(|| Tag::String(token.to_owned()))
+}
+/// Matches `[-+]?(0|[1-9][0-9]*)`.
+fn is_integer(s: &str) -> bool {
+    let digits = s.strip_prefix(['-', '+']).unwrap_or(s);
+    match digits.as_bytes() {
+        [b'0'] => true,
+        [b'1'..=b'9', rest @ ..] => rest.iter().all(u8::is_ascii_digit),
+        _ => false,
+    }
+}
+/// Matches `[-+]?([0-9]+[.]?|[0-9]*[.][0-9]+)(e[-+]?[0-9]+)?`.
+fn is_float(s: &str) -> bool {
+    let s = s.strip_prefix(['-', '+']).unwrap_or(s);
+    let (mantissa, exponent) = match s.find(['e', 'E']) {
+        Some(i) => (&s[..i], Some(&s[i + 1..])),
+        None => (s, None),
+    };
+    let (whole, fraction) = match mantissa.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (mantissa, None),
+    };
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    let mantissa_ok = all_digits(whole)
+        && fraction.is_none_or(all_digits)
+        && !(whole.is_empty() && fraction.unwrap_or("").is_empty());
+    let exponent_ok = exponent.is_none_or(|e| {
+        let e = e.strip_prefix(['-', '+']).unwrap_or(e);
+        !e.is_empty() && all_digits(e)
+    });
+    mantissa_ok && exponent_ok
+}
+
+/// Prints the tag as canonical SNBT.
+///
+/// The alternate form (`{:#}`) spreads compounds and lists over multiple indented lines.
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_snbt(self, f, f.alternate(), 0)
+    }
+}
+fn write_snbt(tag: &Tag, f: &mut fmt::Formatter<'_>, pretty: bool, indent: usize) -> fmt::Result {
+    let newline = |f: &mut fmt::Formatter<'_>, indent: usize| -> fmt::Result {
+        if pretty {
+            f.write_char('\n')?;
+            for _ in 0..indent {
+                f.write_str("    ")?;
+            }
+        }
+        Ok(())
+    };
+    match tag {
+        Tag::End => Ok(()),
+        Tag::Byte(v) => write!(f, "{v}b"),
+        Tag::Short(v) => write!(f, "{v}s"),
+        Tag::Int(v) => write!(f, "{v}"),
+        Tag::Long(v) => write!(f, "{v}L"),
+        Tag::Float(v) => write!(f, "{v:?}f"),
+        Tag::Double(v) => write!(f, "{v:?}d"),
+        Tag::String(v) => write_quoted(v, f),
+        Tag::ByteArray(v) => write_array(f, 'B', v.iter().map(|b| format!("{b}B"))),
+        Tag::IntArray(v) => write_array(f, 'I', v.iter().map(|i| i.to_string())),
+        Tag::LongArray(v) => write_array(f, 'L', v.iter().map(|l| format!("{l}L"))),
+        Tag::List(list) => {
+            f.write_char('[')?;
+            for (i, element) in list.iter().enumerate() {
+                if i > 0 {
+                    f.write_char(',')?;
+                }
+                newline(f, indent + 1)?;
+                write_snbt(element, f, pretty, indent + 1)?;
+            }
+            if !list.is_empty() {
+                newline(f, indent)?;
+            }
+            f.write_char(']')
+        }
+        Tag::Compound(compound) => {
+            f.write_char('{')?;
+            for (i, (key, value)) in compound.iter().enumerate() {
+                if i > 0 {
+                    f.write_char(',')?;
+                }
+                newline(f, indent + 1)?;
+                if !key.is_empty() && key.chars().all(is_unquoted_char) {
+                    f.write_str(key)?;
+                } else {
+                    write_quoted(key, f)?;
+                }
+                f.write_str(if pretty { ": " } else { ":" })?;
+                write_snbt(value, f, pretty, indent + 1)?;
+            }
+            if !compound.is_empty() {
+                newline(f, indent)?;
+            }
+            f.write_char('}')
+        }
+    }
+}
+fn write_array(
+    f: &mut fmt::Formatter<'_>,
+    prefix: char,
+    elements: impl Iterator<Item = String>,
+) -> fmt::Result {
+    write!(f, "[{prefix};")?;
+    for (i, element) in elements.enumerate() {
+        if i > 0 {
+            f.write_char(',')?;
+        }
+        f.write_str(&element)?;
+    }
+    f.write_char(']')
+}
+/// Quote a string, preferring double quotes unless the string contains them.
+fn write_quoted(s: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let quote = if s.contains('"') && !s.contains('\'') {
+        '\''
+    } else {
+        '"'
+    };
+    f.write_char(quote)?;
+    for c in s.chars() {
+        match c {
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c == quote => write!(f, "\\{c}")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char(quote)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::parsing::Parsable;
+
+    fn get_example() -> (Tag, &'static str) {
+        (
+            Tag::Compound(Compound::from([
+                ("byte".to_owned(), Tag::Byte(-1)),
+                ("short".to_owned(), Tag::Short(300)),
+                ("int".to_owned(), Tag::Int(7)),
+                ("long".to_owned(), Tag::Long(1 << 40)),
+                ("float".to_owned(), Tag::Float(0.5)),
+                ("double".to_owned(), Tag::Double(1e100)),
+                ("string".to_owned(), Tag::from("say \"hi\"\n")),
+                ("has space".to_owned(), Tag::List(vec![Tag::from("a")])),
+                ("bytes".to_owned(), Tag::ByteArray(vec![1, -2])),
+                ("ints".to_owned(), Tag::IntArray(vec![])),
+                ("longs".to_owned(), Tag::LongArray(vec![3])),
+                ("empty".to_owned(), Tag::Compound(Compound::new())),
+            ])),
+            r#"{byte:-1b,short:300s,int:7,long:1099511627776L,float:0.5f,double:1e100d,string:'say "hi"\n',"has space":["a"],bytes:[B;1B,-2B],ints:[I;],longs:[L;3L],empty:{}}"#,
+        )
+    }
+
+    #[test]
+    fn snbt_printing_works() {
+        let (tag, snbt) = get_example();
+        assert_eq!(tag.to_string(), snbt);
+        assert_eq!(
+            format!(
+                "{:#}",
+                Tag::List(vec![Tag::Compound(Compound::from([(
+                    "a".to_owned(),
+                    Tag::Int(1)
+                )]))])
+            ),
+            "[\n    {\n        a: 1\n    }\n]"
+        );
+    }
+    #[test]
+    fn snbt_parsing_works() {
+        let (tag, snbt) = get_example();
+        assert_eq!(from_snbt(snbt), Ok(tag.clone()));
+        assert_eq!(from_snbt(&format!("{tag:#}")), Ok(tag));
+
+        let cases = [
+            ("true", Tag::Byte(1)),
+            ("12", Tag::Int(12)),
+            ("3000000000", Tag::from("3000000000")),
+            ("1.5", Tag::Double(1.5)),
+            ("1e5", Tag::from("1e5")),
+            ("1e5f", Tag::Float(1e5)),
+            ("stone", Tag::from("stone")),
+            ("'\\u00e9\\\\'", Tag::from("é\\")),
+            (" [ 1 , 2 ] ", Tag::List(vec![Tag::Int(1), Tag::Int(2)])),
+        ];
+        for (snbt, tag) in cases {
+            assert_eq!(from_snbt(snbt), Ok(tag), "{snbt}");
+        }
+    }
+    #[test]
+    fn snbt_round_trips_through_binary() {
+        let (tag, snbt) = get_example();
+        let parsed = from_snbt(snbt).unwrap();
+        let (_, binary) = Tag::parse(&parsed.serialize()).unwrap();
+        assert_eq!(binary, tag);
+        assert_eq!(binary.to_string(), snbt);
+    }
+    #[test]
+    fn snbt_errors_have_positions() {
+        let error = |snbt| {
+            let e = from_snbt(snbt).unwrap_err();
+            (e.expected, e.line, e.column)
+        };
+        assert_eq!(error("{a 1}"), ("':'", 1, 4));
+        assert_eq!(error("{a:1,\n  b:}"), ("a value", 2, 5));
+        assert_eq!(error("[1,2b]"), ("an element of the same type", 1, 4));
+        assert_eq!(error("[I;1,2L]"), ("an int", 1, 6));
+        assert_eq!(error("\"unterminated"), ("a closing quote", 1, 14));
+        assert_eq!(error("{} x"), ("end of input", 1, 4));
+    }
+}
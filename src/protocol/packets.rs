@@ -2,6 +2,7 @@
 
 // Inspired by https://github.com/iceiix/stevenarella.
 
+use bytes::Bytes;
 use tracing::trace;
 
 /// Enum representation of a packet's direction.
@@ -11,21 +12,219 @@ pub enum PacketDirection {
     Clientbound,
 }
 
+/// The state, direction and latest id that identify a packet type.
+pub trait PacketKind {
+    /// The id in the latest version.
+    ///
+    /// Packets removed before then are given an id past every latest one, so ids stay unique.
+    const ID: i32;
+    const CLIENT_STATE: ClientState;
+    const DIRECTION: PacketDirection;
+}
+
+/// A packet whose body is kept as bytes until something needs to read it.
+///
+/// Forwarding a raw packet to a connection on the same version copies the body as is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawPacket {
+    /// The version the body is laid out in.
+    pub version: ProtocolVersion,
+    pub client_state: ClientState,
+    pub direction: PacketDirection,
+    /// The packet id in the latest version, as in `PacketKind::ID`.
+    pub id: i32,
+    pub body: Bytes,
+    /// The parsed packet, if it has been parsed already.
+    packet: Option<Packet>,
+}
+impl RawPacket {
+    /// Wrap an unparsed packet body.
+    pub fn new(
+        version: ProtocolVersion,
+        client_state: ClientState,
+        direction: PacketDirection,
+        id: i32,
+        body: Bytes,
+    ) -> RawPacket {
+        RawPacket {
+            version,
+            client_state,
+            direction,
+            id,
+            body,
+            packet: None,
+        }
+    }
+    /// Serialize a packet as it is in `version`.
+    ///
+    /// Returns `None` if the packet doesn't exist in `version`.
+    pub fn from_packet(version: ProtocolVersion, packet: Packet) -> Option<RawPacket> {
+        let (client_state, direction, id) = packet.kind();
+        Packet::wire_id(version, client_state, direction, id)?;
+        let mut body = Vec::with_capacity(packet.frame_len_versioned(version)?);
+        packet.serialize_body_versioned_into(version, &mut body);
+        Some(RawPacket {
+            version,
+            client_state,
+            direction,
+            id,
+            body: Bytes::from(body),
+            packet: Some(packet),
+        })
+    }
+    /// Whether this is a `P` packet.
+    pub fn is<P: PacketKind>(&self) -> bool {
+        self.id == P::ID && self.client_state == P::CLIENT_STATE && self.direction == P::DIRECTION
+    }
+    /// The parsed packet, if it has been parsed already.
+    pub fn packet(&self) -> Option<&Packet> {
+        self.packet.as_ref()
+    }
+    /// Parse the body, keeping the result.
+    pub fn parse(&mut self) -> Result<&Packet, ParseError> {
+        if self.packet.is_none() {
+            self.packet = Some(self.parse_body()?);
+        }
+        Ok(self.packet.as_ref().expect("packet was just parsed"))
+    }
+    /// Parse the body, unless it has been parsed already.
+    pub fn into_packet(self) -> Result<Packet, ParseError> {
+        match self.packet {
+            Some(packet) => Ok(packet),
+            None => self.parse_body(),
+        }
+    }
+    fn parse_body(&self) -> Result<Packet, ParseError> {
+        let wire_id = Packet::wire_id(self.version, self.client_state, self.direction, self.id);
+        Packet::parse_body_versioned(
+            self.version,
+            self.client_state,
+            self.direction,
+            self.id,
+            &self.body,
+        )
+        .map(|(_, packet)| packet)
+        .map_err(|_| {
+            let mut frame = VarInt::from(wire_id.unwrap_or(self.id)).serialize();
+            frame.extend(&self.body);
+            Packet::frame_error(self.version, self.client_state, self.direction, &frame)
+        })
+    }
+    /// The state the connection moves to after this packet.
+    ///
+    /// Packets that change the state are always parsed when they're read,
+    /// so this is accurate for packets that haven't been parsed.
+    pub fn state_change(&self) -> Option<ClientState> {
+        self.packet.as_ref().and_then(Packet::state_change)
+    }
+    /// The length of the packet id and body in `version`.
+    ///
+    /// Returns `None` if the packet doesn't exist in `version`, or needs to be parsed
+    /// to be converted to it.
+    pub fn frame_len_versioned(&self, version: ProtocolVersion) -> Option<usize> {
+        if version != self.version {
+            return self.packet.as_ref()?.frame_len_versioned(version);
+        }
+        let id = Packet::wire_id(version, self.client_state, self.direction, self.id)?;
+        Some(VarInt::from(id).encoded_len() + self.body.len())
+    }
+    /// Serialize the packet id and body as they are in `version` onto the end of `buf`.
+    ///
+    /// Returns `None` without writing anything if `frame_len_versioned` would.
+    pub fn serialize_frame_versioned_into(
+        &self,
+        version: ProtocolVersion,
+        buf: &mut impl BufMut,
+    ) -> Option<()> {
+        if version != self.version {
+            return self
+                .packet
+                .as_ref()?
+                .serialize_frame_versioned_into(version, buf);
+        }
+        let id = Packet::wire_id(version, self.client_state, self.direction, self.id)?;
+        VarInt::from(id).serialize_into(buf);
+        buf.put_slice(&self.body);
+        Some(())
+    }
+}
+
+/// The latest id, first version and last version of every packet in a state and direction.
+type IdTable = [(i32, ProtocolVersion, ProtocolVersion)];
+
+/// Whether a packet in an `IdTable` exists in `version`.
+fn exists_in(
+    (_, since, until): &(i32, ProtocolVersion, ProtocolVersion),
+    version: ProtocolVersion,
+) -> bool {
+    (*since..=*until).contains(&version)
+}
+/// Convert a packet id in the latest version into its id in `version`.
+///
+/// Packets are numbered in order, so a packet's id in `version` is
+/// the number of packets before it in `table` that exist in `version`.
+fn wire_id(table: &IdTable, version: ProtocolVersion, id: i32) -> Option<i32> {
+    let position = table.iter().position(|(latest, _, _)| *latest == id)?;
+    if !exists_in(&table[position], version) {
+        return None;
+    }
+    let before = table[..position]
+        .iter()
+        .filter(|packet| exists_in(packet, version))
+        .count();
+    Some(before as i32)
+}
+/// Convert a packet id in `version` into its id in the latest version.
+fn latest_id(table: &IdTable, version: ProtocolVersion, id: i32) -> Option<i32> {
+    table
+        .iter()
+        .filter(|packet| exists_in(packet, version))
+        .nth(usize::try_from(id).ok()?)
+        .map(|(latest, _, _)| *latest)
+}
+
+/// Define the packets of every state and direction.
+///
+/// Packet ids are the ones in [`ProtocolVersion::LATEST`].
+/// Packets and fields added after [`ProtocolVersion::BASELINE`] are marked with
+/// `since Version`, and ones removed before [`ProtocolVersion::LATEST`] are marked with
+/// `until Version`, the last version they're in. Both are skipped in versions without them.
+///
+/// Removed packets stay where they were in the id order and are given their id in
+/// their last version, which `PacketKind::ID` moves past the latest ids.
+/// A field whose type changed is written as the old field marked `until`
+/// followed by the new one marked `since`.
 #[macro_export]
 macro_rules! packets {
+    (@since) => { $crate::protocol::ProtocolVersion::BASELINE };
+    (@since $since:ident) => { $crate::protocol::ProtocolVersion::$since };
+    (@until) => { $crate::protocol::ProtocolVersion::LATEST };
+    (@until $until:ident) => { $crate::protocol::ProtocolVersion::$until };
+    (@id $id:literal) => { $id };
+    (@id $id:literal $until:ident) => {
+        $id + 0x100 * (1 + $crate::protocol::ProtocolVersion::$until as i32)
+    };
+    (@exists $version:ident, $($since:ident)?, $($until:ident)?) => {
+        ($crate::packets!(@since $($since)?)..=$crate::packets!(@until $($until)?)).contains(&$version)
+    };
     ($($state:ident $state_name:ident {
         $($dir:ident $dir_name:ident {
             $(
                 $(#[$attr:meta])*
+                $(since $since:ident)?
+                $(until $until:ident)?
                 packet $name:ident $id:literal {
-                    $($(#[$fattr:meta])* field $field:ident: $field_type:ty,)*
+                    $($(#[$fattr:meta])* $(since $fsince:ident)? $(until $funtil:ident)? field $field:ident: $field_type:ty,)*
                     $($(#[$rattr:meta])* rest $rest:ident,)?
                 }
             )*
         })+
     })+) => {
-        use $crate::protocol::{ClientState, parsing::{VarInt, Parsable, IResult}};
+        use $crate::protocol::{ClientState, ProtocolVersion, error::{ParseError, ParseErrorKind}, parsing::{BufMut, VarInt, Parsable, IResult}};
 
+        // Packets are moved once from the codec to their handler,
+        // so boxing the ones with text components isn't worth the allocation.
+        #[allow(clippy::large_enum_variant)]
         #[derive(Debug, Clone, PartialEq)]
         pub enum Packet {
             $($($(
@@ -38,50 +237,122 @@ macro_rules! packets {
                     Packet::$name(value)
                 }
             }
+            impl PacketKind for $state::$dir::$name {
+                const ID: i32 = $crate::packets!(@id $id $($until)?);
+                const CLIENT_STATE: ClientState = ClientState::$state_name;
+                const DIRECTION: PacketDirection = PacketDirection::$dir_name;
+            }
         )*)+)+
         impl Packet {
-            fn parser(client_state: ClientState, direction: PacketDirection) -> impl Fn(&[u8]) -> IResult<&[u8], Self> {
+            /// The `IdTable` of a state and direction, in id order.
+            fn id_table(client_state: ClientState, direction: PacketDirection) -> &'static IdTable {
+                match client_state {
+                    $(ClientState::$state_name => {
+                        match direction {
+                            $(PacketDirection::$dir_name => &[
+                                $((
+                                    <$state::$dir::$name as PacketKind>::ID,
+                                    $crate::packets!(@since $($since)?),
+                                    $crate::packets!(@until $($until)?),
+                                ),)*
+                            ],)*
+                        }
+                    })*
+                    ClientState::Disconnected => &[],
+                }
+            }
+            /// Convert a packet id in the latest version into its id in `version`.
+            ///
+            /// Returns `None` if the packet doesn't exist in `version`.
+            pub fn wire_id(version: ProtocolVersion, client_state: ClientState, direction: PacketDirection, id: i32) -> Option<i32> {
+                wire_id(Packet::id_table(client_state, direction), version, id)
+            }
+            /// Convert a packet id in `version` into its id in the latest version.
+            pub fn latest_id(version: ProtocolVersion, client_state: ClientState, direction: PacketDirection, id: i32) -> Option<i32> {
+                latest_id(Packet::id_table(client_state, direction), version, id)
+            }
+            fn parser(version: ProtocolVersion, client_state: ClientState, direction: PacketDirection) -> impl Fn(&[u8]) -> IResult<&[u8], Self> {
                 move |input: &[u8]| {
-                    use nom::{combinator::verify, bytes::streaming::take};
+                    use nom::bytes::streaming::take;
 
                     if client_state == ClientState::Disconnected {
                         return nom::combinator::fail(input);
                     }
                     let (input, packet_len) = VarInt::parse_usize(input)?;
                     let (input, packet_body) = take(packet_len)(input)?;
-                    let (packet_body, packet_id) = verify(VarInt::parse, |v| {
-                        match client_state {
-                            $(ClientState::$state_name => {
-                                match direction {
-                                    $(PacketDirection::$dir_name => {
-                                        match **v {
-                                            $($id => true,)*
-                                            _ => false,
-                                        }
-                                    })*
-                                }
-                            })*
-                            ClientState::Disconnected => false,
-                        }
+                    let (_, packet) = Packet::frame_parser(version, client_state, direction)(packet_body)?;
+                    Ok((input, packet))
+                }
+            }
+            /// Parses the contents of a frame (packet id and body), without the length prefix.
+            fn frame_parser(version: ProtocolVersion, client_state: ClientState, direction: PacketDirection) -> impl Fn(&[u8]) -> IResult<&[u8], Self> {
+                move |packet_body: &[u8]| {
+                    use nom::combinator::map_opt;
+
+                    if client_state == ClientState::Disconnected {
+                        return nom::combinator::fail(packet_body);
+                    }
+                    let (packet_body, packet_id) = map_opt(VarInt::parse, |v| {
+                        Packet::latest_id(version, client_state, direction, *v)
                     })(packet_body)?;
                     trace!("Parsing packet: {:?} {:?} {:02x} ({} bytes) {}",
-                        direction, client_state, *packet_id, packet_body.len(),
+                        direction, client_state, packet_id, packet_body.len(),
                         packet_body.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join("")
                     );
-                    let (_, packet) = Packet::body_parser(client_state, direction, packet_id)(packet_body)?;
-                    // trace!("Parsed packet: {:?}", packet);
-                    Ok((input, packet))
+                    Packet::body_parser(version, client_state, direction, packet_id)(packet_body)
                 }
             }
-            fn body_parser(client_state: ClientState, direction: PacketDirection, packet_id: VarInt) -> impl Fn(&[u8]) -> IResult<&[u8], Self> {
+            /// Explain why `parse_frame_versioned` failed on the contents of a frame.
+            pub fn frame_error(version: ProtocolVersion, client_state: ClientState, direction: PacketDirection, frame: &[u8]) -> ParseError {
+                let mut error = ParseError {
+                    client_state,
+                    direction,
+                    packet_id: None,
+                    packet_name: None,
+                    field: None,
+                    offset: 0,
+                    kind: ParseErrorKind::UnknownPacketId,
+                };
+                let (body, packet_id) = match VarInt::parse(frame) {
+                    Ok(parsed) => parsed,
+                    Err(e) => {
+                        error.kind = ParseErrorKind::from(&e);
+                        return error;
+                    }
+                };
+                error.packet_id = Some(*packet_id);
+                let Some(latest_id) = Packet::latest_id(version, client_state, direction, *packet_id) else {
+                    return error;
+                };
+                let id_len = frame.len() - body.len();
+                #[allow(unreachable_patterns)]
+                let (packet_name, (field, offset, kind)) = match (client_state, direction, latest_id) {
+                    $($($(
+                        (ClientState::$state_name, PacketDirection::$dir_name, <$state::$dir::$name as PacketKind>::ID) => {
+                            (stringify!($name), $state::$dir::$name::parse_error_versioned(version, body))
+                        }
+                    )*)+)+
+                    _ => return error,
+                };
+                error.packet_name = Some(packet_name);
+                error.field = field;
+                error.offset = id_len + offset;
+                error.kind = kind;
+                error
+            }
+            /// Parse a packet body in `version`, given the packet's latest id.
+            pub fn parse_body_versioned(version: ProtocolVersion, client_state: ClientState, direction: PacketDirection, id: i32, input: &[u8]) -> IResult<&[u8], Self> {
+                Packet::body_parser(version, client_state, direction, id)(input)
+            }
+            fn body_parser(version: ProtocolVersion, client_state: ClientState, direction: PacketDirection, packet_id: i32) -> impl Fn(&[u8]) -> IResult<&[u8], Self> {
                 move |input: &[u8]| {
                     match client_state {
                         $(ClientState::$state_name => {
                             match direction {
                                 $(PacketDirection::$dir_name => {
-                                    match *packet_id {
-                                        $($id => {
-                                            let (rest, inner) = $state::$dir::$name::parse(input)?;
+                                    match packet_id {
+                                        $(<$state::$dir::$name as PacketKind>::ID => {
+                                            let (rest, inner) = $state::$dir::$name::parse_versioned(version, input)?;
                                             // The packet should have consumed all of the input specified by packet_len.
                                             nom::combinator::eof(rest)?;
                                             Ok((rest, Packet::$name(inner)))
@@ -98,23 +369,93 @@ macro_rules! packets {
                 }
             }
             pub fn parse(client_state: ClientState, direction: PacketDirection, input: &[u8]) -> IResult<&[u8], Self> {
-                Packet::parser(client_state, direction)(input)
+                Packet::parse_versioned(ProtocolVersion::LATEST, client_state, direction, input)
+            }
+            pub fn parse_versioned(version: ProtocolVersion, client_state: ClientState, direction: PacketDirection, input: &[u8]) -> IResult<&[u8], Self> {
+                Packet::parser(version, client_state, direction)(input)
+            }
+            /// Parse a packet from an already-framed (and decompressed) packet id and body.
+            pub fn parse_frame(client_state: ClientState, direction: PacketDirection, input: &[u8]) -> IResult<&[u8], Self> {
+                Packet::parse_frame_versioned(ProtocolVersion::LATEST, client_state, direction, input)
+            }
+            /// Parse a packet from an already-framed (and decompressed) packet id and body in `version`.
+            pub fn parse_frame_versioned(version: ProtocolVersion, client_state: ClientState, direction: PacketDirection, input: &[u8]) -> IResult<&[u8], Self> {
+                Packet::frame_parser(version, client_state, direction)(input)
             }
             pub fn parse_as<T: TryFrom<Packet, Error = Packet>>(client_state: ClientState, direction: PacketDirection, input: &[u8]) -> IResult<&[u8], Result<T, Self>> {
-                nom::combinator::map(Self::parser(client_state, direction), T::try_from)(input)
+                nom::combinator::map(Self::parser(ProtocolVersion::LATEST, client_state, direction), T::try_from)(input)
             }
             pub fn serialize(&self) -> (VarInt, Vec<u8>) {
                 match &self {
                     $($($(
-                        Packet::$name(inner) => (VarInt::from($id), inner.serialize()),
+                        Packet::$name(inner) => (VarInt::from(<$state::$dir::$name as PacketKind>::ID), inner.serialize()),
+                    )*)*)*
+                }
+            }
+            /// Serialize the packet id and body as they are in `version`.
+            ///
+            /// Returns `None` if the packet doesn't exist in `version`.
+            pub fn serialize_versioned(&self, version: ProtocolVersion) -> Option<(VarInt, Vec<u8>)> {
+                match &self {
+                    $($($(
+                        Packet::$name(inner) => {
+                            let id = Packet::wire_id(version, ClientState::$state_name, PacketDirection::$dir_name, <$state::$dir::$name as PacketKind>::ID)?;
+                            Some((VarInt::from(id), inner.serialize_versioned(version)))
+                        }
+                    )*)*)*
+                }
+            }
+            /// The state, direction and latest id of the packet.
+            pub fn kind(&self) -> (ClientState, PacketDirection, i32) {
+                match &self {
+                    $($($(
+                        Packet::$name(_) => (ClientState::$state_name, PacketDirection::$dir_name, <$state::$dir::$name as PacketKind>::ID),
+                    )*)*)*
+                }
+            }
+            /// Serialize the packet body as it is in `version` onto the end of `buf`, without the id.
+            pub fn serialize_body_versioned_into(&self, version: ProtocolVersion, buf: &mut impl BufMut) {
+                match &self {
+                    $($($(
+                        Packet::$name(inner) => inner.serialize_versioned_into(version, buf),
+                    )*)*)*
+                }
+            }
+            /// Serialize the packet id and body as they are in `version` onto the end of `buf`.
+            ///
+            /// Returns `None` without writing anything if the packet doesn't exist in `version`.
+            pub fn serialize_frame_versioned_into(&self, version: ProtocolVersion, buf: &mut impl BufMut) -> Option<()> {
+                match &self {
+                    $($($(
+                        Packet::$name(inner) => {
+                            let id = Packet::wire_id(version, ClientState::$state_name, PacketDirection::$dir_name, <$state::$dir::$name as PacketKind>::ID)?;
+                            VarInt::from(id).serialize_into(buf);
+                            inner.serialize_versioned_into(version, buf);
+                        }
+                    )*)*)*
+                }
+                Some(())
+            }
+            /// The length of the packet id and body in `version`.
+            ///
+            /// Returns `None` if the packet doesn't exist in `version`.
+            pub fn frame_len_versioned(&self, version: ProtocolVersion) -> Option<usize> {
+                match &self {
+                    $($($(
+                        Packet::$name(inner) => {
+                            let id = Packet::wire_id(version, ClientState::$state_name, PacketDirection::$dir_name, <$state::$dir::$name as PacketKind>::ID)?;
+                            Some(VarInt::from(id).encoded_len() + inner.encoded_len_versioned(version))
+                        }
                     )*)*)*
                 }
             }
             pub fn state_change(&self) -> Option<ClientState> {
                 match self {
-                    Packet::Handshake(handshake) => Some(handshake.next_state),
+                    Packet::Handshake(handshake) => Some(handshake.intent.next_state()),
                     Packet::LoginAcknowledged(_) => Some(ClientState::Configuration),
                     Packet::AcknowledgeFinishConfiguration(_) => Some(ClientState::Play),
+                    Packet::AcknowledgeConfiguration(_) => Some(ClientState::Configuration),
+                    Packet::StartConfiguration(_) => Some(ClientState::Configuration),
                     Packet::LoginDisconnect(_) => Some(ClientState::Disconnected),
                     Packet::ConfigurationDisconnect(_) => Some(ClientState::Disconnected),
                     Packet::PlayDisconnect(_) => Some(ClientState::Disconnected),
@@ -128,7 +469,7 @@ macro_rules! packets {
             $(pub mod $dir {
                 #![allow(unused_imports)]
 
-                use $crate::protocol::{ClientState, parsing::{VarInt, Parsable, IResult}, types::*};
+                use $crate::protocol::{ClientState, HandshakeIntent, ProtocolVersion, error::ParseErrorKind, parsing::{BufMut, VarInt, Parsable, IResult}, types::*, entities::{*, metadata::EntityMetadata}, inventory::{slot::{HashedSlot, Slot}, recipe::{RecipeBookEntry, RecipeDisplay, StonecutterRecipe}, trade::MerchantOffer, Equipment}, advancement::{Advancement, AdvancementProgress}, commands::CommandNode, scoreboard::{NumberFormat, ObjectiveAction, TeamAction}, nbt::Tag, text::{TextComponent, JsonTextComponent}};
                 use super::super::Packet;
 
                 $(
@@ -147,22 +488,80 @@ macro_rules! packets {
                             }
                         }
                     }
-                    impl Parsable for $name {
-                        fn parse(input: &[u8]) -> IResult<&[u8], Self> {
-                            $(let (input, $field) = <$field_type>::parse(input)?;)*
+                    impl $name {
+                        /// Parse the packet body as it is in `version`.
+                        #[allow(unused_variables)]
+                        pub fn parse_versioned(version: ProtocolVersion, input: &[u8]) -> IResult<&[u8], Self> {
+                            $(let (input, $field) = if $crate::packets!(@exists version, $($fsince)?, $($funtil)?) {
+                                <$field_type>::parse(input)?
+                            } else {
+                                (input, <$field_type>::default())
+                            };)*
                             $(let (input, $rest) = nom::combinator::rest(input)?;)?
                             Ok((input, $name {
                                 $($field: $field,)*
                                 $($rest: $rest.to_vec(),)?
                             }))
                         }
-                        #[allow(unused_mut)]
-                        fn serialize(&self) -> Vec<u8> {
-                            let mut output = vec![];
-                            $(output.extend(self.$field.serialize());)*
-                            $(output.extend(&self.$rest);)?
+                        /// Find the field that `parse_versioned` fails on, and how far into `input` it starts.
+                        #[allow(unused_mut, unused_variables)]
+                        pub fn parse_error_versioned(version: ProtocolVersion, input: &[u8]) -> (Option<&'static str>, usize, ParseErrorKind) {
+                            let start = input.len();
+                            let mut input = input;
+                            $(if $crate::packets!(@exists version, $($fsince)?, $($funtil)?) {
+                                match <$field_type>::parse(input) {
+                                    Ok((rest, _)) => input = rest,
+                                    Err(e) => return (Some(stringify!($field)), start - input.len(), ParseErrorKind::from(&e)),
+                                }
+                            })*
+                            $(
+                                // `$rest` takes the rest of the packet.
+                                let _ = stringify!($rest);
+                                input = &input[input.len()..];
+                            )?
+                            if input.is_empty() {
+                                (None, start, ParseErrorKind::InvalidData)
+                            } else {
+                                (None, start - input.len(), ParseErrorKind::TrailingBytes(input.len()))
+                            }
+                        }
+                        /// Serialize the packet body as it is in `version`.
+                        pub fn serialize_versioned(&self, version: ProtocolVersion) -> Vec<u8> {
+                            let mut output = Vec::with_capacity(self.encoded_len_versioned(version));
+                            self.serialize_versioned_into(version, &mut output);
                             output
                         }
+                        /// Serialize the packet body as it is in `version` onto the end of `buf`.
+                        #[allow(unused_variables)]
+                        pub fn serialize_versioned_into(&self, version: ProtocolVersion, buf: &mut impl BufMut) {
+                            $(if $crate::packets!(@exists version, $($fsince)?, $($funtil)?) {
+                                self.$field.serialize_into(buf);
+                            })*
+                            $(buf.put_slice(&self.$rest);)?
+                        }
+                        /// The length of the packet body in `version`.
+                        #[allow(unused_variables)]
+                        pub fn encoded_len_versioned(&self, version: ProtocolVersion) -> usize {
+                            0 $(+ if $crate::packets!(@exists version, $($fsince)?, $($funtil)?) {
+                                self.$field.encoded_len()
+                            } else {
+                                0
+                            })* $(+ self.$rest.len())?
+                        }
+                    }
+                    impl Parsable for $name {
+                        fn parse(input: &[u8]) -> IResult<&[u8], Self> {
+                            $name::parse_versioned(ProtocolVersion::LATEST, input)
+                        }
+                        fn serialize(&self) -> Vec<u8> {
+                            self.serialize_versioned(ProtocolVersion::LATEST)
+                        }
+                        fn serialize_into(&self, buf: &mut impl BufMut) {
+                            self.serialize_versioned_into(ProtocolVersion::LATEST, buf)
+                        }
+                        fn encoded_len(&self) -> usize {
+                            self.encoded_len_versioned(ProtocolVersion::LATEST)
+                        }
                     }
                 )*
             })+
@@ -175,9 +574,9 @@ packets!(
         serverbound Serverbound {
             packet Handshake 0x00 {
                 field protocol_version: VarInt,
-                field host: String,
+                field host: BoundedString<255>,
                 field port: u16,
-                field next_state: ClientState,
+                field intent: HandshakeIntent,
             }
         }
         clientbound Clientbound {}
@@ -201,8 +600,8 @@ packets!(
     login Login {
         serverbound Serverbound {
             packet LoginStart 0x00 {
-                field name: String,
-                field uuid: Option<Uuid>,
+                field name: BoundedString<16>,
+                field uuid: Uuid,
             }
             packet EncryptionResponse 0x01 {
                 field shared_secret: Vec<u8>,
@@ -210,18 +609,19 @@ packets!(
             }
             packet LoginPluginResponse 0x02 {
                 field message_id: VarInt,
-                // TODO: Implement
+                // False if the client didn't understand the request.
+                field successful: bool,
                 rest data,
             }
             packet LoginAcknowledged 0x03 {}
             packet LoginCookieResponse 0x04 {
-                // TODO: Implement
-                rest data,
+                field key: String,
+                field payload: PrefixedOptional<Vec<u8>>,
             }
         }
         clientbound Clientbound {
             packet LoginDisconnect 0x00 {
-                field reason: Chat,
+                field reason: JsonTextComponent,
             }
             packet EncryptionRequest 0x01 {
                 field server_id: String,
@@ -230,10 +630,7 @@ packets!(
                 field use_mojang_authentication: bool,
             }
             packet LoginSuccess 0x02 {
-                field uuid: Uuid,
-                field username: String,
-                // TODO: Implement
-                rest properties,
+                field profile: GameProfile,
             }
             packet SetCompression 0x03 {
                 field threshold: VarInt,
@@ -241,12 +638,10 @@ packets!(
             packet LoginPluginRequest 0x04 {
                 field message_id: VarInt,
                 field channel: String,
-                // TODO: Implement
                 rest data,
             }
             packet LoginCookieRequest 0x05 {
-                // TODO: Implement
-                rest data,
+                field key: String,
             }
         }
     }
@@ -265,7 +660,7 @@ packets!(
             }
             packet ConfigurationCookieResponse 0x01 {
                 field key: String,
-                field payload: Option<Vec<u8>>,
+                field payload: PrefixedOptional<Vec<u8>>,
             }
             packet ConfigurationServerboundPluginMessage 0x02 {
                 field channel: String,
@@ -283,8 +678,8 @@ packets!(
                 field result: VarInt,
             }
             packet ServerboundKnownPacks 0x07 {
-                // TODO: Implement
-                rest data,
+                // The packs the client has out of those the server offered.
+                field known_packs: Vec<KnownPack>,
             }
         }
         clientbound Clientbound {
@@ -296,7 +691,7 @@ packets!(
                 rest data,
             }
             packet ConfigurationDisconnect 0x02 {
-                field reason: Chat,
+                field reason: TextComponent,
             }
             packet FinishConfiguration 0x03 {}
             packet ConfigurationClientboundKeepAlive 0x04 {
@@ -307,18 +702,20 @@ packets!(
             }
             packet ResetChat 0x06 {}
             packet RegistryData 0x07 {
-                // TODO: Implement
-                rest data,
+                field registry_id: String,
+                // Pairs of entry id and entry data, which is omitted for known packs.
+                field entries: Vec<(String, PrefixedOptional<Tag>)>,
             }
             packet ConfigurationRemoveResourcePack 0x08 {
-                field uuid: Option<Uuid>,
+                // Every pack is removed if this is missing.
+                field uuid: PrefixedOptional<Uuid>,
             }
             packet ConfigurationAddResourcePack 0x09 {
                 field uuid: Uuid,
                 field url: String,
                 field hash: String,
                 field forced: bool,
-                field prompt: Option<Chat>,
+                field prompt: PrefixedOptional<TextComponent>,
             }
             packet ConfigurationStoreCookie 0x0A {
                 field key: String,
@@ -326,75 +723,2490 @@ packets!(
             }
             packet ConfigurationTransfer 0x0B {
                 field host: String,
-                field port: u16,
+                field port: VarInt,
             }
             packet FeatureFlags 0x0C {
                 field feature_flags: Vec<String>,
             }
             packet ConfigurationUpdateTags 0x0D {
-                // TODO: Implement
-                rest data,
+                // Registry identifiers paired with (tag name, entry ids) pairs.
+                field tags: Vec<(String, Vec<(String, Vec<VarInt>)>)>,
             }
             packet ClientboundKnownPacks 0x0E {
-                // TODO: Implement
-                rest data,
+                field known_packs: Vec<KnownPack>,
             }
             packet ConfigurationCustomReportDetails 0x0F {
-                // TODO: Implement
-                rest data,
+                // Pairs of title and description.
+                field details: Vec<(String, String)>,
             }
             packet ConfigurationServerLinks 0x10 {
-                // TODO: Implement
-                rest data,
+                field links: Vec<ServerLink>,
             }
         }
     }
     play Play {
-        serverbound Serverbound {}
-        clientbound Clientbound {
-            packet PlayDisconnect 0x17 {
-                field reason: Chat,
+        serverbound Serverbound {
+            packet ConfirmTeleportation 0x00 {
+                field teleport_id: VarInt,
+            }
+            packet QueryBlockEntityTag 0x01 {
+                field transaction_id: VarInt,
+                field location: Position,
+            }
+            packet BundleItemSelected 0x02 {
+                field slot_of_bundle: VarInt,
+                field slot_in_bundle: VarInt,
+            }
+            packet ServerboundChangeDifficulty 0x03 {
+                field new_difficulty: Difficulty,
+            }
+            since V1_21_5 packet ChangeGameMode 0x04 {
+                field game_mode: VarInt,
+            }
+            packet AcknowledgeMessage 0x05 {
+                field message_count: VarInt,
+            }
+            packet ChatCommand 0x06 {
+                field command: String,
+            }
+            packet SignedChatCommand 0x07 {
+                field command: String,
+                field timestamp: i64,
+                field salt: i64,
+                // Pairs of argument name and signature.
+                field argument_signatures: Vec<(String, [u8; 256])>,
+                field message_count: VarInt,
+                // The last 20 seen messages.
+                field acknowledged: FixedBitSet<3>,
+                since V1_21_5 field checksum: i8,
+            }
+            packet ChatMessage 0x08 {
+                field message: BoundedString<256>,
+                field timestamp: i64,
+                field salt: i64,
+                field signature: PrefixedOptional<[u8; 256]>,
+                field message_count: VarInt,
+                // The last 20 seen messages.
+                field acknowledged: FixedBitSet<3>,
+                since V1_21_5 field checksum: i8,
+            }
+            packet PlayerSession 0x09 {
+                field session_id: Uuid,
+                field expires_at: i64,
+                field public_key: Vec<u8>,
+                field key_signature: Vec<u8>,
+            }
+            packet ChunkBatchReceived 0x0A {
+                field chunks_per_tick: f32,
+            }
+            packet ClientStatus 0x0B {
+                field action_id: VarInt,
+            }
+            packet ClientTickEnd 0x0C {}
+            packet PlayClientInformation 0x0D {
+                field locale: String,
+                field view_distance: i8,
+                field chat_mode: VarInt,
+                field chat_colors: bool,
+                field displayed_skin_parts: u8,
+                field main_hand: VarInt,
+                field enable_text_filtering: bool,
+                field allow_server_listing: bool,
+                field particle_status: VarInt,
+            }
+            packet CommandSuggestionsRequest 0x0E {
+                field transaction_id: VarInt,
+                field text: String,
+            }
+            packet AcknowledgeConfiguration 0x0F {}
+            packet ClickContainerButton 0x10 {
+                field window_id: VarInt,
+                field button_id: VarInt,
+            }
+            packet ClickContainer 0x11 {
+                field window_id: VarInt,
+                field state_id: VarInt,
+                field slot: i16,
+                field button: i8,
+                field mode: VarInt,
+                // Pairs of slot number and the slot's new contents.
+                until V1_21_4 field changed_slot_items: Vec<(i16, Slot)>,
+                since V1_21_5 field changed_slots: Vec<(i16, HashedSlot)>,
+                until V1_21_4 field carried_slot_item: Slot,
+                since V1_21_5 field carried_item: HashedSlot,
+            }
+            packet ServerboundCloseContainer 0x12 {
+                field window_id: VarInt,
+            }
+            packet ChangeContainerSlotState 0x13 {
+                field slot_id: VarInt,
+                field window_id: VarInt,
+                field state: bool,
+            }
+            packet PlayCookieResponse 0x14 {
+                field key: String,
+                field payload: PrefixedOptional<Vec<u8>>,
+            }
+            packet PlayServerboundPluginMessage 0x15 {
+                field channel: String,
+                rest data,
+            }
+            packet DebugSampleSubscription 0x16 {
+                field sample_type: VarInt,
+            }
+            packet EditBook 0x17 {
+                field slot: VarInt,
+                field entries: Vec<String>,
+                field title: PrefixedOptional<String>,
+            }
+            packet QueryEntityTag 0x18 {
+                field transaction_id: VarInt,
+                field entity_id: VarInt,
+            }
+            packet Interact 0x19 {
+                field entity_id: VarInt,
+                field action: InteractAction,
+                field sneak_key_pressed: bool,
+            }
+            packet JigsawGenerate 0x1A {
+                field location: Position,
+                field levels: VarInt,
+                field keep_jigsaws: bool,
+            }
+            packet PlayServerboundKeepAlive 0x1B {
+                field payload: i64,
+            }
+            packet LockDifficulty 0x1C {
+                field locked: bool,
+            }
+            packet SetPlayerPosition 0x1D {
+                field x: f64,
+                field feet_y: f64,
+                field z: f64,
+                // 0x01: on ground, 0x02: pushing against wall.
+                field flags: u8,
+            }
+            packet SetPlayerPositionAndRotation 0x1E {
+                field x: f64,
+                field feet_y: f64,
+                field z: f64,
+                field yaw: f32,
+                field pitch: f32,
+                // 0x01: on ground, 0x02: pushing against wall.
+                field flags: u8,
+            }
+            packet SetPlayerRotation 0x1F {
+                field yaw: f32,
+                field pitch: f32,
+                // 0x01: on ground, 0x02: pushing against wall.
+                field flags: u8,
+            }
+            packet SetPlayerMovementFlags 0x20 {
+                // 0x01: on ground, 0x02: pushing against wall.
+                field flags: u8,
+            }
+            packet ServerboundMoveVehicle 0x21 {
+                field x: f64,
+                field y: f64,
+                field z: f64,
+                field yaw: f32,
+                field pitch: f32,
+                field on_ground: bool,
+            }
+            packet PaddleBoat 0x22 {
+                field left_paddle_turning: bool,
+                field right_paddle_turning: bool,
+            }
+            packet PickItemFromBlock 0x23 {
+                field location: Position,
+                field include_data: bool,
+            }
+            packet PickItemFromEntity 0x24 {
+                field entity_id: VarInt,
+                field include_data: bool,
+            }
+            packet PlayPingRequest 0x25 {
+                field payload: i64,
+            }
+            packet PlaceRecipe 0x26 {
+                field window_id: VarInt,
+                field recipe_id: VarInt,
+                field make_all: bool,
+            }
+            packet ServerboundPlayerAbilities 0x27 {
+                // 0x02: flying.
+                field flags: i8,
+            }
+            packet PlayerAction 0x28 {
+                field status: VarInt,
+                field location: Position,
+                field face: i8,
+                field sequence: VarInt,
+            }
+            packet PlayerCommand 0x29 {
+                field entity_id: VarInt,
+                field action_id: VarInt,
+                field jump_boost: VarInt,
+            }
+            packet PlayerInput 0x2A {
+                // 0x01: forward, 0x02: backward, 0x04: left, 0x08: right,
+                // 0x10: jump, 0x20: sneak, 0x40: sprint.
+                field flags: u8,
+            }
+            packet PlayerLoaded 0x2B {}
+            packet PlayPong 0x2C {
+                field payload: i32,
+            }
+            packet ChangeRecipeBookSettings 0x2D {
+                field book_id: VarInt,
+                field book_open: bool,
+                field filter_active: bool,
+            }
+            packet SetSeenRecipe 0x2E {
+                field recipe_id: VarInt,
+            }
+            packet RenameItem 0x2F {
+                field item_name: String,
+            }
+            packet PlayResourcePackResponse 0x30 {
+                field uuid: Uuid,
+                field result: VarInt,
+            }
+            packet SeenAdvancements 0x31 {
+                field action: SeenAdvancementsAction,
+            }
+            packet SelectTrade 0x32 {
+                field selected_slot: VarInt,
+            }
+            packet SetBeaconEffect 0x33 {
+                field primary_effect: PrefixedOptional<VarInt>,
+                field secondary_effect: PrefixedOptional<VarInt>,
+            }
+            packet ServerboundSetHeldItem 0x34 {
+                field slot: i16,
+            }
+            packet ProgramCommandBlock 0x35 {
+                field location: Position,
+                field command: String,
+                field mode: VarInt,
+                // 0x01: track output, 0x02: conditional, 0x04: automatic.
+                field flags: i8,
+            }
+            packet ProgramCommandBlockMinecart 0x36 {
+                field entity_id: VarInt,
+                field command: String,
+                field track_output: bool,
+            }
+            packet SetCreativeModeSlot 0x37 {
+                field slot: i16,
+                field clicked_item: Slot,
+            }
+            packet ProgramJigsawBlock 0x38 {
+                field location: Position,
+                field name: String,
+                field target: String,
+                field pool: String,
+                field final_state: String,
+                field joint_type: String,
+                field selection_priority: VarInt,
+                field placement_priority: VarInt,
+            }
+            packet ProgramStructureBlock 0x39 {
+                field location: Position,
+                field action: VarInt,
+                field mode: VarInt,
+                field name: String,
+                field offset: [i8; 3],
+                field size: [i8; 3],
+                field mirror: VarInt,
+                field rotation: VarInt,
+                field metadata: String,
+                field integrity: f32,
+                field seed: VarLong,
+                // 0x01: ignore entities, 0x02: show air, 0x04: show bounding box, 0x08: strict placement.
+                field flags: i8,
+            }
+            since V1_21_5 packet SetTestBlock 0x3A {
+                field location: Position,
+                field mode: VarInt,
+                field message: String,
+            }
+            packet UpdateSign 0x3B {
+                field location: Position,
+                field is_front_text: bool,
+                field lines: [String; 4],
+            }
+            packet SwingArm 0x3C {
+                field hand: VarInt,
+            }
+            packet TeleportToEntity 0x3D {
+                field target_player: Uuid,
+            }
+            since V1_21_5 packet TestInstanceBlockAction 0x3E {
+                field location: Position,
+                field action: TestInstanceAction,
+                field data: TestInstanceData,
+            }
+            packet UseItemOn 0x3F {
+                field hand: VarInt,
+                field location: Position,
+                field face: VarInt,
+                field cursor_position_x: f32,
+                field cursor_position_y: f32,
+                field cursor_position_z: f32,
+                field inside_block: bool,
+                field world_border_hit: bool,
+                field sequence: VarInt,
+            }
+            packet UseItem 0x40 {
+                field hand: VarInt,
+                field sequence: VarInt,
+                field yaw: f32,
+                field pitch: f32,
             }
         }
-    }
-);
-
-#[cfg(test)]
-mod tests {
-    use super::{Packet, PacketDirection};
-    use crate::protocol::{packets::handshake::serverbound::Handshake, types::VarInt, ClientState};
-
-    fn get_handshake() -> (Handshake, &'static [u8]) {
-        (
-            Handshake {
-                protocol_version: VarInt::from(767),
-                host: String::from("localhost"),
-                port: 25565,
-                next_state: ClientState::Status,
-            },
-            &[
-                // Packet length
-                0x10, // Packet ID
-                0x00, // protocol_version: VarInt
-                0xff, 0x05, // host: String
-                0x09, 0x6c, 0x6f, 0x63, 0x61, 0x6c, 0x68, 0x6f, 0x73, 0x74, // port: u16
-                0x63, 0xdd, // next_state: ClientState (VarInt)
-                0x01,
-            ],
-        )
-    }
-
-    #[test]
-    fn packet_parsing_works() {
-        let (handshake, handshake_bytes) = get_handshake();
-
-        let (rest, packet) = Packet::parse(
-            ClientState::Handshake,
-            PacketDirection::Serverbound,
-            handshake_bytes,
-        )
-        .unwrap();
-        assert_eq!(packet, Packet::Handshake(handshake));
-        assert!(rest.is_empty());
+        clientbound Clientbound {
+            packet BundleDelimiter 0x00 {}
+            packet SpawnEntity 0x01 {
+                field entity_id: VarInt,
+                field entity_uuid: Uuid,
+                field entity_type: VarInt,
+                field position: EntityPosition,
+                field pitch: Angle,
+                field yaw: Angle,
+                field head_yaw: Angle,
+                field data: VarInt,
+                field velocity: EntityVelocity,
+            }
+            until V1_21_4 packet SpawnExperienceOrb 0x02 {
+                field entity_id: VarInt,
+                field position: EntityPosition,
+                field count: i16,
+            }
+            packet EntityAnimation 0x02 {
+                field entity_id: VarInt,
+                field animation: u8,
+            }
+            packet AwardStatistics 0x03 {
+                // Tuples of category id, statistic id and value.
+                field statistics: Vec<(VarInt, VarInt, VarInt)>,
+            }
+            packet AcknowledgeBlockChange 0x04 {
+                field sequence_id: VarInt,
+            }
+            packet SetBlockDestroyStage 0x05 {
+                field entity_id: VarInt,
+                field location: Position,
+                field destroy_stage: u8,
+            }
+            packet BlockEntityData 0x06 {
+                field location: Position,
+                field block_entity_type: VarInt,
+                field data: Tag,
+            }
+            packet BlockAction 0x07 {
+                field location: Position,
+                field action_id: u8,
+                field action_parameter: u8,
+                field block_type: VarInt,
+            }
+            packet BlockUpdate 0x08 {
+                field location: Position,
+                field block_id: VarInt,
+            }
+            packet BossBar 0x09 {
+                field uuid: Uuid,
+                field action: BossBarAction,
+            }
+            packet ClientboundChangeDifficulty 0x0A {
+                field difficulty: Difficulty,
+                field locked: bool,
+            }
+            packet ChunkBatchFinished 0x0B {
+                field batch_size: VarInt,
+            }
+            packet ChunkBatchStart 0x0C {}
+            packet ChunkBiomes 0x0D {
+                // Tuples of chunk z, chunk x and biome data.
+                field chunks: Vec<(i32, i32, Vec<u8>)>,
+            }
+            packet ClearTitles 0x0E {
+                field reset: bool,
+            }
+            packet CommandSuggestionsResponse 0x0F {
+                field transaction_id: VarInt,
+                field start: VarInt,
+                field length: VarInt,
+                // Pairs of match and tooltip.
+                field matches: Vec<(String, PrefixedOptional<TextComponent>)>,
+            }
+            packet Commands 0x10 {
+                field nodes: Vec<CommandNode>,
+                // Index of the root node.
+                field root_index: VarInt,
+            }
+            packet ClientboundCloseContainer 0x11 {
+                field window_id: VarInt,
+            }
+            packet SetContainerContent 0x12 {
+                field window_id: VarInt,
+                field state_id: VarInt,
+                field slot_data: Vec<Slot>,
+                field carried_item: Slot,
+            }
+            packet SetContainerProperty 0x13 {
+                field window_id: VarInt,
+                field property: i16,
+                field value: i16,
+            }
+            packet SetContainerSlot 0x14 {
+                field window_id: VarInt,
+                field state_id: VarInt,
+                field slot: i16,
+                field slot_data: Slot,
+            }
+            packet PlayCookieRequest 0x15 {
+                field key: String,
+            }
+            packet SetCooldown 0x16 {
+                field cooldown_group: String,
+                field cooldown_ticks: VarInt,
+            }
+            packet ChatSuggestions 0x17 {
+                field action: VarInt,
+                field entries: Vec<String>,
+            }
+            packet PlayClientboundPluginMessage 0x18 {
+                field channel: String,
+                rest data,
+            }
+            packet DamageEvent 0x19 {
+                field entity_id: VarInt,
+                field source_type_id: VarInt,
+                field source_cause_id: VarInt,
+                field source_direct_id: VarInt,
+                field source_position: PrefixedOptional<(f64, f64, f64)>,
+            }
+            packet DebugSample 0x1A {
+                field sample: Vec<i64>,
+                field sample_type: VarInt,
+            }
+            packet DeleteMessage 0x1B {
+                // The signature of a message that wasn't cached by the client.
+                field message: IdOr<[u8; 256]>,
+            }
+            packet PlayDisconnect 0x1C {
+                field reason: TextComponent,
+            }
+            packet DisguisedChatMessage 0x1D {
+                field message: TextComponent,
+                field chat_type: IdOr<ChatType>,
+                field sender_name: TextComponent,
+                field target_name: PrefixedOptional<TextComponent>,
+            }
+            packet EntityEvent 0x1E {
+                field entity_id: i32,
+                field entity_status: i8,
+            }
+            packet TeleportEntity 0x1F {
+                field entity_id: VarInt,
+                field position: EntityPosition,
+                field velocity: (f64, f64, f64),
+                field yaw: f32,
+                field pitch: f32,
+                field on_ground: bool,
+            }
+            packet Explosion 0x20 {
+                field position: EntityPosition,
+                field player_velocity: PrefixedOptional<(f64, f64, f64)>,
+                field particle: particle::Particle,
+                field sound: IdOr<SoundEvent>,
+            }
+            packet UnloadChunk 0x21 {
+                field chunk_z: i32,
+                field chunk_x: i32,
+            }
+            packet GameEvent 0x22 {
+                field event: u8,
+                field value: f32,
+            }
+            packet OpenHorseScreen 0x23 {
+                field window_id: VarInt,
+                field inventory_columns: VarInt,
+                field entity_id: i32,
+            }
+            packet HurtAnimation 0x24 {
+                field entity_id: VarInt,
+                field yaw: f32,
+            }
+            packet InitializeWorldBorder 0x25 {
+                field x: f64,
+                field z: f64,
+                field old_diameter: f64,
+                field new_diameter: f64,
+                field speed: VarLong,
+                field portal_teleport_boundary: VarInt,
+                field warning_blocks: VarInt,
+                field warning_time: VarInt,
+            }
+            packet PlayClientboundKeepAlive 0x26 {
+                field payload: i64,
+            }
+            packet ChunkDataAndUpdateLight 0x27 {
+                field chunk_x: i32,
+                field chunk_z: i32,
+                // A compound of heightmap name to packed heights.
+                until V1_21_4 field heightmap_tag: Tag,
+                // Pairs of heightmap type and packed heights.
+                since V1_21_5 field heightmaps: Vec<(VarInt, Vec<i64>)>,
+                field data: Vec<u8>,
+                field block_entities: Vec<ChunkBlockEntity>,
+                field light: LightData,
+            }
+            packet WorldEvent 0x28 {
+                field event: i32,
+                field location: Position,
+                field data: i32,
+                field disable_relative_volume: bool,
+            }
+            packet Particle 0x29 {
+                field long_distance: bool,
+                field always_visible: bool,
+                field position: EntityPosition,
+                field offset: (f32, f32, f32),
+                field max_speed: f32,
+                field particle_count: i32,
+                field particle: particle::Particle,
+            }
+            packet UpdateLight 0x2A {
+                field chunk_x: VarInt,
+                field chunk_z: VarInt,
+                field light: LightData,
+            }
+            packet LoginPlay 0x2B {
+                field entity_id: i32,
+                field is_hardcore: bool,
+                field dimension_names: Vec<String>,
+                field max_players: VarInt,
+                field view_distance: VarInt,
+                field simulation_distance: VarInt,
+                field reduced_debug_info: bool,
+                field enable_respawn_screen: bool,
+                field do_limited_crafting: bool,
+                field dimension_type: VarInt,
+                field dimension_name: String,
+                field hashed_seed: i64,
+                field game_mode: u8,
+                field previous_game_mode: i8,
+                field is_debug: bool,
+                field is_flat: bool,
+                // Pair of dimension name and location.
+                field death_location: PrefixedOptional<(String, Position)>,
+                field portal_cooldown: VarInt,
+                field sea_level: VarInt,
+                field enforces_secure_chat: bool,
+            }
+            packet MapData 0x2C {
+                field map_id: VarInt,
+                field scale: i8,
+                field locked: bool,
+                field icons: PrefixedOptional<Vec<MapIcon>>,
+                field color_patch: MapColorPatch,
+            }
+            packet MerchantOffers 0x2D {
+                field window_id: VarInt,
+                field trades: Vec<MerchantOffer>,
+                field villager_level: VarInt,
+                field experience: VarInt,
+                field is_regular_villager: bool,
+                field can_restock: bool,
+            }
+            packet UpdateEntityPosition 0x2E {
+                field entity_id: VarInt,
+                field delta: EntityVelocity,
+                field on_ground: bool,
+            }
+            packet UpdateEntityPositionAndRotation 0x2F {
+                field entity_id: VarInt,
+                field delta: EntityVelocity,
+                field yaw: Angle,
+                field pitch: Angle,
+                field on_ground: bool,
+            }
+            packet MoveMinecartAlongTrack 0x30 {
+                field entity_id: VarInt,
+                field steps: Vec<MinecartStep>,
+            }
+            packet UpdateEntityRotation 0x31 {
+                field entity_id: VarInt,
+                field yaw: Angle,
+                field pitch: Angle,
+                field on_ground: bool,
+            }
+            packet ClientboundMoveVehicle 0x32 {
+                field position: EntityPosition,
+                field yaw: f32,
+                field pitch: f32,
+            }
+            packet OpenBook 0x33 {
+                field hand: VarInt,
+            }
+            packet OpenScreen 0x34 {
+                field window_id: VarInt,
+                field window_type: VarInt,
+                field window_title: TextComponent,
+            }
+            packet OpenSignEditor 0x35 {
+                field location: Position,
+                field is_front_text: bool,
+            }
+            packet PlayPing 0x36 {
+                field payload: i32,
+            }
+            packet PlayPingResponse 0x37 {
+                field payload: i64,
+            }
+            packet PlaceGhostRecipe 0x38 {
+                field window_id: VarInt,
+                field recipe_display: RecipeDisplay,
+            }
+            packet ClientboundPlayerAbilities 0x39 {
+                // 0x01: invulnerable, 0x02: flying, 0x04: allow flying, 0x08: instant break.
+                field flags: i8,
+                field flying_speed: f32,
+                field field_of_view_modifier: f32,
+            }
+            packet PlayerChatMessage 0x3A {
+                since V1_21_5 field global_index: VarInt,
+                field sender: Uuid,
+                field index: VarInt,
+                field message_signature: PrefixedOptional<[u8; 256]>,
+                field message: String,
+                field timestamp: i64,
+                field salt: i64,
+                // Signatures of messages the sender had seen, by index into the
+                // client's message cache unless the client hasn't cached it.
+                field previous_messages: Vec<IdOr<[u8; 256]>>,
+                field unsigned_content: PrefixedOptional<TextComponent>,
+                field filter: ChatFilter,
+                field chat_type: IdOr<ChatType>,
+                field sender_name: TextComponent,
+                field target_name: PrefixedOptional<TextComponent>,
+            }
+            packet EndCombat 0x3B {
+                field duration: VarInt,
+            }
+            packet EnterCombat 0x3C {}
+            packet CombatDeath 0x3D {
+                field player_id: VarInt,
+                field message: TextComponent,
+            }
+            packet PlayerInfoRemove 0x3E {
+                field uuids: Vec<Uuid>,
+            }
+            packet PlayerInfoUpdate 0x3F {
+                field players: PlayerInfoUpdates,
+            }
+            packet LookAt 0x40 {
+                // 0: feet, 1: eyes.
+                field feet_eyes: VarInt,
+                field target: EntityPosition,
+                // Pair of entity id and which part of the entity to look at.
+                field entity: PrefixedOptional<(VarInt, VarInt)>,
+            }
+            packet SynchronizePlayerPosition 0x41 {
+                field teleport_id: VarInt,
+                field position: EntityPosition,
+                field velocity: (f64, f64, f64),
+                field yaw: f32,
+                field pitch: f32,
+                // Bit field of which values are relative.
+                field flags: i32,
+            }
+            packet PlayerRotation 0x42 {
+                field yaw: f32,
+                field pitch: f32,
+            }
+            packet RecipeBookAdd 0x43 {
+                field recipes: Vec<RecipeBookEntry>,
+                // Whether the recipes replace the recipe book, instead of being added to it.
+                field replace: bool,
+            }
+            packet RecipeBookRemove 0x44 {
+                field recipes: Vec<VarInt>,
+            }
+            packet RecipeBookSettings 0x45 {
+                // Open and filter active for crafting, furnace, blast furnace and smoker.
+                field settings: [bool; 8],
+            }
+            packet RemoveEntities 0x46 {
+                field entity_ids: Vec<VarInt>,
+            }
+            packet RemoveEntityEffect 0x47 {
+                field entity_id: VarInt,
+                field effect_id: VarInt,
+            }
+            packet ResetScore 0x48 {
+                field entity_name: String,
+                field objective_name: PrefixedOptional<String>,
+            }
+            packet PlayRemoveResourcePack 0x49 {
+                field uuid: PrefixedOptional<Uuid>,
+            }
+            packet PlayAddResourcePack 0x4A {
+                field uuid: Uuid,
+                field url: String,
+                field hash: String,
+                field forced: bool,
+                field prompt: PrefixedOptional<TextComponent>,
+            }
+            packet Respawn 0x4B {
+                field dimension_type: VarInt,
+                field dimension_name: String,
+                field hashed_seed: i64,
+                field game_mode: u8,
+                field previous_game_mode: i8,
+                field is_debug: bool,
+                field is_flat: bool,
+                // Pair of dimension name and location.
+                field death_location: PrefixedOptional<(String, Position)>,
+                field portal_cooldown: VarInt,
+                field sea_level: VarInt,
+                // 0x01: keep attributes, 0x02: keep metadata.
+                field data_kept: i8,
+            }
+            packet SetHeadRotation 0x4C {
+                field entity_id: VarInt,
+                field head_yaw: Angle,
+            }
+            packet UpdateSectionBlocks 0x4D {
+                field chunk_section_position: i64,
+                // Block state id << 12 | (x << 8 | z << 4 | y).
+                field blocks: Vec<VarLong>,
+            }
+            packet SelectAdvancementsTab 0x4E {
+                field identifier: PrefixedOptional<String>,
+            }
+            packet ServerData 0x4F {
+                field motd: TextComponent,
+                field icon: PrefixedOptional<Vec<u8>>,
+            }
+            packet SetActionBarText 0x50 {
+                field text: TextComponent,
+            }
+            packet SetBorderCenter 0x51 {
+                field x: f64,
+                field z: f64,
+            }
+            packet SetBorderLerpSize 0x52 {
+                field old_diameter: f64,
+                field new_diameter: f64,
+                field speed: VarLong,
+            }
+            packet SetBorderSize 0x53 {
+                field diameter: f64,
+            }
+            packet SetBorderWarningDelay 0x54 {
+                field warning_time: VarInt,
+            }
+            packet SetBorderWarningDistance 0x55 {
+                field warning_blocks: VarInt,
+            }
+            packet SetCamera 0x56 {
+                field camera_id: VarInt,
+            }
+            packet SetCenterChunk 0x57 {
+                field chunk_x: VarInt,
+                field chunk_z: VarInt,
+            }
+            packet SetRenderDistance 0x58 {
+                field view_distance: VarInt,
+            }
+            packet SetCursorItem 0x59 {
+                field carried_item: Slot,
+            }
+            packet SetDefaultSpawnPosition 0x5A {
+                field location: Position,
+                field angle: f32,
+            }
+            packet DisplayObjective 0x5B {
+                field position: VarInt,
+                field score_name: String,
+            }
+            packet SetEntityMetadata 0x5C {
+                field entity_id: VarInt,
+                field metadata: EntityMetadata,
+            }
+            packet LinkEntities 0x5D {
+                field attached_entity_id: i32,
+                field holding_entity_id: i32,
+            }
+            packet SetEntityVelocity 0x5E {
+                field entity_id: VarInt,
+                field velocity: EntityVelocity,
+            }
+            packet SetEquipment 0x5F {
+                field entity_id: VarInt,
+                field equipment: Equipment,
+            }
+            packet SetExperience 0x60 {
+                field experience_bar: f32,
+                field level: VarInt,
+                field total_experience: VarInt,
+            }
+            packet SetHealth 0x61 {
+                field health: f32,
+                field food: VarInt,
+                field food_saturation: f32,
+            }
+            packet ClientboundSetHeldItem 0x62 {
+                field slot: VarInt,
+            }
+            packet UpdateObjectives 0x63 {
+                field objective_name: String,
+                field action: ObjectiveAction,
+            }
+            packet SetPassengers 0x64 {
+                field entity_id: VarInt,
+                field passengers: Vec<VarInt>,
+            }
+            packet SetPlayerInventorySlot 0x65 {
+                field slot: VarInt,
+                field slot_data: Slot,
+            }
+            packet UpdateTeams 0x66 {
+                field team_name: String,
+                field action: TeamAction,
+            }
+            packet UpdateScore 0x67 {
+                field entity_name: String,
+                field objective_name: String,
+                field value: VarInt,
+                field display_name: PrefixedOptional<TextComponent>,
+                field number_format: PrefixedOptional<NumberFormat>,
+            }
+            packet SetSimulationDistance 0x68 {
+                field simulation_distance: VarInt,
+            }
+            packet SetSubtitleText 0x69 {
+                field subtitle_text: TextComponent,
+            }
+            packet UpdateTime 0x6A {
+                field world_age: i64,
+                field time_of_day: i64,
+                field time_of_day_increasing: bool,
+            }
+            packet SetTitleText 0x6B {
+                field title_text: TextComponent,
+            }
+            packet SetTitleAnimationTimes 0x6C {
+                field fade_in: i32,
+                field stay: i32,
+                field fade_out: i32,
+            }
+            packet EntitySoundEffect 0x6D {
+                field sound_event: IdOr<SoundEvent>,
+                field sound_category: SoundCategory,
+                field entity_id: VarInt,
+                field volume: f32,
+                field pitch: f32,
+                field seed: i64,
+            }
+            packet SoundEffect 0x6E {
+                field sound_event: IdOr<SoundEvent>,
+                field sound_category: SoundCategory,
+                // Fixed-point block coordinates, multiplied by 8.
+                field x: i32,
+                field y: i32,
+                field z: i32,
+                field volume: f32,
+                field pitch: f32,
+                field seed: i64,
+            }
+            packet StartConfiguration 0x6F {}
+            packet StopSound 0x70 {
+                field filter: StopSoundFilter,
+            }
+            packet PlayStoreCookie 0x71 {
+                field key: String,
+                field payload: Vec<u8>,
+            }
+            packet SystemChatMessage 0x72 {
+                field content: TextComponent,
+                // Whether the message is shown above the hotbar instead of in chat.
+                field overlay: bool,
+            }
+            packet SetTabListHeaderAndFooter 0x73 {
+                field header: TextComponent,
+                field footer: TextComponent,
+            }
+            packet TagQueryResponse 0x74 {
+                field transaction_id: VarInt,
+                field nbt: Tag,
+            }
+            packet PickupItem 0x75 {
+                field collected_entity_id: VarInt,
+                field collector_entity_id: VarInt,
+                field pickup_item_count: VarInt,
+            }
+            packet SynchronizeVehiclePosition 0x76 {
+                field entity_id: VarInt,
+                field position: EntityPosition,
+                field velocity: (f64, f64, f64),
+                field yaw: f32,
+                field pitch: f32,
+                field on_ground: bool,
+            }
+            since V1_21_5 packet TestInstanceBlockStatus 0x77 {
+                field status: TextComponent,
+                field size: PrefixedOptional<(f64, f64, f64)>,
+            }
+            packet SetTickingState 0x78 {
+                field tick_rate: f32,
+                field is_frozen: bool,
+            }
+            packet StepTick 0x79 {
+                field tick_steps: VarInt,
+            }
+            packet PlayTransfer 0x7A {
+                field host: String,
+                field port: VarInt,
+            }
+            packet UpdateAdvancements 0x7B {
+                field reset: bool,
+                field advancements: Vec<(Identifier, Advancement)>,
+                field removed: Vec<Identifier>,
+                field progress: Vec<(Identifier, AdvancementProgress)>,
+                // Whether the client shows toasts for newly completed advancements.
+                since V1_21_5 field show_advancements: bool,
+            }
+            packet UpdateAttributes 0x7C {
+                field entity_id: VarInt,
+                // Tuples of attribute id, base value and modifiers (id, amount, operation).
+                field attributes: Vec<(VarInt, f64, Vec<(String, f64, i8)>)>,
+            }
+            packet EntityEffect 0x7D {
+                field entity_id: VarInt,
+                field effect_id: VarInt,
+                field amplifier: VarInt,
+                field duration: VarInt,
+                // 0x01: ambient, 0x02: show particles, 0x04: show icon, 0x08: blend.
+                field flags: i8,
+            }
+            packet UpdateRecipes 0x7E {
+                // Pairs of property set id and item ids, such as the items a furnace accepts.
+                field property_sets: Vec<(Identifier, Vec<VarInt>)>,
+                field stonecutter_recipes: Vec<StonecutterRecipe>,
+            }
+            packet PlayUpdateTags 0x7F {
+                // Registry identifiers paired with (tag name, entry ids) pairs.
+                field tags: Vec<(String, Vec<(String, Vec<VarInt>)>)>,
+            }
+            packet ProjectilePower 0x80 {
+                field entity_id: VarInt,
+                field power: f64,
+            }
+            packet PlayCustomReportDetails 0x81 {
+                // Pairs of title and description.
+                field details: Vec<(String, String)>,
+            }
+            packet PlayServerLinks 0x82 {
+                field links: Vec<ServerLink>,
+            }
+        }
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::{Packet, PacketDirection, PacketKind};
+    use crate::protocol::{
+        commands::{ArgumentParser, CommandNode, CommandNodeKind, NumberRange},
+        entities::{
+            metadata::{EntityMetadata, EntityMetadataEntry, EntityMetadataEntryKind, EntityPose},
+            particle, EntityPosition, InteractAction,
+        },
+        inventory::{
+            component::ItemComponent,
+            slot::{HashedItemStack, ItemStack, Slot},
+            Equipment, EquipmentSlot,
+        },
+        nbt::Tag,
+        packets::{
+            handshake::serverbound::Handshake,
+            login::serverbound::{LoginCookieResponse, LoginPluginResponse, LoginStart},
+            play::{clientbound::*, serverbound::*},
+        },
+        parsing::Parsable,
+        text::TextComponent,
+        types::{
+            BitSet, ChatFilter, ChunkBlockEntity, FixedBitSet, IdOr, LightData, OptionalVarInt,
+            Position, PrefixedOptional, SoundCategory, SoundEvent, StopSoundFilter,
+            TestInstanceAction, TestInstanceData, TestInstanceStatus, Uuid, VarInt,
+        },
+        ClientState, HandshakeIntent, ProtocolVersion,
+    };
+
+    /// Check that `bytes` (including the length prefix) parses to `packet` and back.
+    fn assert_round_trip(
+        client_state: ClientState,
+        direction: PacketDirection,
+        packet: Packet,
+        bytes: &[u8],
+    ) {
+        let (rest, parsed) = Packet::parse(client_state, direction, bytes).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(parsed, packet);
+
+        let (packet_id, body) = packet.serialize();
+        let mut frame = VarInt::from(packet_id.serialize().len() + body.len()).serialize();
+        frame.extend(packet_id.serialize());
+        frame.extend(body);
+        assert_eq!(frame, bytes);
+
+        let mut frame = vec![];
+        packet
+            .serialize_frame_versioned_into(ProtocolVersion::LATEST, &mut frame)
+            .unwrap();
+        assert_eq!(
+            packet.frame_len_versioned(ProtocolVersion::LATEST),
+            Some(frame.len())
+        );
+        assert_eq!(frame, bytes[bytes.len() - frame.len()..]);
+    }
+
+    fn get_handshake() -> (Handshake, &'static [u8]) {
+        (
+            Handshake {
+                protocol_version: VarInt::from(767),
+                host: "localhost".into(),
+                port: 25565,
+                intent: HandshakeIntent::Status,
+            },
+            &[
+                0x10, // Packet length
+                0x00, // Packet ID
+                0xff, 0x05, // protocol_version: VarInt
+                0x09, b'l', b'o', b'c', b'a', b'l', b'h', b'o', b's', b't', // host: String
+                0x63, 0xdd, // port: u16
+                0x01, // intent: HandshakeIntent (VarInt)
+            ],
+        )
+    }
+
+    #[test]
+    fn packet_parsing_works() {
+        let (handshake, handshake_bytes) = get_handshake();
+
+        let (rest, packet) = Packet::parse(
+            ClientState::Handshake,
+            PacketDirection::Serverbound,
+            handshake_bytes,
+        )
+        .unwrap();
+        assert_eq!(packet, Packet::Handshake(handshake));
+        assert!(rest.is_empty());
+        assert_eq!(packet.state_change(), Some(ClientState::Status));
+        let Packet::Handshake(parsed) = packet else {
+            unreachable!()
+        };
+        assert_eq!(*parsed.protocol_version, 767);
+        assert_eq!(parsed.port, 25565);
+    }
+    #[test]
+    fn handshake_transfer_intent_works() {
+        let (handshake, handshake_bytes) = get_handshake();
+        let mut transfer_bytes = handshake_bytes.to_vec();
+        *transfer_bytes.last_mut().unwrap() = 0x03;
+        let transfer: Packet = Handshake {
+            intent: HandshakeIntent::Transfer,
+            ..handshake
+        }
+        .into();
+        assert_round_trip(
+            ClientState::Handshake,
+            PacketDirection::Serverbound,
+            transfer.clone(),
+            &transfer_bytes,
+        );
+        assert_eq!(transfer.state_change(), Some(ClientState::Login));
+
+        *transfer_bytes.last_mut().unwrap() = 0x04;
+        assert!(Packet::parse(
+            ClientState::Handshake,
+            PacketDirection::Serverbound,
+            &transfer_bytes
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn frame_errors_work() {
+        use crate::protocol::error::{ParseError, ParseErrorKind};
+
+        let error = |client_state, frame: &[u8]| {
+            Packet::frame_error(
+                ProtocolVersion::LATEST,
+                client_state,
+                PacketDirection::Serverbound,
+                frame,
+            )
+        };
+        // The port is cut off.
+        let (_, handshake_bytes) = get_handshake();
+        assert_eq!(
+            error(ClientState::Handshake, &handshake_bytes[1..15]),
+            ParseError {
+                client_state: ClientState::Handshake,
+                direction: PacketDirection::Serverbound,
+                packet_id: Some(0x00),
+                packet_name: Some("Handshake"),
+                field: Some("port"),
+                offset: 13,
+                kind: ParseErrorKind::UnexpectedEnd,
+            }
+        );
+        let mut transfer_bytes = handshake_bytes[1..].to_vec();
+        *transfer_bytes.last_mut().unwrap() = 0x04;
+        let invalid = error(ClientState::Handshake, &transfer_bytes);
+        assert_eq!(
+            (invalid.field, invalid.offset, invalid.kind),
+            (Some("intent"), 15, ParseErrorKind::InvalidData)
+        );
+        transfer_bytes.extend([0x01, 0x00]);
+        *transfer_bytes.get_mut(15).unwrap() = 0x01;
+        let trailing = error(ClientState::Handshake, &transfer_bytes);
+        assert_eq!(
+            (trailing.field, trailing.offset, trailing.kind),
+            (None, 16, ParseErrorKind::TrailingBytes(2))
+        );
+
+        let unknown = error(ClientState::Status, &[0x7f]);
+        assert_eq!(
+            (unknown.packet_id, unknown.packet_name, unknown.kind),
+            (Some(0x7f), None, ParseErrorKind::UnknownPacketId)
+        );
+        assert_eq!(
+            error(ClientState::Status, &[]).kind,
+            ParseErrorKind::UnexpectedEnd
+        );
+    }
+
+    #[test]
+    fn login_responses_work() {
+        assert_round_trip(
+            ClientState::Login,
+            PacketDirection::Serverbound,
+            LoginPluginResponse {
+                message_id: VarInt::from(3),
+                successful: true,
+                data: vec![0xca, 0xfe],
+            }
+            .into(),
+            &[
+                0x05, // Packet length
+                0x02, // Packet ID
+                0x03, // message_id: VarInt
+                0x01, // successful: bool
+                0xca, 0xfe, // data: rest
+            ],
+        );
+        // A client that doesn't understand the channel sends no data.
+        let (rest, unknown) = LoginPluginResponse::parse(&[0x03, 0x00]).unwrap();
+        assert!(rest.is_empty());
+        assert!(!unknown.successful);
+        assert!(unknown.data.is_empty());
+        assert_round_trip(
+            ClientState::Login,
+            PacketDirection::Serverbound,
+            LoginCookieResponse {
+                key: String::from("a:b"),
+                payload: PrefixedOptional(None),
+            }
+            .into(),
+            &[
+                0x06, // Packet length
+                0x04, // Packet ID
+                0x03, b'a', b':', b'b', // key: String
+                0x00, // payload: PrefixedOptional<Vec<u8>>
+            ],
+        );
+        let (rest, cookie) =
+            LoginCookieResponse::parse(&[0x03, b'a', b':', b'b', 0x01, 0x02, 0xca, 0xfe]).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(cookie.payload.as_deref(), Some(&[0xca, 0xfe][..]));
+    }
+    #[test]
+    fn login_start_works() {
+        assert_round_trip(
+            ClientState::Login,
+            PacketDirection::Serverbound,
+            LoginStart {
+                name: "test".into(),
+                uuid: Uuid::from_u128(0x2a),
+            }
+            .into(),
+            &[
+                0x16, // Packet length
+                0x00, // Packet ID
+                0x04, b't', b'e', b's', b't', // name: String
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // uuid: Uuid
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x2a,
+            ],
+        );
+
+        // Names are limited to 16 characters.
+        let mut long_name = vec![0x00];
+        long_name.extend("a".repeat(17).serialize());
+        long_name.extend(Uuid::nil().serialize());
+        let error = Packet::frame_error(
+            ProtocolVersion::LATEST,
+            ClientState::Login,
+            PacketDirection::Serverbound,
+            &long_name,
+        );
+        assert_eq!(
+            (error.field, error.kind),
+            (
+                Some("name"),
+                crate::protocol::error::ParseErrorKind::InvalidData
+            )
+        );
+    }
+    #[test]
+    fn play_clientbound_sound_effect_works() {
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Clientbound,
+            SoundEffect {
+                sound_event: IdOr::Inline(SoundEvent {
+                    sound_name: "a:b".parse().unwrap(),
+                    fixed_range: PrefixedOptional(None),
+                }),
+                sound_category: SoundCategory::Block,
+                x: 8,
+                y: -8,
+                z: 0,
+                volume: 1.0,
+                pitch: 1.0,
+                seed: 2,
+            }
+            .into(),
+            &[
+                0x24, // Packet length
+                0x6e, // Packet ID
+                0x00, // sound_event: IdOr<SoundEvent>, inline
+                0x03, b'a', b':', b'b', // sound_name: Identifier
+                0x00, // fixed_range: PrefixedOptional<f32>
+                0x04, // sound_category: SoundCategory
+                0x00, 0x00, 0x00, 0x08, // x: i32
+                0xff, 0xff, 0xff, 0xf8, // y: i32
+                0x00, 0x00, 0x00, 0x00, // z: i32
+                0x3f, 0x80, 0x00, 0x00, // volume: f32
+                0x3f, 0x80, 0x00, 0x00, // pitch: f32
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, // seed: i64
+            ],
+        );
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Clientbound,
+            EntitySoundEffect {
+                sound_event: IdOr::Id(VarInt::from(5)),
+                sound_category: SoundCategory::Hostile,
+                entity_id: VarInt::from(1),
+                volume: 0.0,
+                pitch: 0.0,
+                seed: 0,
+            }
+            .into(),
+            &[
+                0x14, // Packet length
+                0x6d, // Packet ID
+                0x06, // sound_event: IdOr<SoundEvent>, one more than the registry id
+                0x05, // sound_category: SoundCategory
+                0x01, // entity_id: VarInt
+                0x00, 0x00, 0x00, 0x00, // volume: f32
+                0x00, 0x00, 0x00, 0x00, // pitch: f32
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // seed: i64
+            ],
+        );
+        // A sound with a fixed range sends it after the name.
+        let (rest, sound) = IdOr::<SoundEvent>::parse(&[
+            0x00, 0x03, b'a', b':', b'b', 0x01, 0x41, 0x80, 0x00, 0x00,
+        ])
+        .unwrap();
+        assert!(rest.is_empty());
+        let IdOr::Inline(sound) = sound else {
+            panic!("expected an inline sound, got {sound:?}");
+        };
+        assert_eq!(sound.fixed_range.0, Some(16.0));
+        // There's no category after `Voice`.
+        assert!(SoundCategory::parse(&[0x0a]).is_err());
+    }
+    #[test]
+    fn play_serverbound_movement_works() {
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Serverbound,
+            SetPlayerPositionAndRotation {
+                x: 1.5,
+                feet_y: 64.0,
+                z: -2.25,
+                yaw: 90.0,
+                pitch: -45.0,
+                flags: 0x01,
+            }
+            .into(),
+            &[
+                0x22, // Packet length
+                0x1e, // Packet ID
+                0x3f, 0xf8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // x: f64
+                0x40, 0x50, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // feet_y: f64
+                0xc0, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // z: f64
+                0x42, 0xb4, 0x00, 0x00, // yaw: f32
+                0xc2, 0x34, 0x00, 0x00, // pitch: f32
+                0x01, // flags: u8
+            ],
+        );
+        // The position is cut off before the flags.
+        let bytes = SetPlayerPositionAndRotation::default().serialize();
+        assert!(SetPlayerPositionAndRotation::parse(&bytes[..bytes.len() - 1]).is_err());
+    }
+    #[test]
+    fn play_serverbound_interact_works() {
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Serverbound,
+            Interact {
+                entity_id: VarInt::from(42),
+                action: InteractAction::Attack,
+                sneak_key_pressed: false,
+            }
+            .into(),
+            &[
+                0x04, // Packet length
+                0x19, // Packet ID
+                0x2a, // entity_id: VarInt
+                0x01, // action: VarInt
+                0x00, // sneak_key_pressed: bool
+            ],
+        );
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Serverbound,
+            Interact {
+                entity_id: VarInt::from(300),
+                action: InteractAction::InteractAt {
+                    target_x: 0.5,
+                    target_y: 1.0,
+                    target_z: 0.0,
+                    hand: VarInt::from(1),
+                },
+                sneak_key_pressed: true,
+            }
+            .into(),
+            &[
+                0x12, // Packet length
+                0x19, // Packet ID
+                0xac, 0x02, // entity_id: VarInt
+                0x02, // action: VarInt
+                0x3f, 0x00, 0x00, 0x00, // target_x: f32
+                0x3f, 0x80, 0x00, 0x00, // target_y: f32
+                0x00, 0x00, 0x00, 0x00, // target_z: f32
+                0x01, // hand: VarInt
+                0x01, // sneak_key_pressed: bool
+            ],
+        );
+        // There's no action after `InteractAt`.
+        assert!(Interact::parse(&[0x2a, 0x03, 0x00]).is_err());
+    }
+    #[test]
+    fn play_serverbound_chat_message_works() {
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Serverbound,
+            ChatMessage {
+                message: "hi".into(),
+                timestamp: 1,
+                salt: 2,
+                signature: PrefixedOptional(None),
+                message_count: VarInt::from(0),
+                acknowledged: FixedBitSet::default(),
+                checksum: 1,
+            }
+            .into(),
+            &[
+                0x1a, // Packet length
+                0x08, // Packet ID
+                0x02, b'h', b'i', // message: String
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, // timestamp: i64
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, // salt: i64
+                0x00, // signature: PrefixedOptional<[u8; 256]>
+                0x00, // message_count: VarInt
+                0x00, 0x00, 0x00, // acknowledged: FixedBitSet<3>
+                0x01, // checksum: i8
+            ],
+        );
+        // Signed messages carry the whole signature inline.
+        let signed = ChatMessage {
+            message: "hi".into(),
+            signature: PrefixedOptional(Some([0xab; 256])),
+            ..Default::default()
+        };
+        let bytes = signed.serialize();
+        assert_eq!(bytes.len(), 3 + 8 + 8 + 1 + 256 + 1 + 3 + 1);
+        let (rest, parsed) = ChatMessage::parse(&bytes).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(parsed.signature.as_ref(), Some(&[0xab; 256]));
+    }
+    #[test]
+    fn play_serverbound_signed_chat_command_works() {
+        let mut bytes = vec![
+            0xa8, 0x02, // Packet length
+            0x07, // Packet ID
+            0x08, b'm', b's', b'g', b' ', b'a', b' ', b'h', b'i', // command: String
+            0x00, 0x00, 0x01, 0x96, 0x8e, 0x2c, 0x4b, 0x00, // timestamp: i64
+            0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, // salt: i64
+            0x01, // argument_signatures: Vec<(String, [u8; 256])>
+            0x07, b'm', b'e', b's', b's', b'a', b'g', b'e', // argument name
+        ];
+        bytes.extend([0x5a; 256]); // its signature
+        bytes.extend([
+            0x01, // message_count: VarInt
+            0x01, 0x00, 0x00, // acknowledged: FixedBitSet<3>
+            0x15, // checksum: i8
+        ]);
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Serverbound,
+            SignedChatCommand {
+                command: "msg a hi".into(),
+                timestamp: 0x0196_8e2c_4b00,
+                salt: i64::MAX,
+                argument_signatures: vec![("message".into(), [0x5a; 256])],
+                message_count: VarInt::from(1),
+                acknowledged: FixedBitSet([0x01, 0x00, 0x00]),
+                checksum: 0x15,
+            }
+            .into(),
+            &bytes,
+        );
+    }
+    #[test]
+    fn play_serverbound_click_container_works() {
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Serverbound,
+            ClickContainer {
+                window_id: VarInt::from(1),
+                state_id: VarInt::from(5),
+                slot: 36,
+                button: 0,
+                mode: VarInt::from(0),
+                changed_slots: vec![(
+                    36,
+                    PrefixedOptional(Some(HashedItemStack {
+                        item_id: VarInt::from(1),
+                        count: VarInt::from(64),
+                        components_to_add: vec![(VarInt::from(5), 0x12345678)],
+                        components_to_remove: vec![],
+                    })),
+                )],
+                carried_item: PrefixedOptional(None),
+                ..Default::default()
+            }
+            .into(),
+            &[
+                0x15, // Packet length
+                0x11, // Packet ID
+                0x01, // window_id: VarInt
+                0x05, // state_id: VarInt
+                0x00, 0x24, // slot: i16
+                0x00, // button: i8
+                0x00, // mode: VarInt
+                0x01, // changed_slots: Vec<(i16, HashedSlot)>
+                0x00, 0x24, // slot 36
+                0x01, 0x01, 0x40, // 64 of item 1
+                0x01, 0x05, 0x12, 0x34, 0x56, 0x78, // minecraft:custom_name and its hash
+                0x00, // no components removed
+                0x00, // carried_item: HashedSlot
+            ],
+        );
+        // Right clicking a stack of 64 picks up half of it.
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Serverbound,
+            ClickContainer {
+                window_id: VarInt::from(2),
+                state_id: VarInt::from(129),
+                slot: 40,
+                button: 1,
+                mode: VarInt::from(0),
+                changed_slots: vec![(
+                    40,
+                    PrefixedOptional(Some(HashedItemStack {
+                        item_id: VarInt::from(1),
+                        count: VarInt::from(32),
+                        ..Default::default()
+                    })),
+                )],
+                carried_item: PrefixedOptional(Some(HashedItemStack {
+                    item_id: VarInt::from(1),
+                    count: VarInt::from(32),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            }
+            .into(),
+            &[
+                0x15, // Packet length
+                0x11, // Packet ID
+                0x02, // window_id: VarInt
+                0x81, 0x01, // state_id: VarInt
+                0x00, 0x28, // slot: i16
+                0x01, // button: i8
+                0x00, // mode: VarInt
+                0x01, // changed_slots: Vec<(i16, HashedSlot)>
+                0x00, 0x28, // slot 40
+                0x01, 0x01, 0x20, // 32 of item 1
+                0x00, 0x00, // no components added or removed
+                0x01, 0x01, 0x20, // carried_item: HashedSlot, 32 of item 1
+                0x00, 0x00, // no components added or removed
+            ],
+        );
+        // Clicking outside the window uses slot -999.
+        let (rest, outside) =
+            ClickContainer::parse(&[0x01, 0x05, 0xfc, 0x19, 0x00, 0x00, 0x00, 0x00]).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(outside.slot, -999);
+        assert!(outside.changed_slots.is_empty());
+    }
+    #[test]
+    fn play_serverbound_set_creative_mode_slot_works() {
+        let packet = SetCreativeModeSlot {
+            slot: 36,
+            clicked_item: ItemStack {
+                item_id: VarInt::from(42),
+                count: VarInt::from(16),
+                components_to_add: vec![
+                    ItemComponent::Damage(VarInt::from(3)),
+                    ItemComponent::Unbreakable,
+                ],
+                components_to_remove: vec![VarInt::from(9)],
+            }
+            .into(),
+        };
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Serverbound,
+            packet.into(),
+            &[
+                0x0b, // Packet length
+                0x37, // Packet ID
+                0x00, 0x24, // slot: i16
+                0x10, // clicked_item: Slot count
+                0x2a, // item_id: VarInt
+                0x02, 0x01, // number of components added and removed
+                0x03, 0x03, // minecraft:damage
+                0x04, // minecraft:unbreakable
+                0x09, // removed minecraft:rarity
+            ],
+        );
+
+        // Components with data, such as a name from an anvil and an enchantment.
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Serverbound,
+            SetCreativeModeSlot {
+                slot: 36,
+                clicked_item: ItemStack {
+                    item_id: VarInt::from(1),
+                    count: VarInt::from(1),
+                    components_to_add: vec![
+                        ItemComponent::CustomName("Rock".into()),
+                        ItemComponent::Enchantments(vec![(VarInt::from(0), VarInt::from(5))]),
+                    ],
+                    components_to_remove: vec![],
+                }
+                .into(),
+            }
+            .into(),
+            &[
+                0x13, // Packet length
+                0x37, // Packet ID
+                0x00, 0x24, // slot: i16
+                0x01, // clicked_item: Slot count
+                0x01, // item_id: VarInt
+                0x02, 0x00, // number of components added and removed
+                0x05, 0x08, 0x00, 0x04, b'R', b'o', b'c', b'k', // minecraft:custom_name
+                0x0a, 0x01, 0x00, 0x05, // minecraft:enchantments
+            ],
+        );
+
+        // Deleting an item sends an empty slot, which is only its count.
+        let (rest, cleared) = SetCreativeModeSlot::parse(&[0x00, 0x24, 0x00]).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(cleared.slot, 36);
+        assert!(cleared.clicked_item.is_empty());
+    }
+    #[test]
+    fn play_serverbound_optional_fields_work() {
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Serverbound,
+            PlayCookieResponse {
+                key: "ex:id".into(),
+                payload: PrefixedOptional(Some(vec![0xca, 0xfe])),
+            }
+            .into(),
+            &[
+                0x0b, // Packet length
+                0x14, // Packet ID
+                0x05, b'e', b'x', b':', b'i', b'd', // key: String
+                0x01, 0x02, 0xca, 0xfe, // payload: PrefixedOptional<Vec<u8>>
+            ],
+        );
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Serverbound,
+            EditBook {
+                slot: VarInt::from(0),
+                entries: vec!["Hello".into(), "".into()],
+                title: PrefixedOptional(Some("Diary".into())),
+            }
+            .into(),
+            &[
+                0x11, // Packet length
+                0x17, // Packet ID
+                0x00, // slot: VarInt
+                0x02, // entries: Vec<String>
+                0x05, b'H', b'e', b'l', b'l', b'o', // page 1
+                0x00, // page 2
+                0x01, 0x05, b'D', b'i', b'a', b'r', b'y', // title: PrefixedOptional<String>
+            ],
+        );
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Serverbound,
+            SetBeaconEffect {
+                primary_effect: PrefixedOptional(Some(VarInt::from(1))),
+                secondary_effect: PrefixedOptional(None),
+            }
+            .into(),
+            &[
+                0x04, // Packet length
+                0x33, // Packet ID
+                0x01, 0x01, // primary_effect: PrefixedOptional<VarInt>
+                0x00, // secondary_effect: PrefixedOptional<VarInt>
+            ],
+        );
+    }
+    #[test]
+    fn play_serverbound_test_instance_block_action_works() {
+        let packet = TestInstanceBlockAction {
+            location: Position::new(1, 2, 3),
+            action: TestInstanceAction::Run,
+            data: TestInstanceData {
+                test: PrefixedOptional(Some("a:b".parse().unwrap())),
+                size: (VarInt::from(5), VarInt::from(4), VarInt::from(3)),
+                rotation: VarInt::from(1),
+                ignore_entities: true,
+                status: TestInstanceStatus::Finished,
+                error_message: PrefixedOptional(None),
+            },
+        };
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Serverbound,
+            packet.clone().into(),
+            &[
+                0x16, // Packet length
+                0x3e, // Packet ID
+                0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x30, 0x02, // location: Position
+                0x06, // action: TestInstanceAction
+                0x01, 0x03, b'a', b':', b'b', // test: PrefixedOptional<Identifier>
+                0x05, 0x04, 0x03, // size: (VarInt, VarInt, VarInt)
+                0x01, // rotation: VarInt
+                0x01, // ignore_entities: bool
+                0x02, // status: TestInstanceStatus
+                0x00, // error_message: PrefixedOptional<TextComponent>
+            ],
+        );
+
+        let failed = TestInstanceBlockAction {
+            data: TestInstanceData {
+                error_message: PrefixedOptional(Some(TextComponent::text("Timed out"))),
+                ..packet.data.clone()
+            },
+            ..packet.clone()
+        };
+        let bytes = failed.serialize();
+        let (rest, parsed) = TestInstanceBlockAction::parse(&bytes).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(
+            parsed.data.error_message.as_ref(),
+            Some(&TextComponent::text("Timed out"))
+        );
+
+        // There's no action after `Run`.
+        let mut bytes = packet.serialize();
+        bytes[8] = 0x07;
+        assert!(TestInstanceBlockAction::parse(&bytes).is_err());
+    }
+    #[test]
+    fn play_serverbound_use_item_on_works() {
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Serverbound,
+            UseItemOn {
+                hand: VarInt::from(0),
+                location: Position::new(0, 64, 0),
+                face: VarInt::from(1),
+                cursor_position_x: 0.5,
+                cursor_position_y: 1.0,
+                cursor_position_z: 0.5,
+                inside_block: false,
+                world_border_hit: false,
+                sequence: VarInt::from(7),
+            }
+            .into(),
+            &[
+                0x1a, // Packet length
+                0x3f, // Packet ID
+                0x00, // hand: VarInt
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, // location: Position
+                0x01, // face: VarInt
+                0x3f, 0x00, 0x00, 0x00, // cursor_position_x: f32
+                0x3f, 0x80, 0x00, 0x00, // cursor_position_y: f32
+                0x3f, 0x00, 0x00, 0x00, // cursor_position_z: f32
+                0x00, // inside_block: bool
+                0x00, // world_border_hit: bool
+                0x07, // sequence: VarInt
+            ],
+        );
+        // Positions pack negative coordinates as two's complement.
+        let mut bytes = vec![0x00];
+        bytes.extend(Position::new(-1, -64, -1).serialize());
+        bytes.extend(UseItemOn::default().serialize()[9..].iter());
+        let (rest, parsed) = UseItemOn::parse(&bytes).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(parsed.location, Position::new(-1, -64, -1));
+    }
+    #[test]
+    fn play_acknowledge_configuration_changes_state() {
+        let packet: Packet = AcknowledgeConfiguration {}.into();
+        assert_eq!(packet.state_change(), Some(ClientState::Configuration));
+    }
+    #[test]
+    fn play_clientbound_login_works() {
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Clientbound,
+            LoginPlay {
+                entity_id: 1,
+                is_hardcore: false,
+                dimension_names: vec!["minecraft:overworld".to_owned()],
+                max_players: VarInt::from(20),
+                view_distance: VarInt::from(10),
+                simulation_distance: VarInt::from(10),
+                reduced_debug_info: false,
+                enable_respawn_screen: true,
+                do_limited_crafting: false,
+                dimension_type: VarInt::from(0),
+                dimension_name: "minecraft:overworld".to_owned(),
+                hashed_seed: 0,
+                game_mode: 1,
+                previous_game_mode: -1,
+                is_debug: false,
+                is_flat: false,
+                death_location: PrefixedOptional(None),
+                portal_cooldown: VarInt::from(0),
+                sea_level: VarInt::from(63),
+                enforces_secure_chat: false,
+            }
+            .into(),
+            &[
+                0x46, // Packet length
+                0x2b, // Packet ID
+                0x00, 0x00, 0x00, 0x01, // entity_id: i32
+                0x00, // is_hardcore: bool
+                0x01, // dimension_names: Vec<String>
+                0x13, // length of "minecraft:overworld"
+                b'm', b'i', b'n', b'e', b'c', b'r', b'a', b'f', b't', b':', // minecraft:
+                b'o', b'v', b'e', b'r', b'w', b'o', b'r', b'l', b'd', // overworld
+                0x14, // max_players: VarInt
+                0x0a, // view_distance: VarInt
+                0x0a, // simulation_distance: VarInt
+                0x00, // reduced_debug_info: bool
+                0x01, // enable_respawn_screen: bool
+                0x00, // do_limited_crafting: bool
+                0x00, // dimension_type: VarInt
+                0x13, // dimension_name: String
+                b'm', b'i', b'n', b'e', b'c', b'r', b'a', b'f', b't', b':', // minecraft:
+                b'o', b'v', b'e', b'r', b'w', b'o', b'r', b'l', b'd', // overworld
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // hashed_seed: i64
+                0x01, // game_mode: u8
+                0xff, // previous_game_mode: i8
+                0x00, // is_debug: bool
+                0x00, // is_flat: bool
+                0x00, // death_location: PrefixedOptional<(String, Position)>
+                0x00, // portal_cooldown: VarInt
+                0x3f, // sea_level: VarInt
+                0x00, // enforces_secure_chat: bool
+            ],
+        );
+        // A player who has died is told where.
+        let died = LoginPlay {
+            death_location: PrefixedOptional(Some((
+                "minecraft:the_nether".to_owned(),
+                Position::new(-8, 70, 3),
+            ))),
+            ..Default::default()
+        };
+        let bytes = died.serialize();
+        let (rest, parsed) = LoginPlay::parse(&bytes).unwrap();
+        assert!(rest.is_empty());
+        let (dimension, location) = parsed.death_location.0.unwrap();
+        assert_eq!(dimension, "minecraft:the_nether");
+        assert_eq!(location, Position::new(-8, 70, 3));
+    }
+    #[test]
+    fn play_clientbound_set_entity_metadata_works() {
+        let packet = SetEntityMetadata {
+            entity_id: VarInt::from(5),
+            metadata: EntityMetadata(vec![
+                EntityMetadataEntry {
+                    index: 0,
+                    kind: EntityMetadataEntryKind::Byte(0x02),
+                },
+                EntityMetadataEntry {
+                    index: 2,
+                    kind: EntityMetadataEntryKind::OptionalChat(PrefixedOptional(None)),
+                },
+                EntityMetadataEntry {
+                    index: 6,
+                    kind: EntityMetadataEntryKind::Pose(EntityPose::Sneaking),
+                },
+                EntityMetadataEntry {
+                    index: 17,
+                    kind: EntityMetadataEntryKind::OptionalVarInt(OptionalVarInt(Some(
+                        VarInt::from(0),
+                    ))),
+                },
+            ]),
+        };
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Clientbound,
+            packet.into(),
+            &[
+                0x0f, // Packet length
+                0x5c, // Packet ID
+                0x05, // entity_id: VarInt
+                0x00, 0x00, 0x02, // index 0: Byte
+                0x02, 0x06, 0x00, // index 2: OptionalChat, absent
+                0x06, 0x15, 0x05, // index 6: Pose
+                0x11, 0x14, 0x01, // index 17: OptionalVarInt, one more than the value
+                0xff, // end of metadata
+            ],
+        );
+
+        // An update with no changes is only the end marker.
+        let (rest, empty) = SetEntityMetadata::parse(&[0x05, 0xff]).unwrap();
+        assert!(rest.is_empty());
+        assert!(empty.metadata.0.is_empty());
+        // Missing the end marker.
+        assert!(SetEntityMetadata::parse(&[0x05, 0x00, 0x00, 0x02]).is_err());
+        // There's no type after the quaternion.
+        assert!(SetEntityMetadata::parse(&[0x05, 0x00, 0x23, 0x00, 0xff]).is_err());
+    }
+    #[test]
+    fn play_clientbound_entity_metadata_values_work() {
+        let metadata = EntityMetadata(vec![
+            EntityMetadataEntry {
+                index: 8,
+                kind: EntityMetadataEntryKind::Slot(
+                    ItemStack::new(VarInt::from(1), VarInt::from(2)).into(),
+                ),
+            },
+            EntityMetadataEntry {
+                index: 9,
+                kind: EntityMetadataEntryKind::Particles(vec![
+                    particle::Particle::Dust {
+                        color: 0xff0000,
+                        scale: 1.0,
+                    },
+                    particle::Particle::Flame,
+                ]),
+            },
+            EntityMetadataEntry {
+                index: 10,
+                kind: EntityMetadataEntryKind::OptionalBlockId(None),
+            },
+            EntityMetadataEntry {
+                index: 11,
+                kind: EntityMetadataEntryKind::Quaternion {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                    w: 1.0,
+                },
+            },
+        ]);
+        let bytes = metadata.serialize();
+        assert_eq!(bytes[..6], [0x08, 0x07, 0x02, 0x01, 0x00, 0x00]);
+        // No block state is air.
+        assert!(bytes.ends_with(
+            &[0x0a, 0x0f, 0x00, 0x0b, 0x22]
+                .iter()
+                .chain(&[0; 12])
+                .chain(&[0x3f, 0x80, 0x00, 0x00, 0xff])
+                .copied()
+                .collect::<Vec<u8>>()
+        ));
+        assert_eq!(EntityMetadata::parse(&bytes).unwrap(), (&[][..], metadata));
+    }
+    #[test]
+    fn play_clientbound_set_container_content_works() {
+        let packet = SetContainerContent {
+            window_id: VarInt::from(1),
+            state_id: VarInt::from(3),
+            slot_data: vec![
+                Slot::empty(),
+                ItemStack {
+                    item_id: VarInt::from(1),
+                    count: VarInt::from(64),
+                    components_to_add: vec![ItemComponent::MaxStackSize(VarInt::from(16))],
+                    components_to_remove: vec![],
+                }
+                .into(),
+            ],
+            carried_item: Slot::empty(),
+        };
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Clientbound,
+            packet.into(),
+            &[
+                0x0c, // Packet length
+                0x12, // Packet ID
+                0x01, // window_id: VarInt
+                0x03, // state_id: VarInt
+                0x02, // slot_data: Vec<Slot>
+                0x00, // empty slot
+                0x40, 0x01, 0x01, 0x00, // 64 of item 1, with 1 component added
+                0x01, 0x10, // minecraft:max_stack_size
+                0x00, // carried_item: Slot
+            ],
+        );
+    }
+    #[test]
+    fn play_clientbound_set_container_slot_works() {
+        let packet = SetContainerSlot {
+            window_id: VarInt::from(0),
+            state_id: VarInt::from(1),
+            slot: 36,
+            slot_data: ItemStack::new(VarInt::from(2), VarInt::from(1)).into(),
+        };
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Clientbound,
+            packet.into(),
+            &[
+                0x09, // Packet length
+                0x14, // Packet ID
+                0x00, // window_id: VarInt
+                0x01, // state_id: VarInt
+                0x00, 0x24, // slot: i16
+                0x01, 0x02, 0x00, 0x00, // slot_data: Slot
+            ],
+        );
+    }
+    #[test]
+    fn play_clientbound_set_equipment_works() {
+        let packet = SetEquipment {
+            entity_id: VarInt::from(7),
+            equipment: Equipment(vec![
+                (
+                    EquipmentSlot::MainHand,
+                    ItemStack::new(VarInt::from(5), VarInt::from(1)).into(),
+                ),
+                (EquipmentSlot::Head, Slot::empty()),
+            ]),
+        };
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Clientbound,
+            packet.into(),
+            &[
+                0x09, // Packet length
+                0x5f, // Packet ID
+                0x07, // entity_id: VarInt
+                0x80, // main hand, with more equipment after it
+                0x01, 0x05, 0x00, 0x00, // Slot
+                0x05, // head
+                0x00, // Slot
+            ],
+        );
+        // There is no tenth equipment slot.
+        assert!(SetEquipment::parse(&[0x07, 0x09, 0x00]).is_err());
+    }
+    #[test]
+    fn play_clientbound_commands_works() {
+        let packet = Commands {
+            nodes: vec![
+                CommandNode {
+                    children: vec![VarInt::from(1)],
+                    ..Default::default()
+                },
+                CommandNode {
+                    kind: CommandNodeKind::Literal {
+                        name: "tp".to_owned(),
+                    },
+                    children: vec![VarInt::from(2)],
+                    ..Default::default()
+                },
+                CommandNode {
+                    kind: CommandNodeKind::Argument {
+                        name: "y".to_owned(),
+                        parser: ArgumentParser::Integer {
+                            range: NumberRange {
+                                min: Some(-64),
+                                max: None,
+                            },
+                        },
+                        suggestions: None,
+                    },
+                    executable: true,
+                    ..Default::default()
+                },
+            ],
+            root_index: VarInt::from(0),
+        };
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Clientbound,
+            packet.into(),
+            &[
+                0x16, // Packet length
+                0x10, // Packet ID
+                0x03, // nodes: Vec<CommandNode>
+                0x00, 0x01, 0x01, // root, with child 1
+                0x01, 0x01, 0x02, 0x02, b't', b'p', // literal "tp", with child 2
+                0x06, 0x00, 0x01, b'y', // executable argument "y", with no children
+                0x03, 0x01, 0xff, 0xff, 0xff, 0xc0, // integer parser, with a minimum of -64
+                0x00, // root_index: VarInt
+            ],
+        );
+        // Redirects and suggestions are only present when flagged.
+        let node = CommandNode {
+            kind: CommandNodeKind::Argument {
+                name: "x".to_owned(),
+                parser: ArgumentParser::Bool,
+                suggestions: Some("minecraft:ask_server".parse().unwrap()),
+            },
+            redirect: Some(VarInt::from(0)),
+            ..Default::default()
+        };
+        let bytes = node.serialize();
+        assert_eq!(bytes[..4], [0x1a, 0x00, 0x00, 0x01]);
+        assert_eq!(CommandNode::parse(&bytes).unwrap(), (&[][..], node));
+    }
+    #[test]
+    fn play_clientbound_stop_sound_works() {
+        let packet = StopSound {
+            filter: StopSoundFilter {
+                category: None,
+                sound: Some("a:b".parse().unwrap()),
+            },
+        };
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Clientbound,
+            packet.into(),
+            &[
+                0x06, // Packet length
+                0x70, // Packet ID
+                0x02, // only a sound
+                0x03, b'a', b':', b'b', // sound: Identifier
+            ],
+        );
+        let (rest, all) = StopSound::parse(&[0x00]).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(all.filter, StopSoundFilter::default());
+        let (_, category) = StopSound::parse(&[0x01, 0x02]).unwrap();
+        assert_eq!(category.filter.category, Some(SoundCategory::Record));
+    }
+    #[test]
+    fn play_clientbound_player_chat_message_works() {
+        let packet = PlayerChatMessage {
+            sender: Uuid::from_u128(1),
+            message: "hi".into(),
+            previous_messages: vec![IdOr::Id(VarInt::from(3)), IdOr::Inline([7; 256])],
+            filter: ChatFilter::PartiallyFiltered(BitSet(vec![0b10])),
+            chat_type: IdOr::Id(VarInt::from(0)),
+            sender_name: TextComponent::text("Notch"),
+            ..Default::default()
+        };
+        let bytes = packet.serialize();
+        let (rest, parsed) = PlayerChatMessage::parse(&bytes).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(parsed.previous_messages[1], IdOr::Inline([7; 256]));
+        let ChatFilter::PartiallyFiltered(mask) = &parsed.filter else {
+            panic!("expected a partial filter, got {:?}", parsed.filter);
+        };
+        assert!(mask.get(1) && !mask.get(0));
+        assert_eq!(parsed, packet);
+    }
+    #[test]
+    fn play_clientbound_update_light_works() {
+        assert_round_trip(
+            ClientState::Play,
+            PacketDirection::Clientbound,
+            UpdateLight {
+                chunk_x: VarInt::from(-1),
+                chunk_z: VarInt::from(2),
+                light: LightData {
+                    sky_light_mask: BitSet(vec![1]),
+                    block_light_mask: BitSet::default(),
+                    empty_sky_light_mask: BitSet::default(),
+                    empty_block_light_mask: BitSet(vec![6]),
+                    sky_light_arrays: vec![vec![0xff, 0xff]],
+                    block_light_arrays: vec![],
+                },
+            }
+            .into(),
+            &[
+                0x20, // Packet length
+                0x2a, // Packet ID
+                0xff, 0xff, 0xff, 0xff, 0x0f, // chunk_x: VarInt
+                0x02, // chunk_z: VarInt
+                0x01, // sky_light_mask: BitSet
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, // section 0
+                0x00, // block_light_mask: BitSet
+                0x00, // empty_sky_light_mask: BitSet
+                0x01, // empty_block_light_mask: BitSet
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x06, // sections 1 and 2
+                0x01, // sky_light_arrays: Vec<Vec<u8>>
+                0x02, 0xff, 0xff, // the array for the lowest section
+                0x00, // block_light_arrays: Vec<Vec<u8>>
+            ],
+        );
+        let (rest, parsed) = UpdateLight::parse(&[
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x06,
+            0x00, 0x00,
+        ])
+        .unwrap();
+        assert!(rest.is_empty());
+        // Masks are bit sets over the sections, starting below the world.
+        let mask = &parsed.light.empty_block_light_mask;
+        assert!(!mask.get(0) && mask.get(1) && mask.get(2) && !mask.get(3));
+    }
+    #[test]
+    fn play_start_configuration_changes_state() {
+        let packet: Packet = StartConfiguration {}.into();
+        assert_eq!(packet.state_change(), Some(ClientState::Configuration));
+    }
+    #[test]
+    fn versioned_packet_ids_work() {
+        use super::play::serverbound::PlayServerboundKeepAlive;
+
+        let old = ProtocolVersion::V1_21_4;
+        let (state, serverbound) = (ClientState::Play, PacketDirection::Serverbound);
+        // Packets before the first one added keep their ids.
+        assert_eq!(Packet::wire_id(old, state, serverbound, 0x03), Some(0x03));
+        // ChangeGameMode was added in 1.21.5, shifting the packets after it.
+        assert_eq!(Packet::wire_id(old, state, serverbound, 0x04), None);
+        assert_eq!(Packet::wire_id(old, state, serverbound, 0x08), Some(0x07));
+        assert_eq!(Packet::wire_id(old, state, serverbound, 0x1B), Some(0x1A));
+        // So were SetTestBlock and TestInstanceBlockAction.
+        assert_eq!(Packet::wire_id(old, state, serverbound, 0x3A), None);
+        assert_eq!(Packet::wire_id(old, state, serverbound, 0x3E), None);
+        assert_eq!(Packet::wire_id(old, state, serverbound, 0x40), Some(0x3D));
+        assert_eq!(Packet::latest_id(old, state, serverbound, 0x3D), Some(0x40));
+        assert_eq!(Packet::latest_id(old, state, serverbound, 0x3E), None);
+        for id in 0x00..=0x40 {
+            assert_eq!(
+                Packet::wire_id(ProtocolVersion::LATEST, state, serverbound, id),
+                Some(id)
+            );
+        }
+
+        let packet: Packet = PlayServerboundKeepAlive { payload: 7 }.into();
+        let (id, body) = packet.serialize_versioned(old).unwrap();
+        assert_eq!(*id, 0x1A);
+        let mut frame = id.serialize();
+        frame.extend(body);
+        assert_eq!(
+            Packet::parse_frame_versioned(old, state, serverbound, &frame)
+                .unwrap()
+                .1,
+            packet
+        );
+
+        let status: Packet = TestInstanceBlockStatus::default().into();
+        assert_eq!(status.serialize_versioned(old), None);
+
+        // SpawnExperienceOrb was removed in 1.21.5, so it only has a wire id before then.
+        let clientbound = PacketDirection::Clientbound;
+        let orb = <SpawnExperienceOrb as PacketKind>::ID;
+        assert!(orb > 0xFF);
+        assert_eq!(Packet::wire_id(old, state, clientbound, orb), Some(0x02));
+        assert_eq!(
+            Packet::wire_id(ProtocolVersion::LATEST, state, clientbound, orb),
+            None
+        );
+        assert_eq!(Packet::latest_id(old, state, clientbound, 0x02), Some(orb));
+        assert_eq!(
+            Packet::latest_id(ProtocolVersion::LATEST, state, clientbound, 0x02),
+            Some(0x02)
+        );
+        // The packets after it are shifted up by one, until TestInstanceBlockStatus was added.
+        assert_eq!(Packet::wire_id(old, state, clientbound, 0x01), Some(0x01));
+        assert_eq!(Packet::wire_id(old, state, clientbound, 0x02), Some(0x03));
+        assert_eq!(Packet::wire_id(old, state, clientbound, 0x26), Some(0x27));
+        assert_eq!(Packet::wire_id(old, state, clientbound, 0x2B), Some(0x2C));
+        assert_eq!(Packet::wire_id(old, state, clientbound, 0x77), None);
+        assert_eq!(Packet::wire_id(old, state, clientbound, 0x78), Some(0x78));
+
+        let packet: Packet = SpawnExperienceOrb {
+            entity_id: 1.into(),
+            position: EntityPosition {
+                x: 0.0,
+                y: 64.0,
+                z: 0.0,
+            },
+            count: 5,
+        }
+        .into();
+        let (id, body) = packet.serialize_versioned(old).unwrap();
+        assert_eq!(*id, 0x02);
+        let mut frame = id.serialize();
+        frame.extend(body);
+        assert_eq!(
+            Packet::parse_frame_versioned(old, state, clientbound, &frame)
+                .unwrap()
+                .1,
+            packet
+        );
+        assert_eq!(packet.serialize_versioned(ProtocolVersion::LATEST), None);
+    }
+    #[test]
+    fn changed_field_layouts_work() {
+        /// Check that `bytes` (including the length prefix) parses to `packet` and back in 1.21.4.
+        fn assert_old_round_trip(
+            client_state: ClientState,
+            direction: PacketDirection,
+            packet: Packet,
+            bytes: &[u8],
+        ) {
+            let old = ProtocolVersion::V1_21_4;
+            let (rest, parsed) =
+                Packet::parse_versioned(old, client_state, direction, bytes).unwrap();
+            assert!(rest.is_empty());
+            assert_eq!(parsed, packet);
+
+            let mut frame = vec![];
+            packet
+                .serialize_frame_versioned_into(old, &mut frame)
+                .unwrap();
+            assert_eq!(packet.frame_len_versioned(old), Some(frame.len()));
+            assert_eq!(frame, bytes[1..]);
+        }
+
+        // 1.21.4 clients send whole item stacks rather than hashes.
+        assert_old_round_trip(
+            ClientState::Play,
+            PacketDirection::Serverbound,
+            ClickContainer {
+                window_id: VarInt::from(1),
+                state_id: VarInt::from(5),
+                slot: 36,
+                button: 0,
+                mode: VarInt::from(0),
+                changed_slot_items: vec![(36, ItemStack::new(1.into(), 64.into()).into())],
+                carried_slot_item: Slot::empty(),
+                ..Default::default()
+            }
+            .into(),
+            &[
+                0x0f, // Packet length
+                0x10, // Packet ID
+                0x01, // window_id: VarInt
+                0x05, // state_id: VarInt
+                0x00, 0x24, // slot: i16
+                0x00, // button: i8
+                0x00, // mode: VarInt
+                0x01, // changed_slot_items: Vec<(i16, Slot)>
+                0x00, 0x24, // slot 36
+                0x40, 0x01, // 64 of item 1
+                0x00, 0x00, // no components added or removed
+                0x00, // carried_slot_item: Slot
+            ],
+        );
+        // Heightmaps were sent as NBT until 1.21.5.
+        assert_old_round_trip(
+            ClientState::Play,
+            PacketDirection::Clientbound,
+            ChunkDataAndUpdateLight {
+                heightmap_tag: Tag::Compound(
+                    [("MOTION_BLOCKING".to_owned(), Tag::LongArray(vec![1]))]
+                        .into_iter()
+                        .collect(),
+                ),
+                ..Default::default()
+            }
+            .into(),
+            &[
+                0x31, // Packet length
+                0x28, // Packet ID
+                0x00, 0x00, 0x00, 0x00, // chunk_x: i32
+                0x00, 0x00, 0x00, 0x00, // chunk_z: i32
+                0x0a, // heightmap_tag: Tag, a nameless compound
+                0x0c, 0x00, 0x0f, b'M', b'O', b'T', b'I', b'O', b'N', b'_', b'B', b'L', b'O', b'C',
+                b'K', b'I', b'N', b'G', // Long array named MOTION_BLOCKING
+                0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, // [1]
+                0x00, // End of compound
+                0x00, // data: Vec<u8>
+                0x00, // block_entities: Vec<ChunkBlockEntity>
+                0x00, 0x00, 0x00, 0x00, // light masks: BitSet
+                0x00, 0x00, // light arrays: Vec<Vec<u8>>
+            ],
+        );
+    }
+    #[test]
+    fn raw_packets_work() {
+        use super::RawPacket;
+
+        let message = ChatMessage {
+            message: "hi".into(),
+            checksum: 1,
+            ..Default::default()
+        };
+        let mut raw =
+            RawPacket::from_packet(ProtocolVersion::LATEST, message.clone().into()).unwrap();
+        assert!(raw.is::<ChatMessage>());
+        assert!(!raw.is::<ChatCommand>());
+        assert_eq!(raw.body[..], message.serialize()[..]);
+
+        let unparsed = RawPacket::new(
+            raw.version,
+            raw.client_state,
+            raw.direction,
+            raw.id,
+            raw.body.clone(),
+        );
+        // Converting to another version needs the parsed packet.
+        let old = ProtocolVersion::V1_21_4;
+        assert_eq!(unparsed.frame_len_versioned(old), None);
+        assert_eq!(
+            raw.frame_len_versioned(old),
+            Packet::from(message.clone()).frame_len_versioned(old)
+        );
+        assert_eq!(raw.parse().unwrap(), &Packet::from(message.clone()));
+        assert_eq!(unparsed.into_packet().unwrap(), Packet::from(message));
+    }
+    #[test]
+    fn versioned_fields_work() {
+        let message = ChatMessage {
+            message: "hi".into(),
+            checksum: 1,
+            ..Default::default()
+        };
+        let latest = message.serialize();
+        let old = message.serialize_versioned(ProtocolVersion::V1_21_4);
+        // 1.21.4 has no checksum.
+        assert_eq!(old.len(), latest.len() - 1);
+        assert_eq!(old[..], latest[..old.len()]);
+        assert_eq!(
+            ChatMessage::parse_versioned(ProtocolVersion::V1_21_4, &old)
+                .unwrap()
+                .1,
+            ChatMessage {
+                checksum: 0,
+                ..message
+            }
+        );
+    }
+    #[test]
+    fn encoded_len_matches_serialize() {
+        /// Check that `value` knows its length without being serialized, and round trips.
+        fn check<T: Parsable + std::fmt::Debug + PartialEq>(value: T) {
+            let bytes = value.serialize();
+            assert_eq!(value.encoded_len(), bytes.len());
+            assert_eq!(T::parse(&bytes).unwrap(), (&[][..], value));
+        }
+        let custom_data = Tag::Compound(
+            [
+                ("name".to_owned(), Tag::from("nul\0 and 🍌")),
+                ("list".to_owned(), Tag::List(vec![Tag::Int(1), Tag::Int(2)])),
+                ("longs".to_owned(), Tag::LongArray(vec![-1, 1 << 40])),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let name = TextComponent::text("Named").bold(true).append("!");
+        let stack = ItemStack {
+            components_to_add: vec![
+                ItemComponent::CustomData(custom_data.clone()),
+                ItemComponent::CustomName(name.clone()),
+            ],
+            components_to_remove: vec![VarInt::from(3)],
+            ..ItemStack::new(1.into(), 64.into())
+        };
+
+        check(ChunkDataAndUpdateLight {
+            chunk_x: 1,
+            chunk_z: -1,
+            heightmaps: vec![(VarInt::from(1), vec![0; 37])],
+            data: vec![0; 16],
+            block_entities: vec![ChunkBlockEntity {
+                packed_xz: 0x12,
+                y: -60,
+                block_entity_type: VarInt::from(7),
+                data: custom_data.clone(),
+            }],
+            light: LightData {
+                sky_light_mask: BitSet(vec![0b10]),
+                sky_light_arrays: vec![vec![0xff; 2048]],
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        check(SystemChatMessage {
+            content: name.clone(),
+            overlay: false,
+        });
+        check(SetEntityMetadata {
+            entity_id: VarInt::from(5),
+            metadata: EntityMetadata(vec![
+                EntityMetadataEntry {
+                    index: 8,
+                    kind: EntityMetadataEntryKind::Slot(stack.clone().into()),
+                },
+                EntityMetadataEntry {
+                    index: 9,
+                    kind: EntityMetadataEntryKind::Nbt(custom_data),
+                },
+                EntityMetadataEntry {
+                    index: 10,
+                    kind: EntityMetadataEntryKind::Particles(vec![
+                        particle::Particle::Item {
+                            item: stack.clone().into(),
+                        },
+                        particle::Particle::Dust {
+                            color: 0xff0000,
+                            scale: 1.0,
+                        },
+                    ]),
+                },
+            ]),
+        });
+        check(SetContainerContent {
+            window_id: VarInt::from(1),
+            state_id: VarInt::from(2),
+            slot_data: vec![Slot::empty(), stack.into()],
+            carried_item: Slot::empty(),
+        });
     }
 }
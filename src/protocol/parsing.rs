@@ -1,3 +1,4 @@
+pub use bytes::BufMut;
 pub use nom::IResult;
 use nom::{
     bytes::streaming::{take, take_while_m_n},
@@ -55,6 +56,373 @@ impl std::fmt::Display for VarInt {
     }
 }
 
+/// Implementation of the protocol's VarLong type.
+///
+/// Like `VarInt`, but wraps an i64 and takes up to 10 bytes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
+pub struct VarLong(i64);
+impl std::ops::Deref for VarLong {
+    type Target = i64;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl std::ops::DerefMut for VarLong {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+impl From<i64> for VarLong {
+    fn from(value: i64) -> Self {
+        VarLong(value)
+    }
+}
+impl From<VarLong> for i64 {
+    fn from(value: VarLong) -> Self {
+        *value
+    }
+}
+impl std::fmt::Display for VarLong {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// An optional value, represented in the protocol as
+/// a boolean optionally followed by `T` if the boolean was true.
+///
+/// See `Parsable::parse_optional`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrefixedOptional<T>(pub Option<T>);
+impl<T> Default for PrefixedOptional<T> {
+    fn default() -> Self {
+        PrefixedOptional(None)
+    }
+}
+impl<T> std::ops::Deref for PrefixedOptional<T> {
+    type Target = Option<T>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl<T> std::ops::DerefMut for PrefixedOptional<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+impl<T> From<Option<T>> for PrefixedOptional<T> {
+    fn from(value: Option<T>) -> Self {
+        PrefixedOptional(value)
+    }
+}
+impl<T> From<PrefixedOptional<T>> for Option<T> {
+    fn from(value: PrefixedOptional<T>) -> Self {
+        value.0
+    }
+}
+
+/// An array of `T`, represented in the protocol as a `VarInt` length followed by the items.
+///
+/// This is how `Vec<T>` is parsed, so the alias only documents intent.
+pub type PrefixedArray<T> = Vec<T>;
+
+/// A bit set of any length, represented in the protocol as a `VarInt`-prefixed array of longs.
+///
+/// Bit `i` is bit `i % 64` of long `i / 64`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BitSet(pub Vec<i64>);
+impl BitSet {
+    pub fn get(&self, index: usize) -> bool {
+        self.0
+            .get(index / 64)
+            .is_some_and(|long| long & (1 << (index % 64)) != 0)
+    }
+    /// Set a bit, growing the set if needed.
+    pub fn set(&mut self, index: usize, value: bool) {
+        if index / 64 >= self.0.len() {
+            if !value {
+                return;
+            }
+            self.0.resize(index / 64 + 1, 0);
+        }
+        if value {
+            self.0[index / 64] |= 1 << (index % 64);
+        } else {
+            self.0[index / 64] &= !(1 << (index % 64));
+        }
+    }
+}
+
+/// A bit set of known length, represented in the protocol as `BYTES` bytes with no prefix.
+///
+/// Bit `i` is bit `i % 8` of byte `i / 8`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FixedBitSet<const BYTES: usize>(pub [u8; BYTES]);
+impl<const BYTES: usize> Default for FixedBitSet<BYTES> {
+    fn default() -> Self {
+        FixedBitSet([0; BYTES])
+    }
+}
+impl<const BYTES: usize> FixedBitSet<BYTES> {
+    pub fn get(&self, index: usize) -> bool {
+        self.0
+            .get(index / 8)
+            .is_some_and(|byte| byte & (1 << (index % 8)) != 0)
+    }
+    /// Set a bit. Bits past the end of the set are ignored.
+    pub fn set(&mut self, index: usize, value: bool) {
+        let Some(byte) = self.0.get_mut(index / 8) else {
+            return;
+        };
+        if value {
+            *byte |= 1 << (index % 8);
+        } else {
+            *byte &= !(1 << (index % 8));
+        }
+    }
+}
+
+/// Either a registry id or an inline value, represented in the protocol
+/// as a `VarInt` that is 0 before an inline `T`, or one more than the id.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IdOr<T> {
+    Id(VarInt),
+    Inline(T),
+}
+impl<T> Default for IdOr<T> {
+    fn default() -> Self {
+        IdOr::Id(VarInt::default())
+    }
+}
+
+/// An optional `VarInt`, represented in the protocol as 0 for none
+/// or one more than the value.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Hash)]
+pub struct OptionalVarInt(pub Option<VarInt>);
+impl From<Option<VarInt>> for OptionalVarInt {
+    fn from(value: Option<VarInt>) -> Self {
+        OptionalVarInt(value)
+    }
+}
+
+/// A set of registry entries, given by a tag or by their ids.
+///
+/// Represented in the protocol as a `VarInt` that is 0 before a tag name,
+/// or one more than the number of ids that follow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdSet {
+    Tag(String),
+    Ids(Vec<VarInt>),
+}
+impl Default for IdSet {
+    fn default() -> Self {
+        IdSet::Ids(vec![])
+    }
+}
+
+/// A rotation in steps of 1/256 of a full turn, represented in the protocol as a single byte.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Hash)]
+pub struct Angle(pub u8);
+impl Angle {
+    /// Round an angle in degrees to the nearest step, wrapping it into a single turn.
+    pub fn from_degrees(degrees: f32) -> Self {
+        Angle((degrees / 360.0 * 256.0).round().rem_euclid(256.0) as u8)
+    }
+    pub fn to_degrees(self) -> f32 {
+        self.0 as f32 * 360.0 / 256.0
+    }
+}
+impl From<u8> for Angle {
+    fn from(value: u8) -> Self {
+        Angle(value)
+    }
+}
+impl From<Angle> for u8 {
+    fn from(value: Angle) -> Self {
+        value.0
+    }
+}
+
+/// A namespaced identifier such as `minecraft:stone`, represented in the protocol as a `String`.
+///
+/// The namespace may only contain `[a-z0-9_.-]`, and the path may also contain `/`.
+/// Parsing an identifier without a namespace gives it the `minecraft` namespace.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Identifier {
+    pub namespace: String,
+    pub path: String,
+}
+impl Identifier {
+    pub const DEFAULT_NAMESPACE: &'static str = "minecraft";
+
+    /// Create an identifier, checking that both parts are valid.
+    ///
+    /// On failure, returns the identifier as it would have been written.
+    pub fn new(namespace: impl Into<String>, path: impl Into<String>) -> Result<Self, String> {
+        let (namespace, path) = (namespace.into(), path.into());
+        let valid_namespace = namespace
+            .bytes()
+            .all(|b| matches!(b, b'a'..=b'z' | b'0'..=b'9' | b'_' | b'.' | b'-'));
+        let valid_path = path
+            .bytes()
+            .all(|b| matches!(b, b'a'..=b'z' | b'0'..=b'9' | b'_' | b'.' | b'-' | b'/'));
+        if valid_namespace && valid_path {
+            Ok(Identifier { namespace, path })
+        } else {
+            Err(format!("{namespace}:{path}"))
+        }
+    }
+    /// Create an identifier in the `minecraft` namespace.
+    pub fn minecraft(path: impl Into<String>) -> Result<Self, String> {
+        Identifier::new(Self::DEFAULT_NAMESPACE, path)
+    }
+}
+impl Default for Identifier {
+    fn default() -> Self {
+        Identifier {
+            namespace: Self::DEFAULT_NAMESPACE.to_string(),
+            path: String::new(),
+        }
+    }
+}
+impl std::str::FromStr for Identifier {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((namespace, path)) => Identifier::new(namespace, path),
+            None => Identifier::minecraft(s),
+        }
+    }
+}
+impl TryFrom<&str> for Identifier {
+    type Error = String;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+impl std::fmt::Display for Identifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.namespace, self.path)
+    }
+}
+
+/// A string of at most `MAX` UTF-16 code units, represented in the protocol as a `String`.
+///
+/// Parsing fails for longer strings, so packets can declare the vanilla limit of each field.
+/// Serializing doesn't check the length.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
+pub struct BoundedString<const MAX: usize>(pub String);
+impl<const MAX: usize> std::ops::Deref for BoundedString<MAX> {
+    type Target = String;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl<const MAX: usize> std::ops::DerefMut for BoundedString<MAX> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+impl<const MAX: usize> From<String> for BoundedString<MAX> {
+    fn from(value: String) -> Self {
+        BoundedString(value)
+    }
+}
+impl<const MAX: usize> From<&str> for BoundedString<MAX> {
+    fn from(value: &str) -> Self {
+        BoundedString(value.to_string())
+    }
+}
+impl<const MAX: usize> From<BoundedString<MAX>> for String {
+    fn from(value: BoundedString<MAX>) -> Self {
+        value.0
+    }
+}
+impl<const MAX: usize> std::fmt::Display for BoundedString<MAX> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// A low-precision vector, such as an entity's velocity.
+///
+/// Represented in the protocol as a single zero byte for a (near) zero vector. Otherwise,
+/// each component is scaled by the largest one rounded up and packed into 15 bits,
+/// alongside the scale, in 6 bytes. Scales above 3 are followed by a `VarInt` of the rest.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct LpVec3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+impl LpVec3 {
+    const ABS_MAX: f64 = 1.7179869183E10;
+    const ABS_MIN: f64 = 3.051944088384301E-5;
+    const MAX_QUANTIZED: f64 = 32766.0;
+    const CONTINUATION_FLAG: u64 = 0b100;
+
+    fn sanitize(value: f64) -> f64 {
+        if value.is_nan() {
+            0.0
+        } else {
+            value.clamp(-Self::ABS_MAX, Self::ABS_MAX)
+        }
+    }
+    fn pack(value: f64) -> u64 {
+        ((value * 0.5 + 0.5) * Self::MAX_QUANTIZED).round() as u64
+    }
+    fn unpack(bits: u64) -> f64 {
+        (bits & 0x7fff).min(Self::MAX_QUANTIZED as u64) as f64 * 2.0 / Self::MAX_QUANTIZED - 1.0
+    }
+    /// The sanitized components and the scale, or `None` for a zero vector.
+    fn scaled(&self) -> Option<([f64; 3], u64)> {
+        let components = [self.x, self.y, self.z].map(Self::sanitize);
+        let max = components.iter().fold(0.0f64, |max, c| max.max(c.abs()));
+        (max >= Self::ABS_MIN).then(|| (components, max.ceil() as u64))
+    }
+}
+
+/// Define an enum represented in the protocol as a `VarInt` of its discriminant.
+#[macro_export]
+macro_rules! varint_enum {
+    ($(#[$attr:meta])* $vis:vis enum $name:ident {
+        $($(#[$vattr:meta])* $variant:ident = $value:literal,)*
+    }) => {
+        $(#[$attr])*
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        $vis enum $name {
+            $($(#[$vattr])* $variant = $value,)*
+        }
+        impl TryFrom<i32> for $name {
+            type Error = i32;
+            fn try_from(value: i32) -> ::std::result::Result<Self, Self::Error> {
+                match value {
+                    $($value => ::std::result::Result::Ok($name::$variant),)*
+                    _ => ::std::result::Result::Err(value),
+                }
+            }
+        }
+        impl $crate::protocol::parsing::Parsable for $name {
+            #[tracing::instrument]
+            fn parse(data: &[u8]) -> $crate::protocol::parsing::IResult<&[u8], Self> {
+                use $crate::protocol::parsing::VarInt;
+                nom::combinator::map_res(
+                    <VarInt as $crate::protocol::parsing::Parsable>::parse,
+                    |value| $name::try_from(*value),
+                )(data)
+            }
+            fn serialize_into(&self, buf: &mut impl $crate::protocol::parsing::BufMut) {
+                use $crate::protocol::parsing::VarInt;
+                $crate::protocol::parsing::Parsable::serialize_into(&VarInt::from(*self as i32), buf)
+            }
+            fn encoded_len(&self) -> usize {
+                use $crate::protocol::parsing::VarInt;
+                $crate::protocol::parsing::Parsable::encoded_len(&VarInt::from(*self as i32))
+            }
+        }
+    };
+}
+
 /// A structure that can be serialized and deserialized.
 ///
 /// Similar to serde's `Serialize` and `Deserialize` traits.
@@ -64,7 +432,22 @@ pub trait Parsable {
     where
         Self: Sized;
     /// Serialize `self` into a vector of bytes.
-    fn serialize(&self) -> Vec<u8>;
+    fn serialize(&self) -> Vec<u8> {
+        let mut output = vec![];
+        self.serialize_into(&mut output);
+        output
+    }
+    /// Serialize `self` onto the end of `buf`.
+    ///
+    /// Implementors must override this or `serialize`, and should override
+    /// this and `encoded_len`, since the defaults serialize `self` into a new vector.
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        buf.put_slice(&self.serialize());
+    }
+    /// The number of bytes that `serialize` produces.
+    fn encoded_len(&self) -> usize {
+        self.serialize().len()
+    }
 
     /// Helper to optionally parse `Self`.
     ///
@@ -111,32 +494,219 @@ pub trait Parsable {
         Self::parse_repeated(*vec_len as usize, data)
     }
 }
+/// An optional value with no prefix, which is present if there is data left.
+///
+/// Only usable for the last field of a packet, use `PrefixedOptional` everywhere else.
 impl<T: Parsable + std::fmt::Debug> Parsable for Option<T> {
     #[tracing::instrument]
     fn parse(data: &[u8]) -> IResult<&[u8], Self> {
         nom::combinator::opt(T::parse)(data)
     }
-    #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        match self {
-            Some(t) => t.serialize(),
-            None => Vec::new(),
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        if let Some(t) = self {
+            t.serialize_into(buf);
         }
     }
+    fn encoded_len(&self) -> usize {
+        self.as_ref().map_or(0, Parsable::encoded_len)
+    }
 }
 impl<T: Parsable + std::fmt::Debug> Parsable for Vec<T> {
     #[tracing::instrument]
     fn parse(data: &[u8]) -> IResult<&[u8], Self> {
         T::parse_vec(data)
     }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        VarInt::from(self.len()).serialize_into(buf);
+        for item in self {
+            item.serialize_into(buf);
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        VarInt::from(self.len()).encoded_len()
+            + self.iter().map(Parsable::encoded_len).sum::<usize>()
+    }
+}
+
+impl<T: Parsable + std::fmt::Debug> Parsable for PrefixedOptional<T> {
     #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        let mut output = vec![];
-        output.extend(VarInt::from(self.len()).serialize());
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        nom::combinator::map(T::parse_optional, PrefixedOptional)(data)
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.0.is_some().serialize_into(buf);
+        self.0.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        1 + self.0.encoded_len()
+    }
+}
+impl Parsable for BitSet {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        nom::combinator::map(Vec::parse, BitSet)(data)
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.0.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len()
+    }
+}
+impl<const BYTES: usize> Parsable for FixedBitSet<BYTES> {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        nom::combinator::map(<[u8; BYTES]>::parse, FixedBitSet)(data)
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        buf.put_slice(&self.0);
+    }
+    fn encoded_len(&self) -> usize {
+        BYTES
+    }
+}
+impl<T: Parsable + std::fmt::Debug> Parsable for IdOr<T> {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, id) = VarInt::parse(data)?;
+        if *id == 0 {
+            nom::combinator::map(T::parse, IdOr::Inline)(data)
+        } else {
+            Ok((data, IdOr::Id(VarInt::from(*id - 1))))
+        }
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        match self {
+            IdOr::Id(id) => VarInt::from(**id + 1).serialize_into(buf),
+            IdOr::Inline(value) => {
+                VarInt::from(0).serialize_into(buf);
+                value.serialize_into(buf);
+            }
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        match self {
+            IdOr::Id(id) => VarInt::from(**id + 1).encoded_len(),
+            IdOr::Inline(value) => 1 + value.encoded_len(),
+        }
+    }
+}
+impl<T: Parsable + std::fmt::Debug> Parsable for Box<T> {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        nom::combinator::map(T::parse, Box::new)(data)
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        (**self).serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        (**self).encoded_len()
+    }
+}
+impl Parsable for OptionalVarInt {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, value) = VarInt::parse(data)?;
+        Ok((
+            data,
+            OptionalVarInt((*value != 0).then(|| VarInt::from(*value - 1))),
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        VarInt::from(self.0.map_or(0, |value| *value + 1)).serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        VarInt::from(self.0.map_or(0, |value| *value + 1)).encoded_len()
+    }
+}
+impl Parsable for IdSet {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, kind) = VarInt::parse_usize(data)?;
+        if kind == 0 {
+            nom::combinator::map(String::parse, IdSet::Tag)(data)
+        } else {
+            nom::combinator::map(|data| VarInt::parse_repeated(kind - 1, data), IdSet::Ids)(data)
+        }
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        match self {
+            IdSet::Tag(tag) => {
+                VarInt::from(0).serialize_into(buf);
+                tag.serialize_into(buf);
+            }
+            IdSet::Ids(ids) => {
+                VarInt::from(ids.len() + 1).serialize_into(buf);
+                for id in ids {
+                    id.serialize_into(buf);
+                }
+            }
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        match self {
+            IdSet::Tag(tag) => 1 + tag.encoded_len(),
+            IdSet::Ids(ids) => {
+                VarInt::from(ids.len() + 1).encoded_len()
+                    + ids.iter().map(Parsable::encoded_len).sum::<usize>()
+            }
+        }
+    }
+}
+impl<T: Parsable + std::fmt::Debug, const N: usize> Parsable for [T; N] {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, items) = T::parse_repeated(N, data)?;
+        Ok((
+            data,
+            items.try_into().expect("parsed the right number of items"),
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
         for item in self {
-            output.extend(item.serialize());
+            item.serialize_into(buf);
         }
-        output
+    }
+    fn encoded_len(&self) -> usize {
+        self.iter().map(Parsable::encoded_len).sum()
+    }
+}
+impl<A: Parsable + std::fmt::Debug, B: Parsable + std::fmt::Debug> Parsable for (A, B) {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, a) = A::parse(data)?;
+        let (data, b) = B::parse(data)?;
+        Ok((data, (a, b)))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.0.serialize_into(buf);
+        self.1.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len() + self.1.encoded_len()
+    }
+}
+
+impl<
+        A: Parsable + std::fmt::Debug,
+        B: Parsable + std::fmt::Debug,
+        C: Parsable + std::fmt::Debug,
+    > Parsable for (A, B, C)
+{
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, a) = A::parse(data)?;
+        let (data, b) = B::parse(data)?;
+        let (data, c) = C::parse(data)?;
+        Ok((data, (a, b, c)))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.0.serialize_into(buf);
+        self.1.serialize_into(buf);
+        self.2.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len() + self.1.encoded_len() + self.2.encoded_len()
     }
 }
 
@@ -145,9 +715,11 @@ impl Parsable for uuid::Uuid {
     fn parse(data: &[u8]) -> IResult<&[u8], Self> {
         map_res(take(16usize), uuid::Uuid::from_slice)(data)
     }
-    #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        self.as_bytes().to_vec()
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        buf.put_slice(self.as_bytes());
+    }
+    fn encoded_len(&self) -> usize {
+        16
     }
 }
 impl Parsable for serde_json::Value {
@@ -155,9 +727,15 @@ impl Parsable for serde_json::Value {
     fn parse(data: &[u8]) -> IResult<&[u8], Self> {
         map_res(String::parse, |json: String| serde_json::from_str(&json))(data)
     }
-    #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        serde_json::to_string(self).expect("valid json").serialize()
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        serde_json::to_string(self)
+            .expect("valid json")
+            .serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        serde_json::to_string(self)
+            .expect("valid json")
+            .encoded_len()
     }
 }
 impl Parsable for VarInt {
@@ -178,22 +756,60 @@ impl Parsable for VarInt {
         output |= ((end[0] & 0x7f) as u32) << (7 * start.len());
         Ok((rest, VarInt(output as i32)))
     }
-    #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
+    fn serialize_into(&self, buf: &mut impl BufMut) {
         let mut value = self.0 as u32;
-        let mut output = vec![];
         loop {
             let data = (value & 0x7f) as u8;
             value >>= 7;
 
             if value == 0 {
-                output.push(data);
+                buf.put_u8(data);
                 break;
             } else {
-                output.push(data | 0x80);
+                buf.put_u8(data | 0x80);
             }
         }
-        output
+    }
+    fn encoded_len(&self) -> usize {
+        // 7 bits per byte, with at least one byte.
+        (32 - (self.0 as u32 | 1).leading_zeros() as usize).div_ceil(7)
+    }
+}
+impl Parsable for VarLong {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let mut output = 0u64;
+
+        // 0-9 bytes with the most significant bit set,
+        // followed by one with the bit unset.
+        let start_parser = take_while_m_n(0, 9, |byte| byte & 0x80 == 0x80);
+        let end_parser = take_while_m_n(1, 1, |byte| byte & 0x80 != 0x80);
+        let mut parser = start_parser.and(end_parser);
+        let (rest, (start, end)) = parser.parse(data)?;
+
+        for (i, &b) in start.iter().enumerate() {
+            output |= ((b & 0x7f) as u64) << (7 * i);
+        }
+        output |= ((end[0] & 0x7f) as u64) << (7 * start.len());
+        Ok((rest, VarLong(output as i64)))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        let mut value = self.0 as u64;
+        loop {
+            let data = (value & 0x7f) as u8;
+            value >>= 7;
+
+            if value == 0 {
+                buf.put_u8(data);
+                break;
+            } else {
+                buf.put_u8(data | 0x80);
+            }
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        // 7 bits per byte, with at least one byte.
+        (64 - (self.0 as u64 | 1).leading_zeros() as usize).div_ceil(7)
     }
 }
 impl Parsable for String {
@@ -204,12 +820,119 @@ impl Parsable for String {
         let s = String::from_utf8_lossy(str_bytes).to_string();
         Ok((data, s))
     }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        VarInt::from(self.len()).serialize_into(buf);
+        buf.put_slice(self.as_bytes());
+    }
+    fn encoded_len(&self) -> usize {
+        VarInt::from(self.len()).encoded_len() + self.len()
+    }
+}
+impl Parsable for Angle {
     #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        let mut output = vec![];
-        output.extend(VarInt::from(self.len()).serialize());
-        output.extend(self.as_bytes());
-        output
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        nom::combinator::map(u8::parse, Angle)(data)
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        buf.put_u8(self.0);
+    }
+    fn encoded_len(&self) -> usize {
+        1
+    }
+}
+impl Parsable for Identifier {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        map_res(BoundedString::<32767>::parse, |s| s.parse())(data)
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        let len = self.namespace.len() + 1 + self.path.len();
+        VarInt::from(len).serialize_into(buf);
+        buf.put_slice(self.namespace.as_bytes());
+        buf.put_u8(b':');
+        buf.put_slice(self.path.as_bytes());
+    }
+    fn encoded_len(&self) -> usize {
+        let len = self.namespace.len() + 1 + self.path.len();
+        VarInt::from(len).encoded_len() + len
+    }
+}
+impl<const MAX: usize> Parsable for BoundedString<MAX> {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        // Each UTF-16 code unit takes at most 3 bytes of UTF-8,
+        // so check the byte length before taking the bytes.
+        nom::combinator::verify(VarInt::parse_usize, |len| *len <= MAX * 3)(data)?;
+        let (data, s) =
+            nom::combinator::verify(String::parse, |s: &String| s.encode_utf16().count() <= MAX)(
+                data,
+            )?;
+        Ok((data, BoundedString(s)))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.0.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.0.encoded_len()
+    }
+}
+impl Parsable for LpVec3 {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, first) = u8::parse(data)?;
+        if first == 0 {
+            return Ok((data, LpVec3::default()));
+        }
+        let (data, second) = u8::parse(data)?;
+        let (data, rest) = u32::parse(data)?;
+        let bits = (rest as u64) << 16 | (second as u64) << 8 | first as u64;
+
+        let mut scale = bits & 0b11;
+        let data = if bits & Self::CONTINUATION_FLAG != 0 {
+            let (data, high) = VarInt::parse(data)?;
+            scale |= (*high as u32 as u64) << 2;
+            data
+        } else {
+            data
+        };
+        let scale = scale as f64;
+        Ok((
+            data,
+            LpVec3 {
+                x: Self::unpack(bits >> 3) * scale,
+                y: Self::unpack(bits >> 18) * scale,
+                z: Self::unpack(bits >> 33) * scale,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        let Some(([x, y, z], scale)) = self.scaled() else {
+            buf.put_u8(0);
+            return;
+        };
+        let continued = scale > 0b11;
+        let mut bits = scale & 0b11;
+        if continued {
+            bits |= Self::CONTINUATION_FLAG;
+        }
+        let scale_f = scale as f64;
+        bits |= Self::pack(x / scale_f) << 3
+            | Self::pack(y / scale_f) << 18
+            | Self::pack(z / scale_f) << 33;
+
+        buf.put_u8(bits as u8);
+        buf.put_u8((bits >> 8) as u8);
+        buf.put_u32((bits >> 16) as u32);
+        if continued {
+            VarInt::from((scale >> 2) as i32).serialize_into(buf);
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        match self.scaled() {
+            None => 1,
+            Some((_, scale)) if scale > 0b11 => 6 + VarInt::from((scale >> 2) as i32).encoded_len(),
+            Some(_) => 6,
+        }
     }
 }
 impl Parsable for u8 {
@@ -217,9 +940,11 @@ impl Parsable for u8 {
     fn parse(data: &[u8]) -> IResult<&[u8], Self> {
         nom_nums::u8(data)
     }
-    #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        self.to_be_bytes().to_vec()
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        buf.put_u8(*self);
+    }
+    fn encoded_len(&self) -> usize {
+        std::mem::size_of::<Self>()
     }
 }
 impl Parsable for i8 {
@@ -227,9 +952,11 @@ impl Parsable for i8 {
     fn parse(data: &[u8]) -> IResult<&[u8], Self> {
         nom_nums::i8(data)
     }
-    #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        self.to_be_bytes().to_vec()
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        buf.put_i8(*self);
+    }
+    fn encoded_len(&self) -> usize {
+        std::mem::size_of::<Self>()
     }
 }
 impl Parsable for u16 {
@@ -237,9 +964,11 @@ impl Parsable for u16 {
     fn parse(data: &[u8]) -> IResult<&[u8], Self> {
         nom_nums::be_u16(data)
     }
-    #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        self.to_be_bytes().to_vec()
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        buf.put_u16(*self);
+    }
+    fn encoded_len(&self) -> usize {
+        std::mem::size_of::<Self>()
     }
 }
 impl Parsable for i16 {
@@ -247,9 +976,11 @@ impl Parsable for i16 {
     fn parse(data: &[u8]) -> IResult<&[u8], Self> {
         nom_nums::be_i16(data)
     }
-    #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        self.to_be_bytes().to_vec()
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        buf.put_i16(*self);
+    }
+    fn encoded_len(&self) -> usize {
+        std::mem::size_of::<Self>()
     }
 }
 impl Parsable for u32 {
@@ -257,9 +988,11 @@ impl Parsable for u32 {
     fn parse(data: &[u8]) -> IResult<&[u8], Self> {
         nom_nums::be_u32(data)
     }
-    #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        self.to_be_bytes().to_vec()
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        buf.put_u32(*self);
+    }
+    fn encoded_len(&self) -> usize {
+        std::mem::size_of::<Self>()
     }
 }
 impl Parsable for i32 {
@@ -267,9 +1000,11 @@ impl Parsable for i32 {
     fn parse(data: &[u8]) -> IResult<&[u8], Self> {
         nom_nums::be_i32(data)
     }
-    #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        self.to_be_bytes().to_vec()
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        buf.put_i32(*self);
+    }
+    fn encoded_len(&self) -> usize {
+        std::mem::size_of::<Self>()
     }
 }
 impl Parsable for u64 {
@@ -277,9 +1012,11 @@ impl Parsable for u64 {
     fn parse(data: &[u8]) -> IResult<&[u8], Self> {
         nom_nums::be_u64(data)
     }
-    #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        self.to_be_bytes().to_vec()
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        buf.put_u64(*self);
+    }
+    fn encoded_len(&self) -> usize {
+        std::mem::size_of::<Self>()
     }
 }
 impl Parsable for i64 {
@@ -287,9 +1024,11 @@ impl Parsable for i64 {
     fn parse(data: &[u8]) -> IResult<&[u8], Self> {
         nom_nums::be_i64(data)
     }
-    #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        self.to_be_bytes().to_vec()
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        buf.put_i64(*self);
+    }
+    fn encoded_len(&self) -> usize {
+        std::mem::size_of::<Self>()
     }
 }
 impl Parsable for u128 {
@@ -297,9 +1036,11 @@ impl Parsable for u128 {
     fn parse(data: &[u8]) -> IResult<&[u8], Self> {
         nom_nums::be_u128(data)
     }
-    #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        self.to_be_bytes().to_vec()
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        buf.put_u128(*self);
+    }
+    fn encoded_len(&self) -> usize {
+        std::mem::size_of::<Self>()
     }
 }
 impl Parsable for i128 {
@@ -307,9 +1048,11 @@ impl Parsable for i128 {
     fn parse(data: &[u8]) -> IResult<&[u8], Self> {
         nom_nums::be_i128(data)
     }
-    #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        self.to_be_bytes().to_vec()
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        buf.put_i128(*self);
+    }
+    fn encoded_len(&self) -> usize {
+        std::mem::size_of::<Self>()
     }
 }
 impl Parsable for f32 {
@@ -317,9 +1060,11 @@ impl Parsable for f32 {
     fn parse(data: &[u8]) -> IResult<&[u8], Self> {
         nom_nums::be_f32(data)
     }
-    #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        self.to_be_bytes().to_vec()
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        buf.put_f32(*self);
+    }
+    fn encoded_len(&self) -> usize {
+        std::mem::size_of::<Self>()
     }
 }
 impl Parsable for f64 {
@@ -327,9 +1072,11 @@ impl Parsable for f64 {
     fn parse(data: &[u8]) -> IResult<&[u8], Self> {
         nom_nums::be_f64(data)
     }
-    #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        self.to_be_bytes().to_vec()
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        buf.put_f64(*self);
+    }
+    fn encoded_len(&self) -> usize {
+        std::mem::size_of::<Self>()
     }
 }
 impl Parsable for bool {
@@ -337,13 +1084,11 @@ impl Parsable for bool {
     fn parse(data: &[u8]) -> IResult<&[u8], Self> {
         nom_nums::u8(data).map(|(data, num)| (data, num > 0x00))
     }
-    #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        if *self {
-            vec![0x01]
-        } else {
-            vec![0x00]
-        }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        buf.put_u8(*self as u8);
+    }
+    fn encoded_len(&self) -> usize {
+        1
     }
 }
 
@@ -379,8 +1124,254 @@ mod tests {
     fn serialize_varint_works() {
         for (value, bytes) in get_varints() {
             assert_eq!(bytes, VarInt::from(value).serialize());
+            assert_eq!(bytes.len(), VarInt::from(value).encoded_len());
+        }
+    }
+
+    fn get_varlongs() -> Vec<(i64, Vec<u8>)> {
+        vec![
+            (0, vec![0x00]),
+            (1, vec![0x01]),
+            (127, vec![0x7f]),
+            (128, vec![0x80, 0x01]),
+            (2147483647, vec![0xff, 0xff, 0xff, 0xff, 0x07]),
+            (
+                9223372036854775807,
+                vec![0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f],
+            ),
+            (
+                -1,
+                vec![0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01],
+            ),
+            (
+                -9223372036854775808,
+                vec![0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01],
+            ),
+        ]
+    }
+    #[test]
+    fn parse_varlong_works() {
+        for (value, bytes) in get_varlongs() {
+            assert_eq!(value, *VarLong::parse(&bytes).unwrap().1);
+        }
+    }
+    #[test]
+    fn serialize_varlong_works() {
+        for (value, bytes) in get_varlongs() {
+            assert_eq!(bytes, VarLong::from(value).serialize());
+            assert_eq!(bytes.len(), VarLong::from(value).encoded_len());
+        }
+    }
+    #[test]
+    fn serialize_into_works() {
+        fn check<T: Parsable>(value: T) {
+            let mut buf = vec![0xff];
+            value.serialize_into(&mut buf);
+            assert_eq!(buf[1..], value.serialize()[..]);
+            assert_eq!(value.encoded_len(), buf.len() - 1);
+        }
+        check(String::from("hello"));
+        check(vec![(VarInt::from(300), 1.5f32), (VarInt::from(-1), -0.5)]);
+        check(PrefixedOptional(Some(uuid::Uuid::from_u128(1))));
+        check(PrefixedOptional::<i64>(None));
+        check(Some(true));
+        check([1u16, 2, 3]);
+        check(BitSet(vec![1, -1]));
+        check(FixedBitSet([1, 2, 3]));
+        check(IdOr::Inline(String::from("a")));
+        check(IdSet::Ids(vec![VarInt::from(128)]));
+        check(serde_json::json!({ "a": 1 }));
+    }
+    #[test]
+    fn prefixed_optional_works() {
+        let some = PrefixedOptional(Some(VarInt::from(300)));
+        assert_eq!(some.serialize(), vec![0x01, 0xac, 0x02]);
+        assert_eq!(
+            PrefixedOptional::<VarInt>::parse(&[0x01, 0xac, 0x02]).unwrap(),
+            (&[][..], some)
+        );
+        assert_eq!(PrefixedOptional::<VarInt>(None).serialize(), vec![0x00]);
+        assert_eq!(
+            PrefixedOptional::<VarInt>::parse(&[0x00, 0xac]).unwrap(),
+            (&[0xac][..], PrefixedOptional(None))
+        );
+    }
+
+    #[test]
+    fn bit_sets_work() {
+        let mut bits = BitSet::default();
+        bits.set(65, true);
+        bits.set(200, false);
+        assert_eq!(bits, BitSet(vec![0, 2]));
+        assert!(bits.get(65) && !bits.get(64) && !bits.get(1000));
+        assert_eq!(
+            bits.serialize(),
+            vec![0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]
+        );
+        assert_eq!(BitSet::parse(&bits.serialize()).unwrap(), (&[][..], bits));
+
+        let mut fixed = FixedBitSet::<3>::default();
+        fixed.set(9, true);
+        fixed.set(24, true);
+        assert_eq!(fixed.serialize(), vec![0x00, 0x02, 0x00]);
+        assert!(fixed.get(9) && !fixed.get(8));
+        assert_eq!(
+            FixedBitSet::<3>::parse(&[0x00, 0x02, 0x00, 0xff]).unwrap(),
+            (&[0xff][..], fixed)
+        );
+    }
+    #[test]
+    fn id_or_works() {
+        let id = IdOr::<String>::Id(VarInt::from(4));
+        assert_eq!(id.serialize(), vec![0x05]);
+        assert_eq!(IdOr::<String>::parse(&[0x05]).unwrap(), (&[][..], id));
+        let inline = IdOr::Inline("a".to_owned());
+        assert_eq!(inline.serialize(), vec![0x00, 0x01, 0x61]);
+        assert_eq!(
+            IdOr::<String>::parse(&[0x00, 0x01, 0x61]).unwrap(),
+            (&[][..], inline)
+        );
+    }
+    #[test]
+    fn id_set_works() {
+        for (set, bytes) in [
+            (IdSet::Tag("a".to_owned()), vec![0x00, 0x01, 0x61]),
+            (
+                IdSet::Ids(vec![VarInt::from(1), VarInt::from(300)]),
+                vec![0x03, 0x01, 0xac, 0x02],
+            ),
+            (IdSet::Ids(vec![]), vec![0x01]),
+        ] {
+            assert_eq!(set.serialize(), bytes);
+            assert_eq!(IdSet::parse(&bytes).unwrap(), (&[][..], set));
         }
     }
+    #[test]
+    fn varint_enums_work() {
+        varint_enum! {
+            enum Example {
+                A = 0,
+                B = 300,
+            }
+        }
+        assert_eq!(Example::B.serialize(), vec![0xac, 0x02]);
+        assert_eq!(Example::parse(&[0x00]).unwrap(), (&[][..], Example::A));
+        assert!(Example::parse(&[0x01]).is_err());
+    }
+    #[test]
+    fn angles_work() {
+        assert_eq!(Angle::from_degrees(90.0), Angle(64));
+        assert_eq!(Angle::from_degrees(-90.0), Angle(192));
+        assert_eq!(Angle::from_degrees(360.0), Angle(0));
+        assert_eq!(Angle(128).to_degrees(), 180.0);
+        assert_eq!(Angle(64).serialize(), vec![0x40]);
+        assert_eq!(Angle::parse(&[0xc0]).unwrap(), (&[][..], Angle(192)));
+    }
+    #[test]
+    fn identifiers_work() {
+        let stone: Identifier = "stone".parse().unwrap();
+        assert_eq!(stone, Identifier::minecraft("stone").unwrap());
+        assert_eq!(stone.to_string(), "minecraft:stone");
+        let custom = Identifier::try_from("composition:sounds/click_1.ogg").unwrap();
+        assert_eq!(custom.namespace, "composition");
+        assert_eq!(custom.path, "sounds/click_1.ogg");
+
+        assert!("Stone".parse::<Identifier>().is_err());
+        assert!("a/b:c".parse::<Identifier>().is_err());
+        assert!("a:b:c".parse::<Identifier>().is_err());
+
+        let bytes = String::from("composition:sounds/click_1.ogg").serialize();
+        assert_eq!(custom.serialize(), bytes);
+        assert_eq!(custom.encoded_len(), bytes.len());
+        assert_eq!(Identifier::parse(&bytes).unwrap(), (&[][..], custom));
+        assert!(Identifier::parse(&String::from("no spaces").serialize()).is_err());
+    }
+    #[test]
+    fn bounded_strings_work() {
+        let bytes = String::from("four").serialize();
+        assert_eq!(
+            BoundedString::<4>::parse(&bytes).unwrap(),
+            (&[][..], BoundedString::from("four"))
+        );
+        assert!(BoundedString::<3>::parse(&bytes).is_err());
+        // The limit is in UTF-16 code units, not bytes.
+        let bytes = String::from("\u{e9}\u{e9}").serialize();
+        assert!(BoundedString::<2>::parse(&bytes).is_ok());
+        let bytes = String::from("\u{1f600}").serialize();
+        assert!(BoundedString::<1>::parse(&bytes).is_err());
+        // Oversized lengths are rejected before waiting for the rest of the string.
+        assert!(matches!(
+            BoundedString::<1>::parse(&[0x04]),
+            Err(nom::Err::Error(_))
+        ));
+    }
+    #[test]
+    fn lp_vec3_works() {
+        let zero = LpVec3::default();
+        assert_eq!(zero.serialize(), vec![0x00]);
+        assert_eq!(LpVec3::parse(&[0x00]).unwrap(), (&[][..], zero));
+
+        let check = |vec: LpVec3, len: usize| {
+            let bytes = vec.serialize();
+            assert_eq!(bytes.len(), len);
+            assert_eq!(vec.encoded_len(), len);
+            let (rest, parsed) = LpVec3::parse(&bytes).unwrap();
+            assert!(rest.is_empty());
+            // Each component keeps 15 bits relative to the scale.
+            let scale = [vec.x, vec.y, vec.z]
+                .iter()
+                .fold(0.0f64, |max, c| max.max(c.abs()))
+                .ceil();
+            for (a, b) in [(vec.x, parsed.x), (vec.y, parsed.y), (vec.z, parsed.z)] {
+                assert!(
+                    (a - b).abs() <= scale / 32766.0,
+                    "{vec:?} became {parsed:?}"
+                );
+            }
+        };
+        check(
+            LpVec3 {
+                x: 0.5,
+                y: -0.25,
+                z: 1.0,
+            },
+            6,
+        );
+        check(
+            LpVec3 {
+                x: -3.0,
+                y: 2.5,
+                z: 0.0,
+            },
+            6,
+        );
+        // Scales above 3 need a trailing VarInt.
+        check(
+            LpVec3 {
+                x: 10.0,
+                y: -4.5,
+                z: 0.125,
+            },
+            7,
+        );
+        check(
+            LpVec3 {
+                x: 0.0,
+                y: 1000.0,
+                z: -999.0,
+            },
+            8,
+        );
+        assert_eq!(
+            LpVec3 {
+                x: f64::NAN,
+                y: 0.0,
+                z: 0.0
+            }
+            .serialize(),
+            vec![0x00]
+        );
+    }
 
     fn get_strings() -> Vec<(&'static str, Vec<u8>)> {
         let s_127 = "0123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456";
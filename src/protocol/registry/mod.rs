@@ -0,0 +1,405 @@
+use crate::protocol::{
+    nbt::{self, SnbtError, Tag},
+    packets::configuration::clientbound::{ConfigurationUpdateTags, RegistryData},
+    parsing::PrefixedOptional,
+    types::{KnownPack, VarInt},
+    ProtocolVersion,
+};
+use once_cell::sync::OnceCell;
+
+/// The bundled registries, as SNBT compounds of entry id to entry data,
+/// with the version each registry was first synchronized with the client in.
+///
+/// Only the registries the client needs to enter Play are included,
+/// and entry data is laid out as in [`ProtocolVersion::LATEST`].
+const VANILLA_SOURCES: &[(&str, ProtocolVersion, &str)] = &[
+    (
+        "minecraft:dimension_type",
+        ProtocolVersion::BASELINE,
+        include_str!("data/dimension_type.snbt"),
+    ),
+    (
+        "minecraft:worldgen/biome",
+        ProtocolVersion::BASELINE,
+        include_str!("data/biome.snbt"),
+    ),
+    (
+        "minecraft:damage_type",
+        ProtocolVersion::BASELINE,
+        include_str!("data/damage_type.snbt"),
+    ),
+    (
+        "minecraft:chat_type",
+        ProtocolVersion::BASELINE,
+        include_str!("data/chat_type.snbt"),
+    ),
+    (
+        "minecraft:painting_variant",
+        ProtocolVersion::BASELINE,
+        include_str!("data/painting_variant.snbt"),
+    ),
+    (
+        "minecraft:wolf_variant",
+        ProtocolVersion::BASELINE,
+        include_str!("data/wolf_variant.snbt"),
+    ),
+    (
+        "minecraft:wolf_sound_variant",
+        ProtocolVersion::V1_21_5,
+        include_str!("data/wolf_sound_variant.snbt"),
+    ),
+    (
+        "minecraft:cat_variant",
+        ProtocolVersion::V1_21_5,
+        include_str!("data/cat_variant.snbt"),
+    ),
+    (
+        "minecraft:pig_variant",
+        ProtocolVersion::V1_21_5,
+        include_str!("data/pig_variant.snbt"),
+    ),
+    (
+        "minecraft:cow_variant",
+        ProtocolVersion::V1_21_5,
+        include_str!("data/cow_variant.snbt"),
+    ),
+    (
+        "minecraft:chicken_variant",
+        ProtocolVersion::V1_21_5,
+        include_str!("data/chicken_variant.snbt"),
+    ),
+    (
+        "minecraft:frog_variant",
+        ProtocolVersion::V1_21_5,
+        include_str!("data/frog_variant.snbt"),
+    ),
+];
+static VANILLA: OnceCell<Vec<Registry>> = OnceCell::new();
+
+/// The bundled tags, as SNBT compounds of tag name to entry ids for a registry in
+/// `VANILLA_SOURCES`.
+const VANILLA_TAG_SOURCES: &[(&str, &str)] = &[(
+    "minecraft:damage_type",
+    include_str!("data/tags/damage_type.snbt"),
+)];
+static VANILLA_TAGS: OnceCell<Vec<RegistryTags>> = OnceCell::new();
+
+/// The vanilla registries bundled with Composition, parsed on first use.
+pub fn vanilla() -> &'static [Registry] {
+    VANILLA.get_or_init(|| {
+        VANILLA_SOURCES
+            .iter()
+            .map(|(id, _, snbt)| Registry::from_snbt(*id, snbt).expect("bundled registry is valid"))
+            .collect()
+    })
+}
+/// The bundled vanilla registries that clients on `version` expect.
+pub fn vanilla_for(version: ProtocolVersion) -> impl Iterator<Item = &'static Registry> {
+    VANILLA_SOURCES
+        .iter()
+        .zip(vanilla())
+        .filter(move |((_, since, _), _)| *since <= version)
+        .map(|(_, registry)| registry)
+}
+/// Find one of the bundled vanilla registries by id.
+pub fn vanilla_registry(id: &str) -> Option<&'static Registry> {
+    vanilla().iter().find(|registry| registry.id == id)
+}
+
+/// The vanilla tags bundled with Composition, parsed on first use.
+pub fn vanilla_tags() -> &'static [RegistryTags] {
+    VANILLA_TAGS.get_or_init(|| {
+        VANILLA_TAG_SOURCES
+            .iter()
+            .map(|(id, snbt)| {
+                let registry = vanilla_registry(id).expect("tags are for a bundled registry");
+                RegistryTags::from_snbt(registry, snbt).expect("bundled tags are valid")
+            })
+            .collect()
+    })
+}
+/// The bundled vanilla tags for the registries that clients on `version` expect.
+pub fn vanilla_tags_for(version: ProtocolVersion) -> impl Iterator<Item = &'static RegistryTags> {
+    vanilla_tags()
+        .iter()
+        .filter(move |tags| vanilla_for(version).any(|registry| registry.id == tags.registry_id))
+}
+
+/// Build the `RegistryData` packets to send a client that has `known_packs`.
+///
+/// The bundled registries come from the `minecraft:core` pack of `version`,
+/// so their data is left out if the client already has it.
+pub fn registry_packets<'r>(
+    version: ProtocolVersion,
+    registries: impl IntoIterator<Item = &'r Registry>,
+    known_packs: &[KnownPack],
+) -> Vec<RegistryData> {
+    let include_data = !known_packs.contains(&KnownPack::core(version));
+    registries
+        .into_iter()
+        .map(|registry| registry.to_packet(include_data))
+        .collect()
+}
+
+/// Build the `ConfigurationUpdateTags` packet that sends `tags`.
+pub fn tags_packet<'t>(
+    tags: impl IntoIterator<Item = &'t RegistryTags>,
+) -> ConfigurationUpdateTags {
+    ConfigurationUpdateTags {
+        tags: tags
+            .into_iter()
+            .map(|tags| (tags.registry_id.clone(), tags.tags.clone()))
+            .collect(),
+    }
+}
+
+/// Why a registry or its tags couldn't be loaded from SNBT.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    #[error(transparent)]
+    Snbt(#[from] SnbtError),
+    /// The SNBT was valid, but wasn't a compound of entry or tag ids.
+    #[error("expected a compound of registry entries")]
+    ExpectedCompound,
+    /// A tag wasn't a list of entry ids, or included itself.
+    #[error("tag {0} must be a list of entry ids that doesn't include itself")]
+    InvalidTag(String),
+    /// A tag named an entry or tag that doesn't exist.
+    #[error("unknown registry entry {0}")]
+    UnknownEntry(String),
+}
+
+/// A registry synchronized with the client during configuration.
+///
+/// The client numbers entries in the order they are sent,
+/// so entry indices are the ids used in Play packets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Registry {
+    pub id: String,
+    pub entries: Vec<(String, Tag)>,
+}
+impl Registry {
+    /// Parse a registry from an SNBT compound of entry id to entry data.
+    pub fn from_snbt(id: impl Into<String>, snbt: &str) -> Result<Registry, RegistryError> {
+        let Tag::Compound(entries) = nbt::from_snbt(snbt)? else {
+            return Err(RegistryError::ExpectedCompound);
+        };
+        Ok(Registry {
+            id: id.into(),
+            entries: entries.into_iter().collect(),
+        })
+    }
+    /// The network id of an entry.
+    pub fn index_of(&self, entry: &str) -> Option<usize> {
+        self.entries.iter().position(|(id, _)| id == entry)
+    }
+    pub fn to_packet(&self, include_data: bool) -> RegistryData {
+        RegistryData {
+            registry_id: self.id.clone(),
+            entries: self
+                .entries
+                .iter()
+                .map(|(id, data)| {
+                    let data = Some(data.clone()).filter(|_| include_data);
+                    (id.clone(), PrefixedOptional(data))
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Named sets of a registry's entries, such as the damage types that bypass armor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistryTags {
+    pub registry_id: String,
+    /// Tag names paired with the network ids of their entries.
+    pub tags: Vec<(String, Vec<VarInt>)>,
+}
+impl RegistryTags {
+    /// Parse tags from an SNBT compound of tag name to a list of entry ids in `registry`.
+    ///
+    /// Ids starting with `#` include every entry of another tag in the same compound.
+    pub fn from_snbt(registry: &Registry, snbt: &str) -> Result<RegistryTags, RegistryError> {
+        let Tag::Compound(tags) = nbt::from_snbt(snbt)? else {
+            return Err(RegistryError::ExpectedCompound);
+        };
+        let tags = tags.into_iter().collect::<Vec<_>>();
+        let resolved = tags
+            .iter()
+            .map(|(name, _)| Ok((name.clone(), Self::resolve(registry, &tags, name, 0)?)))
+            .collect::<Result<_, RegistryError>>()?;
+        Ok(RegistryTags {
+            registry_id: registry.id.clone(),
+            tags: resolved,
+        })
+    }
+    /// Find the network ids of a tag's entries, including those of any tags it includes.
+    fn resolve(
+        registry: &Registry,
+        tags: &[(String, Tag)],
+        name: &str,
+        depth: usize,
+    ) -> Result<Vec<VarInt>, RegistryError> {
+        let Some((_, tag)) = tags.iter().find(|(id, _)| id == name) else {
+            return Err(RegistryError::UnknownEntry(format!("#{name}")));
+        };
+        let (Tag::List(values), true) = (tag, depth < tags.len()) else {
+            return Err(RegistryError::InvalidTag(name.to_owned()));
+        };
+        let mut ids = vec![];
+        for value in values {
+            let Tag::String(value) = value else {
+                return Err(RegistryError::InvalidTag(name.to_owned()));
+            };
+            let included = match value.strip_prefix('#') {
+                Some(other) => Self::resolve(registry, tags, other, depth + 1)?,
+                None => {
+                    let index = registry
+                        .index_of(value)
+                        .ok_or_else(|| RegistryError::UnknownEntry(value.clone()))?;
+                    vec![VarInt::from(index as i32)]
+                }
+            };
+            for id in included {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+        }
+        Ok(ids)
+    }
+    /// The network ids of a tag's entries.
+    pub fn get(&self, name: &str) -> Option<&[VarInt]> {
+        self.tags
+            .iter()
+            .find(|(id, _)| id == name)
+            .map(|(_, ids)| ids.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::parsing::Parsable;
+
+    #[test]
+    fn vanilla_registries_parse() {
+        assert_eq!(vanilla().len(), VANILLA_SOURCES.len());
+        for registry in vanilla() {
+            assert!(!registry.entries.is_empty(), "{} is empty", registry.id);
+            for (id, data) in &registry.entries {
+                assert!(data.as_compound().is_some(), "{id} is not a compound");
+            }
+        }
+        let dimension_types = vanilla_registry("minecraft:dimension_type").unwrap();
+        assert_eq!(dimension_types.index_of("minecraft:overworld"), Some(0));
+        assert_eq!(
+            vanilla_registry("minecraft:damage_type")
+                .unwrap()
+                .entries
+                .len(),
+            49
+        );
+    }
+    #[test]
+    fn registry_errors_work() {
+        assert_eq!(
+            Registry::from_snbt("a:b", "[1, 2]"),
+            Err(RegistryError::ExpectedCompound)
+        );
+        let Err(RegistryError::Snbt(error)) = Registry::from_snbt("a:b", "{\n  a: }") else {
+            panic!("expected an SNBT error");
+        };
+        assert_eq!((error.line, error.column), (2, 6));
+        assert!(Registry::from_snbt("a:b", "{}").unwrap().entries.is_empty());
+    }
+    #[test]
+    fn vanilla_tags_work() {
+        let damage_types = vanilla_registry("minecraft:damage_type").unwrap();
+        let tags = &vanilla_tags()[0];
+        assert_eq!(tags.registry_id, "minecraft:damage_type");
+        let id = |entry| VarInt::from(damage_types.index_of(entry).unwrap() as i32);
+        assert!(tags
+            .get("minecraft:is_fire")
+            .unwrap()
+            .contains(&id("minecraft:lava")));
+        // Included tags are expanded into their entries.
+        let explosions = tags.get("minecraft:is_explosion").unwrap();
+        assert_eq!(
+            tags.get("minecraft:always_hurts_ender_dragons"),
+            Some(explosions)
+        );
+        let bypasses_shield = tags.get("minecraft:bypasses_shield").unwrap();
+        for id in tags.get("minecraft:bypasses_armor").unwrap() {
+            assert!(bypasses_shield.contains(id));
+        }
+
+        for version in [ProtocolVersion::V1_21_4, ProtocolVersion::LATEST] {
+            let packet = tags_packet(vanilla_tags_for(version));
+            assert_eq!(packet.tags.len(), 1);
+            assert_eq!(packet.tags[0].1, tags.tags);
+        }
+    }
+    #[test]
+    fn tag_errors_work() {
+        let registry = Registry::from_snbt("a:b", "{\"a:x\": {}, \"a:y\": {}}").unwrap();
+        let tags = RegistryTags::from_snbt(&registry, "{\"a:t\": [\"a:y\", \"a:x\"]}");
+        assert_eq!(
+            tags.unwrap().get("a:t"),
+            Some(&[VarInt::from(1), VarInt::from(0)][..])
+        );
+        assert_eq!(
+            RegistryTags::from_snbt(&registry, "{\"a:t\": [\"a:z\"]}"),
+            Err(RegistryError::UnknownEntry("a:z".to_owned()))
+        );
+        assert_eq!(
+            RegistryTags::from_snbt(&registry, "{\"a:t\": [\"#a:u\"]}"),
+            Err(RegistryError::UnknownEntry("#a:u".to_owned()))
+        );
+        assert_eq!(
+            RegistryTags::from_snbt(&registry, "{\"a:t\": [\"#a:u\"], \"a:u\": [\"#a:t\"]}"),
+            Err(RegistryError::InvalidTag("a:t".to_owned()))
+        );
+    }
+    #[test]
+    fn registries_depend_on_version() {
+        let ids = |version| {
+            vanilla_for(version)
+                .map(|r| r.id.as_str())
+                .collect::<Vec<_>>()
+        };
+        let latest = ids(ProtocolVersion::LATEST);
+        assert_eq!(latest.len(), vanilla().len());
+        let old = ids(ProtocolVersion::V1_21_4);
+        assert!(old.contains(&"minecraft:wolf_variant"));
+        for id in [
+            "minecraft:pig_variant",
+            "minecraft:cow_variant",
+            "minecraft:chicken_variant",
+            "minecraft:wolf_sound_variant",
+        ] {
+            assert!(latest.contains(&id));
+            assert!(!old.contains(&id), "{id} is new in 1.21.5");
+        }
+    }
+    #[test]
+    fn known_packs_omit_data() {
+        let version = ProtocolVersion::LATEST;
+        let without = registry_packets(version, vanilla(), &[]);
+        assert!(without
+            .iter()
+            .flat_map(|packet| &packet.entries)
+            .all(|(_, data)| data.is_some()));
+
+        let with = registry_packets(version, vanilla(), &[KnownPack::core(version)]);
+        assert!(with
+            .iter()
+            .flat_map(|packet| &packet.entries)
+            .all(|(_, data)| data.is_none()));
+
+        for packet in without.into_iter().chain(with) {
+            let bytes = packet.serialize();
+            assert_eq!(RegistryData::parse(&bytes).unwrap(), (&[][..], packet));
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use crate::protocol::{
+    nbt::Tag,
+    parsing::{BufMut, IResult, Parsable},
+    text::TextComponent,
+    types::{PrefixedOptional, VarInt},
+};
+
+/// How scores are shown in the sidebar and player list,
+/// represented in the protocol as a `VarInt` of the kind followed by its fields.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum NumberFormat {
+    /// Scores aren't shown.
+    #[default]
+    Blank,
+    /// Scores are shown with a text style.
+    Styled(Tag),
+    /// The given text is shown instead of scores.
+    Fixed(TextComponent),
+}
+impl Parsable for NumberFormat {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, kind) = VarInt::parse(data)?;
+        match *kind {
+            0 => Ok((data, NumberFormat::Blank)),
+            1 => nom::combinator::map(Tag::parse, NumberFormat::Styled)(data),
+            2 => nom::combinator::map(TextComponent::parse, NumberFormat::Fixed)(data),
+            _ => nom::combinator::fail(data),
+        }
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        match self {
+            NumberFormat::Blank => VarInt::from(0).serialize_into(buf),
+            NumberFormat::Styled(style) => {
+                VarInt::from(1).serialize_into(buf);
+                style.serialize_into(buf);
+            }
+            NumberFormat::Fixed(content) => {
+                VarInt::from(2).serialize_into(buf);
+                content.serialize_into(buf);
+            }
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        1 + match self {
+            NumberFormat::Blank => 0,
+            NumberFormat::Styled(style) => style.encoded_len(),
+            NumberFormat::Fixed(content) => content.encoded_len(),
+        }
+    }
+}
+
+/// A change to a scoreboard objective, represented in the protocol
+/// as a byte of the mode followed by its fields.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ObjectiveAction {
+    Create(ObjectiveInfo),
+    #[default]
+    Remove,
+    Update(ObjectiveInfo),
+}
+impl Parsable for ObjectiveAction {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, mode) = i8::parse(data)?;
+        match mode {
+            0 => nom::combinator::map(ObjectiveInfo::parse, ObjectiveAction::Create)(data),
+            1 => Ok((data, ObjectiveAction::Remove)),
+            2 => nom::combinator::map(ObjectiveInfo::parse, ObjectiveAction::Update)(data),
+            _ => nom::combinator::fail(data),
+        }
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        match self {
+            ObjectiveAction::Create(info) => {
+                0i8.serialize_into(buf);
+                info.serialize_into(buf);
+            }
+            ObjectiveAction::Remove => 1i8.serialize_into(buf),
+            ObjectiveAction::Update(info) => {
+                2i8.serialize_into(buf);
+                info.serialize_into(buf);
+            }
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        1 + match self {
+            ObjectiveAction::Create(info) => info.encoded_len(),
+            ObjectiveAction::Remove => 0,
+            ObjectiveAction::Update(info) => info.encoded_len(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ObjectiveInfo {
+    pub display_name: TextComponent,
+    pub render_type: ObjectiveRenderType,
+    /// The format for scores that don't have their own.
+    pub number_format: PrefixedOptional<NumberFormat>,
+}
+impl Parsable for ObjectiveInfo {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, display_name) = TextComponent::parse(data)?;
+        let (data, render_type) = ObjectiveRenderType::parse(data)?;
+        let (data, number_format) = PrefixedOptional::parse(data)?;
+        Ok((
+            data,
+            ObjectiveInfo {
+                display_name,
+                render_type,
+                number_format,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.display_name.serialize_into(buf);
+        self.render_type.serialize_into(buf);
+        self.number_format.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.display_name.encoded_len()
+            + self.render_type.encoded_len()
+            + self.number_format.encoded_len()
+    }
+}
+
+crate::varint_enum! {
+    /// How scores are shown in the player list.
+    #[derive(Default)]
+    pub enum ObjectiveRenderType {
+        #[default]
+        Integer = 0,
+        Hearts = 1,
+    }
+}
+
+/// A change to a team, represented in the protocol as a byte
+/// of the method followed by its fields.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum TeamAction {
+    Create {
+        info: TeamInfo,
+        /// Usernames of players and UUIDs of other entities.
+        entities: Vec<String>,
+    },
+    #[default]
+    Remove,
+    Update(TeamInfo),
+    AddEntities(Vec<String>),
+    RemoveEntities(Vec<String>),
+}
+impl Parsable for TeamAction {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        use nom::combinator::map;
+
+        let (data, method) = i8::parse(data)?;
+        match method {
+            0 => {
+                let (data, info) = TeamInfo::parse(data)?;
+                let (data, entities) = Vec::parse(data)?;
+                Ok((data, TeamAction::Create { info, entities }))
+            }
+            1 => Ok((data, TeamAction::Remove)),
+            2 => map(TeamInfo::parse, TeamAction::Update)(data),
+            3 => map(Vec::parse, TeamAction::AddEntities)(data),
+            4 => map(Vec::parse, TeamAction::RemoveEntities)(data),
+            _ => nom::combinator::fail(data),
+        }
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        match self {
+            TeamAction::Create { info, entities } => {
+                0i8.serialize_into(buf);
+                info.serialize_into(buf);
+                entities.serialize_into(buf);
+            }
+            TeamAction::Remove => 1i8.serialize_into(buf),
+            TeamAction::Update(info) => {
+                2i8.serialize_into(buf);
+                info.serialize_into(buf);
+            }
+            TeamAction::AddEntities(entities) => {
+                3i8.serialize_into(buf);
+                entities.serialize_into(buf);
+            }
+            TeamAction::RemoveEntities(entities) => {
+                4i8.serialize_into(buf);
+                entities.serialize_into(buf);
+            }
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        1 + match self {
+            TeamAction::Create { info, entities } => info.encoded_len() + entities.encoded_len(),
+            TeamAction::Remove => 0,
+            TeamAction::Update(info) => info.encoded_len(),
+            TeamAction::AddEntities(entities) => entities.encoded_len(),
+            TeamAction::RemoveEntities(entities) => entities.encoded_len(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TeamInfo {
+    pub display_name: TextComponent,
+    /// 0x01: allow friendly fire, 0x02: see invisible teammates.
+    pub friendly_flags: i8,
+    pub name_tag_visibility: NameTagVisibility,
+    pub collision_rule: CollisionRule,
+    /// The formatting code used for members' names.
+    pub color: VarInt,
+    pub prefix: TextComponent,
+    pub suffix: TextComponent,
+}
+impl Parsable for TeamInfo {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, display_name) = TextComponent::parse(data)?;
+        let (data, friendly_flags) = i8::parse(data)?;
+        let (data, name_tag_visibility) = NameTagVisibility::parse(data)?;
+        let (data, collision_rule) = CollisionRule::parse(data)?;
+        let (data, color) = VarInt::parse(data)?;
+        let (data, prefix) = TextComponent::parse(data)?;
+        let (data, suffix) = TextComponent::parse(data)?;
+        Ok((
+            data,
+            TeamInfo {
+                display_name,
+                friendly_flags,
+                name_tag_visibility,
+                collision_rule,
+                color,
+                prefix,
+                suffix,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.display_name.serialize_into(buf);
+        self.friendly_flags.serialize_into(buf);
+        self.name_tag_visibility.serialize_into(buf);
+        self.collision_rule.serialize_into(buf);
+        self.color.serialize_into(buf);
+        self.prefix.serialize_into(buf);
+        self.suffix.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.display_name.encoded_len()
+            + self.friendly_flags.encoded_len()
+            + self.name_tag_visibility.encoded_len()
+            + self.collision_rule.encoded_len()
+            + self.color.encoded_len()
+            + self.prefix.encoded_len()
+            + self.suffix.encoded_len()
+    }
+}
+
+crate::varint_enum! {
+    #[derive(Default)]
+    pub enum NameTagVisibility {
+        #[default]
+        Always = 0,
+        Never = 1,
+        HideForOtherTeams = 2,
+        HideForOwnTeam = 3,
+    }
+}
+
+crate::varint_enum! {
+    /// Which entities members of a team push.
+    #[derive(Default)]
+    pub enum CollisionRule {
+        #[default]
+        Always = 0,
+        Never = 1,
+        PushOtherTeams = 2,
+        PushOwnTeam = 3,
+    }
+}
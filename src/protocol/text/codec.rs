@@ -0,0 +1,284 @@
+use super::{ClickEvent, HoverEvent, Style, TextColor, TextComponent, TextContent};
+use serde::{
+    de::{self, IgnoredAny, Visitor},
+    ser::{self, SerializeMap},
+    Deserialize, Serialize,
+};
+
+impl Serialize for TextComponent {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        match &self.content {
+            TextContent::Text(text) => map.serialize_entry("text", text)?,
+            TextContent::Translatable {
+                key,
+                fallback,
+                args,
+            } => {
+                map.serialize_entry("translate", key)?;
+                if let Some(fallback) = fallback {
+                    map.serialize_entry("fallback", fallback)?;
+                }
+                if !args.is_empty() {
+                    map.serialize_entry("with", args)?;
+                }
+            }
+            TextContent::Score { name, objective } => map.serialize_entry(
+                "score",
+                &Score {
+                    name: name.clone(),
+                    objective: objective.clone(),
+                },
+            )?,
+            TextContent::Selector {
+                selector,
+                separator,
+            } => {
+                map.serialize_entry("selector", selector)?;
+                if let Some(separator) = separator {
+                    map.serialize_entry("separator", separator)?;
+                }
+            }
+            TextContent::Keybind(keybind) => map.serialize_entry("keybind", keybind)?,
+        }
+        let style = &self.style;
+        if let Some(color) = &style.color {
+            map.serialize_entry("color", &color.to_string())?;
+        }
+        for (key, value) in [
+            ("bold", style.bold),
+            ("italic", style.italic),
+            ("underlined", style.underlined),
+            ("strikethrough", style.strikethrough),
+            ("obfuscated", style.obfuscated),
+        ] {
+            if let Some(value) = value {
+                map.serialize_entry(key, &value)?;
+            }
+        }
+        if let Some(font) = &style.font {
+            map.serialize_entry("font", font)?;
+        }
+        if let Some(insertion) = &style.insertion {
+            map.serialize_entry("insertion", insertion)?;
+        }
+        if let Some(click_event) = &style.click_event {
+            map.serialize_entry("click_event", click_event)?;
+        }
+        if let Some(hover_event) = &style.hover_event {
+            map.serialize_entry("hover_event", hover_event)?;
+        }
+        if let Some(shadow_color) = &style.shadow_color {
+            map.serialize_entry("shadow_color", shadow_color)?;
+        }
+        if !self.extra.is_empty() {
+            map.serialize_entry("extra", &self.extra)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for TextComponent {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(TextComponentVisitor)
+    }
+}
+struct TextComponentVisitor;
+impl<'de> Visitor<'de> for TextComponentVisitor {
+    type Value = TextComponent;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a text component")
+    }
+    // Primitives are shorthand for their text.
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<TextComponent, E> {
+        Ok(TextComponent::text(v.to_string()))
+    }
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<TextComponent, E> {
+        Ok(TextComponent::text(v.to_string()))
+    }
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<TextComponent, E> {
+        Ok(TextComponent::text(v.to_string()))
+    }
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<TextComponent, E> {
+        Ok(TextComponent::text(v.to_string()))
+    }
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<TextComponent, E> {
+        Ok(TextComponent::text(v))
+    }
+    fn visit_string<E: de::Error>(self, v: String) -> Result<TextComponent, E> {
+        Ok(TextComponent::text(v))
+    }
+    /// A list is shorthand for its first element with the rest appended.
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<TextComponent, A::Error> {
+        let mut first: TextComponent = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &"at least one component"))?;
+        while let Some(component) = seq.next_element()? {
+            first.extra.push(component);
+        }
+        Ok(first)
+    }
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<TextComponent, A::Error> {
+        let mut text = None;
+        let mut translate = None;
+        let mut fallback = None;
+        let mut with = vec![];
+        let mut score: Option<Score> = None;
+        let mut selector = None;
+        let mut separator = None;
+        let mut keybind = None;
+        // Vanilla wraps primitives in `{"": value}` when they share a list with compounds.
+        let mut wrapped = None;
+        let mut style = Style::default();
+        let mut extra = vec![];
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "text" => text = Some(map.next_value()?),
+                "translate" => translate = Some(map.next_value()?),
+                "fallback" => fallback = Some(map.next_value()?),
+                "with" => with = map.next_value()?,
+                "score" => score = Some(map.next_value()?),
+                "selector" => selector = Some(map.next_value()?),
+                "separator" => separator = Some(Box::new(map.next_value()?)),
+                "keybind" => keybind = Some(map.next_value()?),
+                "" => wrapped = Some(map.next_value::<TextComponent>()?),
+                "color" => {
+                    let color: String = map.next_value()?;
+                    style.color = Some(color.parse().map_err(|_| {
+                        de::Error::invalid_value(de::Unexpected::Str(&color), &"a text color")
+                    })?);
+                }
+                "bold" => style.bold = Some(map.next_value::<FlexibleBool>()?.0),
+                "italic" => style.italic = Some(map.next_value::<FlexibleBool>()?.0),
+                "underlined" => style.underlined = Some(map.next_value::<FlexibleBool>()?.0),
+                "strikethrough" => style.strikethrough = Some(map.next_value::<FlexibleBool>()?.0),
+                "obfuscated" => style.obfuscated = Some(map.next_value::<FlexibleBool>()?.0),
+                "font" => style.font = Some(map.next_value()?),
+                "insertion" => style.insertion = Some(map.next_value()?),
+                "click_event" => style.click_event = Some(map.next_value::<ClickEvent>()?),
+                "hover_event" => style.hover_event = Some(map.next_value::<HoverEvent>()?),
+                "shadow_color" => style.shadow_color = Some(map.next_value()?),
+                "extra" => extra = map.next_value()?,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        let content = if let Some(text) = text {
+            TextContent::Text(text)
+        } else if let Some(key) = translate {
+            TextContent::Translatable {
+                key,
+                fallback,
+                args: with,
+            }
+        } else if let Some(Score { name, objective }) = score {
+            TextContent::Score { name, objective }
+        } else if let Some(selector) = selector {
+            TextContent::Selector {
+                selector,
+                separator,
+            }
+        } else if let Some(keybind) = keybind {
+            TextContent::Keybind(keybind)
+        } else if let Some(wrapped) = wrapped {
+            return Ok(wrapped);
+        } else {
+            return Err(de::Error::custom("text component has no content"));
+        };
+        Ok(TextComponent {
+            content,
+            style,
+            extra,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Score {
+    name: String,
+    objective: String,
+}
+
+/// A boolean that can also be read from a number, since NBT stores booleans as bytes.
+struct FlexibleBool(bool);
+impl<'de> Deserialize<'de> for FlexibleBool {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FlexibleBoolVisitor;
+        impl Visitor<'_> for FlexibleBoolVisitor {
+            type Value = FlexibleBool;
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a boolean")
+            }
+            fn visit_bool<E: de::Error>(self, v: bool) -> Result<FlexibleBool, E> {
+                Ok(FlexibleBool(v))
+            }
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<FlexibleBool, E> {
+                Ok(FlexibleBool(v != 0))
+            }
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<FlexibleBool, E> {
+                Ok(FlexibleBool(v != 0))
+            }
+        }
+        deserializer.deserialize_any(FlexibleBoolVisitor)
+    }
+}
+
+impl Serialize for TextColor {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+pub(super) fn one() -> i32 {
+    1
+}
+
+/// Entity UUIDs are stored as four big-endian ints, but older data uses strings.
+pub(super) mod uuid_as_ints {
+    use crate::protocol::nbt::IntArray;
+    use serde::{
+        de::{self, Visitor},
+        Serialize,
+    };
+    use uuid::Uuid;
+
+    pub fn serialize<S: serde::Serializer>(uuid: &Uuid, serializer: S) -> Result<S::Ok, S::Error> {
+        let bits = uuid.as_u128();
+        let ints = (0..4)
+            .rev()
+            .map(|i| (bits >> (i * 32)) as u32 as i32)
+            .collect();
+        IntArray(ints).serialize(serializer)
+    }
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Uuid, D::Error> {
+        deserializer.deserialize_any(UuidVisitor)
+    }
+
+    struct UuidVisitor;
+    impl<'de> Visitor<'de> for UuidVisitor {
+        type Value = Uuid;
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a uuid as a string or four ints")
+        }
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Uuid, E> {
+            Uuid::parse_str(v).map_err(E::custom)
+        }
+        fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Uuid, A::Error> {
+            let mut bits = 0u128;
+            for i in 0..4 {
+                let int: i32 = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+                bits = (bits << 32) | int as u32 as u128;
+            }
+            if seq.next_element::<de::IgnoredAny>()?.is_some() {
+                return Err(de::Error::invalid_length(5, &self));
+            }
+            Ok(Uuid::from_u128(bits))
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use super::{NamedColor, Style, TextColor, TextComponent};
+
+/// The character that starts a legacy formatting code.
+const SECTION_SIGN: char = '§';
+
+impl TextComponent {
+    /// Convert text using legacy `§` formatting codes into a component.
+    ///
+    /// Colors reset any formatting before them, as they do in vanilla.
+    /// Unknown codes are dropped.
+    pub fn from_legacy(text: &str) -> TextComponent {
+        let mut segments: Vec<TextComponent> = vec![];
+        let mut style = Style::default();
+        let mut current = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c != SECTION_SIGN {
+                current.push(c);
+                continue;
+            }
+            let Some(code) = chars.next() else {
+                break;
+            };
+            if !current.is_empty() {
+                segments.push(TextComponent {
+                    style: style.clone(),
+                    ..TextComponent::text(std::mem::take(&mut current))
+                });
+            }
+            if let Some(color) = NamedColor::from_code(code) {
+                style = Style {
+                    color: Some(TextColor::Named(color)),
+                    ..Style::default()
+                };
+                continue;
+            }
+            match code.to_ascii_lowercase() {
+                'k' => style.obfuscated = Some(true),
+                'l' => style.bold = Some(true),
+                'm' => style.strikethrough = Some(true),
+                'n' => style.underlined = Some(true),
+                'o' => style.italic = Some(true),
+                'r' => style = Style::default(),
+                _ => {}
+            }
+        }
+        if !current.is_empty() {
+            segments.push(TextComponent {
+                style,
+                ..TextComponent::text(current)
+            });
+        }
+        match segments.len() {
+            0 => TextComponent::text(""),
+            1 => segments.remove(0),
+            _ => TextComponent {
+                extra: segments,
+                ..TextComponent::text("")
+            },
+        }
+    }
+    /// Convert the component into text using legacy `§` formatting codes.
+    ///
+    /// RGB colors are approximated with the nearest named color,
+    /// and anything legacy codes can't express (such as click events) is lost.
+    pub fn to_legacy(&self) -> String {
+        let mut output = String::new();
+        let mut last = None;
+        self.write_legacy(&Style::default(), &mut last, &mut output);
+        output
+    }
+    fn write_legacy(&self, parent: &Style, last: &mut Option<String>, output: &mut String) {
+        let style = self.style.inherit(parent);
+        let mut content = String::new();
+        self.write_content(&mut content);
+        if !content.is_empty() {
+            let codes = legacy_codes(&style);
+            if last.as_ref() != Some(&codes) {
+                // Codes other than colors only add formatting, so start from a reset.
+                if last.is_some() && style.color.is_none() {
+                    output.push(SECTION_SIGN);
+                    output.push('r');
+                }
+                output.push_str(&codes);
+                *last = Some(codes);
+            }
+            output.push_str(&content);
+        }
+        for component in &self.extra {
+            component.write_legacy(&style, last, output);
+        }
+    }
+}
+
+/// The codes that switch to a style, assuming formatting has been reset.
+fn legacy_codes(style: &Style) -> String {
+    let mut codes = String::new();
+    if let Some(color) = style.color {
+        let color = match color {
+            TextColor::Named(color) => color,
+            TextColor::Rgb(rgb) => NamedColor::nearest(rgb),
+        };
+        codes.push(SECTION_SIGN);
+        codes.push(color.code());
+    }
+    for (enabled, code) in [
+        (style.obfuscated, 'k'),
+        (style.bold, 'l'),
+        (style.strikethrough, 'm'),
+        (style.underlined, 'n'),
+        (style.italic, 'o'),
+    ] {
+        if enabled == Some(true) {
+            codes.push(SECTION_SIGN);
+            codes.push(code);
+        }
+    }
+    codes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_legacy_works() {
+        assert_eq!(
+            TextComponent::from_legacy("plain"),
+            TextComponent::text("plain")
+        );
+        assert_eq!(
+            TextComponent::from_legacy("§6Gold §lbold§r plain§z"),
+            TextComponent::text("")
+                .append(TextComponent::text("Gold ").color(NamedColor::Gold))
+                .append(
+                    TextComponent::text("bold")
+                        .color(NamedColor::Gold)
+                        .bold(true)
+                )
+                .append(TextComponent::text(" plain"))
+        );
+    }
+    #[test]
+    fn to_legacy_works() {
+        let component = TextComponent::text("Gold ")
+            .color(NamedColor::Gold)
+            .append(TextComponent::text("bold").bold(true))
+            .append(TextComponent::text(" red").color(TextColor::Rgb(0xfe5050)));
+        assert_eq!(component.to_legacy(), "§6Gold §6§lbold§c red");
+        assert_eq!(
+            TextComponent::from_legacy(&component.to_legacy()).to_legacy(),
+            component.to_legacy()
+        );
+        assert_eq!(
+            TextComponent::text("a")
+                .bold(true)
+                .append(TextComponent::text("b").bold(false))
+                .to_legacy(),
+            "§la§rb"
+        );
+    }
+}
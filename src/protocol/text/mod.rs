@@ -0,0 +1,669 @@
+/// Serde implementations shared by the JSON and NBT encodings.
+mod codec;
+/// Conversion to and from legacy `§` formatting codes.
+mod legacy;
+
+use crate::protocol::{
+    nbt::{self, Tag},
+    parsing::{BufMut, IResult, Parsable},
+};
+use uuid::Uuid;
+
+/// A piece of formatted text, as shown in chat, titles, item names and so on.
+///
+/// Its `Parsable` impl uses the NBT encoding that the configuration and play states use.
+/// See `JsonTextComponent` for the JSON encoding used in the login and status states.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextComponent {
+    pub content: TextContent,
+    pub style: Style,
+    /// Components shown after this one, which inherit its style.
+    pub extra: Vec<TextComponent>,
+}
+impl Default for TextComponent {
+    fn default() -> Self {
+        TextComponent::text("")
+    }
+}
+impl TextComponent {
+    fn new(content: TextContent) -> TextComponent {
+        TextComponent {
+            content,
+            style: Style::default(),
+            extra: vec![],
+        }
+    }
+    /// Literal text.
+    pub fn text(text: impl Into<String>) -> TextComponent {
+        TextComponent::new(TextContent::Text(text.into()))
+    }
+    /// Text translated by the client, with `args` substituted into it.
+    pub fn translatable(key: impl Into<String>, args: Vec<TextComponent>) -> TextComponent {
+        TextComponent::new(TextContent::Translatable {
+            key: key.into(),
+            fallback: None,
+            args,
+        })
+    }
+    /// The score of `name` for `objective`.
+    pub fn score(name: impl Into<String>, objective: impl Into<String>) -> TextComponent {
+        TextComponent::new(TextContent::Score {
+            name: name.into(),
+            objective: objective.into(),
+        })
+    }
+    /// The names of the entities matching an entity selector.
+    pub fn selector(selector: impl Into<String>) -> TextComponent {
+        TextComponent::new(TextContent::Selector {
+            selector: selector.into(),
+            separator: None,
+        })
+    }
+    /// The key the client has bound to an action, such as `key.jump`.
+    pub fn keybind(keybind: impl Into<String>) -> TextComponent {
+        TextComponent::new(TextContent::Keybind(keybind.into()))
+    }
+
+    /// Add a component after this one.
+    pub fn append(mut self, component: impl Into<TextComponent>) -> Self {
+        self.extra.push(component.into());
+        self
+    }
+    /// Set the text shown for a translation key that the client doesn't know.
+    ///
+    /// Has no effect on other kinds of content.
+    pub fn fallback(mut self, fallback: impl Into<String>) -> Self {
+        if let TextContent::Translatable { fallback: f, .. } = &mut self.content {
+            *f = Some(fallback.into());
+        }
+        self
+    }
+    /// Set the text shown between selected entities.
+    ///
+    /// Has no effect on other kinds of content.
+    pub fn separator(mut self, separator: impl Into<TextComponent>) -> Self {
+        if let TextContent::Selector { separator: s, .. } = &mut self.content {
+            *s = Some(Box::new(separator.into()));
+        }
+        self
+    }
+    pub fn color(mut self, color: impl Into<TextColor>) -> Self {
+        self.style.color = Some(color.into());
+        self
+    }
+    pub fn bold(mut self, bold: bool) -> Self {
+        self.style.bold = Some(bold);
+        self
+    }
+    pub fn italic(mut self, italic: bool) -> Self {
+        self.style.italic = Some(italic);
+        self
+    }
+    pub fn underlined(mut self, underlined: bool) -> Self {
+        self.style.underlined = Some(underlined);
+        self
+    }
+    pub fn strikethrough(mut self, strikethrough: bool) -> Self {
+        self.style.strikethrough = Some(strikethrough);
+        self
+    }
+    pub fn obfuscated(mut self, obfuscated: bool) -> Self {
+        self.style.obfuscated = Some(obfuscated);
+        self
+    }
+    pub fn font(mut self, font: impl Into<String>) -> Self {
+        self.style.font = Some(font.into());
+        self
+    }
+    /// Set the text inserted into the chat box when the component is shift-clicked.
+    pub fn insertion(mut self, insertion: impl Into<String>) -> Self {
+        self.style.insertion = Some(insertion.into());
+        self
+    }
+    pub fn click_event(mut self, click_event: ClickEvent) -> Self {
+        self.style.click_event = Some(click_event);
+        self
+    }
+    pub fn hover_event(mut self, hover_event: HoverEvent) -> Self {
+        self.style.hover_event = Some(hover_event);
+        self
+    }
+    /// Set the color of the text's shadow as ARGB.
+    pub fn shadow_color(mut self, shadow_color: i32) -> Self {
+        self.style.shadow_color = Some(shadow_color);
+        self
+    }
+
+    /// Render the component as unformatted text.
+    ///
+    /// Translations use their fallback (or key) as the format string,
+    /// and scores render as nothing since they are resolved by the server.
+    pub fn to_plain_text(&self) -> String {
+        let mut output = String::new();
+        self.write_plain_text(&mut output);
+        output
+    }
+    fn write_plain_text(&self, output: &mut String) {
+        self.write_content(output);
+        for component in &self.extra {
+            component.write_plain_text(output);
+        }
+    }
+    /// Write this component's own text, without its children.
+    fn write_content(&self, output: &mut String) {
+        match &self.content {
+            TextContent::Text(text) => output.push_str(text),
+            TextContent::Translatable {
+                key,
+                fallback,
+                args,
+            } => format_translation(fallback.as_ref().unwrap_or(key), args, output),
+            TextContent::Score { .. } => {}
+            TextContent::Selector { selector, .. } => output.push_str(selector),
+            TextContent::Keybind(keybind) => output.push_str(keybind),
+        }
+    }
+    /// Whether the component is plain text with no style or children.
+    fn is_plain_text(&self) -> bool {
+        matches!(self.content, TextContent::Text(_))
+            && self.style == Style::default()
+            && self.extra.is_empty()
+    }
+}
+impl From<&str> for TextComponent {
+    fn from(value: &str) -> Self {
+        TextComponent::text(value)
+    }
+}
+impl From<String> for TextComponent {
+    fn from(value: String) -> Self {
+        TextComponent::text(value)
+    }
+}
+impl Parsable for TextComponent {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        nom::combinator::map_res(Tag::parse, nbt::from_tag)(data)
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        // Vanilla sends unstyled text as a bare string.
+        match self.bare_text() {
+            Some(text) => {
+                // The type id of a string tag.
+                buf.put_u8(8);
+                nbt::serialize_string(text, buf);
+            }
+            None => self.to_tag().serialize_into(buf),
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        match self.bare_text() {
+            Some(text) => 1 + nbt::string_len(text),
+            None => self.to_tag().encoded_len(),
+        }
+    }
+}
+impl TextComponent {
+    /// The text of an unstyled text component, which is sent as a bare string.
+    fn bare_text(&self) -> Option<&str> {
+        match &self.content {
+            TextContent::Text(text) if self.is_plain_text() => Some(text),
+            _ => None,
+        }
+    }
+    fn to_tag(&self) -> Tag {
+        nbt::to_tag(self).expect("text components are valid nbt")
+    }
+}
+
+/// Substitute `%s` and `%1$s` style arguments into a translation.
+fn format_translation(format: &str, args: &[TextComponent], output: &mut String) {
+    let mut next_arg = 0;
+    let mut rest = format;
+    while let Some(i) = rest.find('%') {
+        output.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        if let Some(r) = rest.strip_prefix('%') {
+            output.push('%');
+            rest = r;
+            continue;
+        }
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        let (index, r) = match rest[digits..].strip_prefix("$s") {
+            Some(r) if digits > 0 => (rest[..digits].parse::<usize>().ok().map(|n| n - 1), r),
+            _ => match rest.strip_prefix('s') {
+                Some(r) => {
+                    next_arg += 1;
+                    (Some(next_arg - 1), r)
+                }
+                None => {
+                    output.push('%');
+                    continue;
+                }
+            },
+        };
+        if let Some(arg) = index.and_then(|i| args.get(i)) {
+            arg.write_plain_text(output);
+        }
+        rest = r;
+    }
+    output.push_str(rest);
+}
+
+/// A text component sent as a JSON string, as in the login and status states.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct JsonTextComponent(pub TextComponent);
+impl std::ops::Deref for JsonTextComponent {
+    type Target = TextComponent;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl From<TextComponent> for JsonTextComponent {
+    fn from(value: TextComponent) -> Self {
+        JsonTextComponent(value)
+    }
+}
+impl From<JsonTextComponent> for TextComponent {
+    fn from(value: JsonTextComponent) -> Self {
+        value.0
+    }
+}
+impl Parsable for JsonTextComponent {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        nom::combinator::map_res(String::parse, |json: String| {
+            serde_json::from_str(&json).map(JsonTextComponent)
+        })(data)
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.to_json().serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.to_json().encoded_len()
+    }
+}
+impl JsonTextComponent {
+    fn to_json(&self) -> String {
+        serde_json::to_string(&self.0).expect("text components are valid json")
+    }
+}
+
+/// What a text component displays.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextContent {
+    Text(String),
+    Translatable {
+        key: String,
+        /// Shown if the client doesn't know the key.
+        fallback: Option<String>,
+        args: Vec<TextComponent>,
+    },
+    Score {
+        /// A player name or entity selector.
+        name: String,
+        objective: String,
+    },
+    Selector {
+        selector: String,
+        /// Shown between entity names, defaults to `", "`.
+        separator: Option<Box<TextComponent>>,
+    },
+    Keybind(String),
+}
+
+/// How a text component is formatted.
+///
+/// Fields that are `None` are inherited from the parent component.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Style {
+    pub color: Option<TextColor>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub underlined: Option<bool>,
+    pub strikethrough: Option<bool>,
+    pub obfuscated: Option<bool>,
+    pub font: Option<String>,
+    pub insertion: Option<String>,
+    pub click_event: Option<ClickEvent>,
+    pub hover_event: Option<HoverEvent>,
+    /// ARGB color of the text's shadow.
+    pub shadow_color: Option<i32>,
+}
+impl Style {
+    /// Fill in any unset fields from the parent's style.
+    pub fn inherit(&self, parent: &Style) -> Style {
+        Style {
+            color: self.color.or(parent.color),
+            bold: self.bold.or(parent.bold),
+            italic: self.italic.or(parent.italic),
+            underlined: self.underlined.or(parent.underlined),
+            strikethrough: self.strikethrough.or(parent.strikethrough),
+            obfuscated: self.obfuscated.or(parent.obfuscated),
+            font: self.font.clone().or_else(|| parent.font.clone()),
+            insertion: self.insertion.clone().or_else(|| parent.insertion.clone()),
+            click_event: self
+                .click_event
+                .clone()
+                .or_else(|| parent.click_event.clone()),
+            hover_event: self
+                .hover_event
+                .clone()
+                .or_else(|| parent.hover_event.clone()),
+            shadow_color: self.shadow_color.or(parent.shadow_color),
+        }
+    }
+}
+
+/// The color of a text component.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextColor {
+    Named(NamedColor),
+    /// A 24-bit RGB color.
+    Rgb(u32),
+}
+impl TextColor {
+    pub fn rgb(&self) -> u32 {
+        match self {
+            TextColor::Named(color) => color.rgb(),
+            TextColor::Rgb(rgb) => *rgb,
+        }
+    }
+}
+impl From<NamedColor> for TextColor {
+    fn from(value: NamedColor) -> Self {
+        TextColor::Named(value)
+    }
+}
+impl std::fmt::Display for TextColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextColor::Named(color) => f.write_str(color.name()),
+            TextColor::Rgb(rgb) => write!(f, "#{rgb:06X}"),
+        }
+    }
+}
+impl std::str::FromStr for TextColor {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(hex) = s.strip_prefix('#') {
+            match u32::from_str_radix(hex, 16) {
+                Ok(rgb) if hex.len() == 6 => Ok(TextColor::Rgb(rgb)),
+                _ => Err(()),
+            }
+        } else {
+            NamedColor::ALL
+                .into_iter()
+                .find(|color| color.name() == s)
+                .map(TextColor::Named)
+                .ok_or(())
+        }
+    }
+}
+
+/// The 16 colors that have names and legacy formatting codes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NamedColor {
+    Black,
+    DarkBlue,
+    DarkGreen,
+    DarkAqua,
+    DarkRed,
+    DarkPurple,
+    Gold,
+    Gray,
+    DarkGray,
+    Blue,
+    Green,
+    Aqua,
+    Red,
+    LightPurple,
+    Yellow,
+    White,
+}
+impl NamedColor {
+    /// Every named color, in order of their legacy codes.
+    pub const ALL: [NamedColor; 16] = [
+        NamedColor::Black,
+        NamedColor::DarkBlue,
+        NamedColor::DarkGreen,
+        NamedColor::DarkAqua,
+        NamedColor::DarkRed,
+        NamedColor::DarkPurple,
+        NamedColor::Gold,
+        NamedColor::Gray,
+        NamedColor::DarkGray,
+        NamedColor::Blue,
+        NamedColor::Green,
+        NamedColor::Aqua,
+        NamedColor::Red,
+        NamedColor::LightPurple,
+        NamedColor::Yellow,
+        NamedColor::White,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NamedColor::Black => "black",
+            NamedColor::DarkBlue => "dark_blue",
+            NamedColor::DarkGreen => "dark_green",
+            NamedColor::DarkAqua => "dark_aqua",
+            NamedColor::DarkRed => "dark_red",
+            NamedColor::DarkPurple => "dark_purple",
+            NamedColor::Gold => "gold",
+            NamedColor::Gray => "gray",
+            NamedColor::DarkGray => "dark_gray",
+            NamedColor::Blue => "blue",
+            NamedColor::Green => "green",
+            NamedColor::Aqua => "aqua",
+            NamedColor::Red => "red",
+            NamedColor::LightPurple => "light_purple",
+            NamedColor::Yellow => "yellow",
+            NamedColor::White => "white",
+        }
+    }
+    /// The character after `§` that selects this color.
+    pub fn code(&self) -> char {
+        char::from_digit(*self as u32, 16).expect("there are 16 colors")
+    }
+    pub fn rgb(&self) -> u32 {
+        match self {
+            NamedColor::Black => 0x000000,
+            NamedColor::DarkBlue => 0x0000AA,
+            NamedColor::DarkGreen => 0x00AA00,
+            NamedColor::DarkAqua => 0x00AAAA,
+            NamedColor::DarkRed => 0xAA0000,
+            NamedColor::DarkPurple => 0xAA00AA,
+            NamedColor::Gold => 0xFFAA00,
+            NamedColor::Gray => 0xAAAAAA,
+            NamedColor::DarkGray => 0x555555,
+            NamedColor::Blue => 0x5555FF,
+            NamedColor::Green => 0x55FF55,
+            NamedColor::Aqua => 0x55FFFF,
+            NamedColor::Red => 0xFF5555,
+            NamedColor::LightPurple => 0xFF55FF,
+            NamedColor::Yellow => 0xFFFF55,
+            NamedColor::White => 0xFFFFFF,
+        }
+    }
+    pub fn from_code(code: char) -> Option<NamedColor> {
+        let index = code.to_ascii_lowercase().to_digit(16)?;
+        Some(NamedColor::ALL[index as usize])
+    }
+    /// The named color closest to an RGB color.
+    pub fn nearest(rgb: u32) -> NamedColor {
+        let channels = |rgb: u32| [(rgb >> 16) & 0xff, (rgb >> 8) & 0xff, rgb & 0xff];
+        NamedColor::ALL
+            .into_iter()
+            .min_by_key(|color| {
+                channels(color.rgb())
+                    .into_iter()
+                    .zip(channels(rgb))
+                    .map(|(a, b)| a.abs_diff(b).pow(2))
+                    .sum::<u32>()
+            })
+            .expect("there are 16 colors")
+    }
+}
+
+/// What happens when a text component is clicked.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ClickEvent {
+    OpenUrl {
+        url: String,
+    },
+    OpenFile {
+        path: String,
+    },
+    RunCommand {
+        command: String,
+    },
+    SuggestCommand {
+        command: String,
+    },
+    /// Only works in books.
+    ChangePage {
+        page: i32,
+    },
+    CopyToClipboard {
+        value: String,
+    },
+}
+
+/// What is shown when a text component is hovered over.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum HoverEvent {
+    ShowText {
+        value: Box<TextComponent>,
+    },
+    ShowItem {
+        id: String,
+        #[serde(default = "codec::one")]
+        count: i32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        components: Option<Tag>,
+    },
+    ShowEntity {
+        /// The entity type.
+        id: String,
+        #[serde(with = "codec::uuid_as_ints")]
+        uuid: Uuid,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<Box<TextComponent>>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_example() -> TextComponent {
+        TextComponent::text("Hello, ")
+            .color(NamedColor::Gold)
+            .append(
+                TextComponent::selector("@p")
+                    .bold(true)
+                    .click_event(ClickEvent::SuggestCommand {
+                        command: "/msg ".to_owned(),
+                    })
+                    .hover_event(HoverEvent::ShowEntity {
+                        id: "minecraft:player".to_owned(),
+                        uuid: Uuid::from_u128(0x0123_4567_89ab_cdef_0123_4567_89ab_cdef),
+                        name: Some(Box::new("Steve".into())),
+                    }),
+            )
+            .append(
+                TextComponent::translatable("chat.type.text", vec!["a".into(), "b".into()])
+                    .color(TextColor::Rgb(0x123456)),
+            )
+    }
+
+    #[test]
+    fn json_encoding_works() {
+        let json = serde_json::to_value(get_example()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "text": "Hello, ",
+                "color": "gold",
+                "extra": [
+                    {
+                        "selector": "@p",
+                        "bold": true,
+                        "click_event": { "action": "suggest_command", "command": "/msg " },
+                        "hover_event": {
+                            "action": "show_entity",
+                            "id": "minecraft:player",
+                            "uuid": [0x01234567, -0x76543211, 0x01234567, -0x76543211],
+                            "name": { "text": "Steve" }
+                        }
+                    },
+                    {
+                        "translate": "chat.type.text",
+                        "with": [{ "text": "a" }, { "text": "b" }],
+                        "color": "#123456"
+                    }
+                ]
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<TextComponent>(json).unwrap(),
+            get_example()
+        );
+    }
+    #[test]
+    fn json_shorthands_work() {
+        let parse = |json| serde_json::from_value::<TextComponent>(json).unwrap();
+        assert_eq!(parse(serde_json::json!("hi")), TextComponent::text("hi"));
+        assert_eq!(
+            parse(serde_json::json!(["a", { "text": "b", "bold": 1 }])),
+            TextComponent::text("a").append(TextComponent::text("b").bold(true))
+        );
+        assert_eq!(
+            parse(serde_json::json!({ "translate": "x", "with": [3] })),
+            TextComponent::translatable("x", vec!["3".into()])
+        );
+    }
+    #[test]
+    fn nbt_encoding_works() {
+        let example = get_example();
+        let bytes = example.serialize();
+        assert_eq!(example.encoded_len(), bytes.len());
+        assert_eq!(TextComponent::parse(&bytes).unwrap(), (&[][..], example));
+
+        let plain = TextComponent::text("plain");
+        assert_eq!(plain.serialize(), Tag::from("plain").serialize());
+        assert_eq!(plain.encoded_len(), plain.serialize().len());
+        assert_eq!(
+            TextComponent::parse(&plain.serialize()).unwrap(),
+            (&[][..], plain)
+        );
+    }
+    #[test]
+    fn json_packet_encoding_works() {
+        let component = JsonTextComponent(get_example());
+        let bytes = component.serialize();
+        assert_eq!(
+            JsonTextComponent::parse(&bytes).unwrap(),
+            (&[][..], component)
+        );
+    }
+    #[test]
+    fn plain_text_works() {
+        assert_eq!(get_example().to_plain_text(), "Hello, @pchat.type.text");
+        let greeting = TextComponent::translatable("greeting", vec!["A".into(), "B".into()])
+            .fallback("%2$s greets %1$s (100%%), %s");
+        assert_eq!(greeting.to_plain_text(), "B greets A (100%), A");
+    }
+    #[test]
+    fn text_colors_work() {
+        assert_eq!("red".parse(), Ok(TextColor::Named(NamedColor::Red)));
+        assert_eq!("#00ff7F".parse(), Ok(TextColor::Rgb(0x00ff7f)));
+        assert_eq!("#fff".parse::<TextColor>(), Err(()));
+        assert_eq!(NamedColor::Red.code(), 'c');
+        assert_eq!(NamedColor::from_code('C'), Some(NamedColor::Red));
+        assert_eq!(NamedColor::nearest(0xfe5050), NamedColor::Red);
+    }
+}
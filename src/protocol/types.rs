@@ -1,16 +1,21 @@
-use crate::protocol::parsing::{IResult, Parsable};
+use crate::protocol::{
+    nbt::Tag,
+    parsing::{BufMut, IResult, Parsable},
+    text::TextComponent,
+};
 pub use uuid::Uuid;
 
 // /// Alias for a u128.
 // pub type Uuid = u128;
-pub use crate::protocol::parsing::VarInt;
+pub use crate::protocol::parsing::{
+    Angle, BitSet, BoundedString, FixedBitSet, IdOr, IdSet, Identifier, LpVec3, OptionalVarInt,
+    PrefixedArray, PrefixedOptional, VarInt, VarLong,
+};
 /// Alias for a `serde_json::Value`.
 pub type Json = serde_json::Value;
-/// Alias for a `Json`.
-pub type Chat = Json;
 
 /// An implementation of the protocol's [Position](https://wiki.vg/Protocol#Position) type.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub struct Position {
     pub x: i32,
     pub y: i32,
@@ -37,19 +42,22 @@ impl Parsable for Position {
 
         Ok((data, Position::new(x as i32, y as i32, z as i32)))
     }
-    #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
+    fn serialize_into(&self, buf: &mut impl BufMut) {
         let i: i64 = ((self.x as i64 & 0x3FF_FFFF) << 38)
             | ((self.z as i64 & 0x3FF_FFFF) << 12)
             | (self.y as i64 & 0xFFF);
-        i.serialize()
+        i.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        8
     }
 }
 
 /// An enum of the possible difficulties in Minecraft.
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
 pub enum Difficulty {
     Peaceful = 0,
+    #[default]
     Easy = 1,
     Normal = 2,
     Hard = 3,
@@ -71,9 +79,1161 @@ impl Parsable for Difficulty {
     fn parse(data: &[u8]) -> IResult<&[u8], Self> {
         nom::combinator::map_res(u8::parse, Difficulty::try_from)(data)
     }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        buf.put_u8(*self as u8);
+    }
+    fn encoded_len(&self) -> usize {
+        1
+    }
+}
+
+/// What the player did in the advancements screen.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum SeenAdvancementsAction {
+    OpenedTab {
+        tab_id: String,
+    },
+    #[default]
+    ClosedScreen,
+}
+impl Parsable for SeenAdvancementsAction {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, action) = VarInt::parse(data)?;
+        match *action {
+            0 => {
+                let (data, tab_id) = String::parse(data)?;
+                Ok((data, SeenAdvancementsAction::OpenedTab { tab_id }))
+            }
+            1 => Ok((data, SeenAdvancementsAction::ClosedScreen)),
+            _ => nom::combinator::fail(data),
+        }
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        match self {
+            SeenAdvancementsAction::OpenedTab { tab_id } => {
+                VarInt::from(0).serialize_into(buf);
+                tab_id.serialize_into(buf);
+            }
+            SeenAdvancementsAction::ClosedScreen => VarInt::from(1).serialize_into(buf),
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        match self {
+            SeenAdvancementsAction::OpenedTab { tab_id } => 1 + tab_id.encoded_len(),
+            SeenAdvancementsAction::ClosedScreen => 1,
+        }
+    }
+}
+
+/// Sky and block light for a column of chunk sections.
+///
+/// Each mask has one bit per section, including the sections above and below the world.
+/// Each light array holds 2048 bytes of half-byte light levels.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LightData {
+    pub sky_light_mask: BitSet,
+    pub block_light_mask: BitSet,
+    pub empty_sky_light_mask: BitSet,
+    pub empty_block_light_mask: BitSet,
+    pub sky_light_arrays: Vec<Vec<u8>>,
+    pub block_light_arrays: Vec<Vec<u8>>,
+}
+impl Parsable for LightData {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, sky_light_mask) = BitSet::parse(data)?;
+        let (data, block_light_mask) = BitSet::parse(data)?;
+        let (data, empty_sky_light_mask) = BitSet::parse(data)?;
+        let (data, empty_block_light_mask) = BitSet::parse(data)?;
+        let (data, sky_light_arrays) = Vec::parse(data)?;
+        let (data, block_light_arrays) = Vec::parse(data)?;
+        Ok((
+            data,
+            LightData {
+                sky_light_mask,
+                block_light_mask,
+                empty_sky_light_mask,
+                empty_block_light_mask,
+                sky_light_arrays,
+                block_light_arrays,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.sky_light_mask.serialize_into(buf);
+        self.block_light_mask.serialize_into(buf);
+        self.empty_sky_light_mask.serialize_into(buf);
+        self.empty_block_light_mask.serialize_into(buf);
+        self.sky_light_arrays.serialize_into(buf);
+        self.block_light_arrays.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.sky_light_mask.encoded_len()
+            + self.block_light_mask.encoded_len()
+            + self.empty_sky_light_mask.encoded_len()
+            + self.empty_block_light_mask.encoded_len()
+            + self.sky_light_arrays.encoded_len()
+            + self.block_light_arrays.encoded_len()
+    }
+}
+
+/// A block entity in a chunk, sent alongside the chunk's blocks.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ChunkBlockEntity {
+    /// The block's x and z coordinates within the chunk, packed as `x << 4 | z`.
+    pub packed_xz: u8,
+    pub y: i16,
+    pub block_entity_type: VarInt,
+    pub data: Tag,
+}
+impl Parsable for ChunkBlockEntity {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, packed_xz) = u8::parse(data)?;
+        let (data, y) = i16::parse(data)?;
+        let (data, block_entity_type) = VarInt::parse(data)?;
+        let (data, tag) = Tag::parse(data)?;
+        Ok((
+            data,
+            ChunkBlockEntity {
+                packed_xz,
+                y,
+                block_entity_type,
+                data: tag,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.packed_xz.serialize_into(buf);
+        self.y.serialize_into(buf);
+        self.block_entity_type.serialize_into(buf);
+        self.data.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.packed_xz.encoded_len()
+            + self.y.encoded_len()
+            + self.block_entity_type.encoded_len()
+            + self.data.encoded_len()
+    }
+}
+
+/// A data pack that both sides of the connection may already have.
+///
+/// Registry entries from a pack the client knows are sent without their data.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct KnownPack {
+    pub namespace: String,
+    pub id: String,
+    pub version: String,
+}
+impl KnownPack {
+    /// The pack holding the vanilla registry contents for `version`.
+    pub fn core(version: crate::protocol::ProtocolVersion) -> KnownPack {
+        KnownPack {
+            namespace: "minecraft".to_owned(),
+            id: "core".to_owned(),
+            version: version.game_version().to_owned(),
+        }
+    }
+}
+impl Parsable for KnownPack {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, namespace) = String::parse(data)?;
+        let (data, id) = String::parse(data)?;
+        let (data, version) = String::parse(data)?;
+        Ok((
+            data,
+            KnownPack {
+                namespace,
+                id,
+                version,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.namespace.serialize_into(buf);
+        self.id.serialize_into(buf);
+        self.version.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.namespace.encoded_len() + self.id.encoded_len() + self.version.encoded_len()
+    }
+}
+
+/// A player's identity and properties, such as their skin and cape textures.
+#[derive(Debug, Clone, PartialEq, Default, serde::Deserialize)]
+pub struct GameProfile {
+    #[serde(rename = "id")]
+    pub uuid: Uuid,
+    #[serde(rename = "name")]
+    pub username: String,
+    #[serde(default)]
+    pub properties: Vec<ProfileProperty>,
+}
+impl GameProfile {
+    /// Create the profile for a player in offline mode, which has no properties.
+    pub fn offline(username: String, uuid: Option<Uuid>) -> GameProfile {
+        GameProfile {
+            uuid: uuid.unwrap_or_else(|| offline_uuid(&username)),
+            username,
+            properties: vec![],
+        }
+    }
+}
+impl Parsable for GameProfile {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, uuid) = Uuid::parse(data)?;
+        let (data, username) = String::parse(data)?;
+        let (data, properties) = Vec::parse(data)?;
+        Ok((
+            data,
+            GameProfile {
+                uuid,
+                username,
+                properties,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.uuid.serialize_into(buf);
+        self.username.serialize_into(buf);
+        self.properties.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.uuid.encoded_len() + self.username.encoded_len() + self.properties.encoded_len()
+    }
+}
+
+/// A property of a `GameProfile`, signed by the session server in online mode.
+#[derive(Debug, Clone, PartialEq, Eq, Default, serde::Deserialize)]
+pub struct ProfileProperty {
+    pub name: String,
+    pub value: String,
+    pub signature: Option<String>,
+}
+impl Parsable for ProfileProperty {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, name) = String::parse(data)?;
+        let (data, value) = String::parse(data)?;
+        let (data, signature) = String::parse_optional(data)?;
+        Ok((
+            data,
+            ProfileProperty {
+                name,
+                value,
+                signature,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.name.serialize_into(buf);
+        self.value.serialize_into(buf);
+        self.signature.is_some().serialize_into(buf);
+        self.signature.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.name.encoded_len() + self.value.encoded_len() + 1 + self.signature.encoded_len()
+    }
+}
+
+/// Derive a player's UUID from their username, like vanilla servers do in offline mode.
+///
+/// This is the version 3 UUID of `"OfflinePlayer:" + username` with no namespace,
+/// equivalent to Java's `UUID.nameUUIDFromBytes`.
+pub fn offline_uuid(username: &str) -> Uuid {
+    use md5::{Digest, Md5};
+
+    let hash = Md5::digest(format!("OfflinePlayer:{username}").as_bytes());
+    uuid::Builder::from_md5_bytes(hash.into()).into_uuid()
+}
+
+/// The players in a `PlayerInfoUpdate`, which all update the same fields.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PlayerInfoUpdates {
+    /// A bit set of the fields present in each entry.
+    pub actions: u8,
+    pub players: Vec<PlayerInfo>,
+}
+impl PlayerInfoUpdates {
+    pub const ADD_PLAYER: u8 = 0x01;
+    pub const INITIALIZE_CHAT: u8 = 0x02;
+    pub const UPDATE_GAME_MODE: u8 = 0x04;
+    pub const UPDATE_LISTED: u8 = 0x08;
+    pub const UPDATE_LATENCY: u8 = 0x10;
+    pub const UPDATE_DISPLAY_NAME: u8 = 0x20;
+    pub const UPDATE_LIST_PRIORITY: u8 = 0x40;
+    pub const UPDATE_HAT: u8 = 0x80;
+
+    fn parse_player(actions: u8, data: &[u8]) -> IResult<&[u8], PlayerInfo> {
+        let has = |action: u8| actions & action != 0;
+        let (mut data, uuid) = Uuid::parse(data)?;
+        let mut player = PlayerInfo {
+            profile: GameProfile {
+                uuid,
+                ..GameProfile::default()
+            },
+            ..PlayerInfo::default()
+        };
+        if has(Self::ADD_PLAYER) {
+            let (d, username) = String::parse(data)?;
+            let (d, properties) = Vec::parse(d)?;
+            player.profile.username = username;
+            player.profile.properties = properties;
+            data = d;
+        }
+        if has(Self::INITIALIZE_CHAT) {
+            let (d, chat_session) = PlayerChatSession::parse_optional(data)?;
+            player.chat_session = chat_session;
+            data = d;
+        }
+        if has(Self::UPDATE_GAME_MODE) {
+            let (d, game_mode) = VarInt::parse(data)?;
+            player.game_mode = game_mode;
+            data = d;
+        }
+        if has(Self::UPDATE_LISTED) {
+            let (d, listed) = bool::parse(data)?;
+            player.listed = listed;
+            data = d;
+        }
+        if has(Self::UPDATE_LATENCY) {
+            let (d, latency) = VarInt::parse(data)?;
+            player.latency = latency;
+            data = d;
+        }
+        if has(Self::UPDATE_DISPLAY_NAME) {
+            let (d, display_name) = TextComponent::parse_optional(data)?;
+            player.display_name = display_name;
+            data = d;
+        }
+        if has(Self::UPDATE_LIST_PRIORITY) {
+            let (d, list_priority) = VarInt::parse(data)?;
+            player.list_priority = list_priority;
+            data = d;
+        }
+        if has(Self::UPDATE_HAT) {
+            let (d, show_hat) = bool::parse(data)?;
+            player.show_hat = show_hat;
+            data = d;
+        }
+        Ok((data, player))
+    }
+    fn serialize_player_into(&self, player: &PlayerInfo, buf: &mut impl BufMut) {
+        let has = |action: u8| self.actions & action != 0;
+        player.profile.uuid.serialize_into(buf);
+        if has(Self::ADD_PLAYER) {
+            player.profile.username.serialize_into(buf);
+            player.profile.properties.serialize_into(buf);
+        }
+        if has(Self::INITIALIZE_CHAT) {
+            player.chat_session.is_some().serialize_into(buf);
+            player.chat_session.serialize_into(buf);
+        }
+        if has(Self::UPDATE_GAME_MODE) {
+            player.game_mode.serialize_into(buf);
+        }
+        if has(Self::UPDATE_LISTED) {
+            player.listed.serialize_into(buf);
+        }
+        if has(Self::UPDATE_LATENCY) {
+            player.latency.serialize_into(buf);
+        }
+        if has(Self::UPDATE_DISPLAY_NAME) {
+            player.display_name.is_some().serialize_into(buf);
+            player.display_name.serialize_into(buf);
+        }
+        if has(Self::UPDATE_LIST_PRIORITY) {
+            player.list_priority.serialize_into(buf);
+        }
+        if has(Self::UPDATE_HAT) {
+            player.show_hat.serialize_into(buf);
+        }
+    }
+    /// The number of bytes that `serialize_player_into` produces.
+    fn player_len(&self, player: &PlayerInfo) -> usize {
+        let has = |action: u8| self.actions & action != 0;
+        let mut len = player.profile.uuid.encoded_len();
+        if has(Self::ADD_PLAYER) {
+            len += player.profile.username.encoded_len() + player.profile.properties.encoded_len();
+        }
+        if has(Self::INITIALIZE_CHAT) {
+            len += 1 + player.chat_session.encoded_len();
+        }
+        if has(Self::UPDATE_GAME_MODE) {
+            len += player.game_mode.encoded_len();
+        }
+        if has(Self::UPDATE_LISTED) {
+            len += 1;
+        }
+        if has(Self::UPDATE_LATENCY) {
+            len += player.latency.encoded_len();
+        }
+        if has(Self::UPDATE_DISPLAY_NAME) {
+            len += 1 + player.display_name.encoded_len();
+        }
+        if has(Self::UPDATE_LIST_PRIORITY) {
+            len += player.list_priority.encoded_len();
+        }
+        if has(Self::UPDATE_HAT) {
+            len += 1;
+        }
+        len
+    }
+}
+impl Parsable for PlayerInfoUpdates {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, actions) = u8::parse(data)?;
+        let (data, len) = VarInt::parse(data)?;
+        let (data, players) = nom::multi::count(
+            |data| PlayerInfoUpdates::parse_player(actions, data),
+            *len as usize,
+        )(data)?;
+        Ok((data, PlayerInfoUpdates { actions, players }))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.actions.serialize_into(buf);
+        VarInt::from(self.players.len()).serialize_into(buf);
+        for player in &self.players {
+            self.serialize_player_into(player, buf);
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        self.actions.encoded_len()
+            + VarInt::from(self.players.len()).encoded_len()
+            + self
+                .players
+                .iter()
+                .map(|player| self.player_len(player))
+                .sum::<usize>()
+    }
+}
+
+/// A player's entry in the tab list.
+///
+/// Only the fields selected by `PlayerInfoUpdates::actions` are sent.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PlayerInfo {
+    /// The username and properties are only sent when adding the player.
+    pub profile: GameProfile,
+    pub chat_session: Option<PlayerChatSession>,
+    pub game_mode: VarInt,
+    pub listed: bool,
+    /// Ping in milliseconds.
+    pub latency: VarInt,
+    pub display_name: Option<TextComponent>,
+    /// Players with higher priority are listed first.
+    pub list_priority: VarInt,
+    pub show_hat: bool,
+}
+
+/// The key a player signs their chat messages with.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PlayerChatSession {
+    pub session_id: Uuid,
+    /// When the key expires, in milliseconds since the Unix epoch.
+    pub expires_at: i64,
+    pub public_key: Vec<u8>,
+    pub key_signature: Vec<u8>,
+}
+impl Parsable for PlayerChatSession {
     #[tracing::instrument]
-    fn serialize(&self) -> Vec<u8> {
-        vec![*self as u8]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, session_id) = Uuid::parse(data)?;
+        let (data, expires_at) = i64::parse(data)?;
+        let (data, public_key) = Vec::parse(data)?;
+        let (data, key_signature) = Vec::parse(data)?;
+        Ok((
+            data,
+            PlayerChatSession {
+                session_id,
+                expires_at,
+                public_key,
+                key_signature,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.session_id.serialize_into(buf);
+        self.expires_at.serialize_into(buf);
+        self.public_key.serialize_into(buf);
+        self.key_signature.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.session_id.encoded_len()
+            + self.expires_at.encoded_len()
+            + self.public_key.encoded_len()
+            + self.key_signature.encoded_len()
+    }
+}
+
+/// A sound that isn't in the client's sound event registry.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SoundEvent {
+    pub sound_name: Identifier,
+    /// How far away the sound can be heard, which otherwise depends on its volume.
+    pub fixed_range: PrefixedOptional<f32>,
+}
+impl Parsable for SoundEvent {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, sound_name) = Identifier::parse(data)?;
+        let (data, fixed_range) = PrefixedOptional::parse(data)?;
+        Ok((
+            data,
+            SoundEvent {
+                sound_name,
+                fixed_range,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.sound_name.serialize_into(buf);
+        self.fixed_range.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.sound_name.encoded_len() + self.fixed_range.encoded_len()
+    }
+}
+
+crate::varint_enum! {
+    /// The volume slider a sound is played under.
+    #[derive(Default)]
+    pub enum SoundCategory {
+        #[default]
+        Master = 0,
+        Music = 1,
+        Record = 2,
+        Weather = 3,
+        Block = 4,
+        Hostile = 5,
+        Neutral = 6,
+        Player = 7,
+        Ambient = 8,
+        Voice = 9,
+    }
+}
+
+/// A chat type that isn't in the client's chat type registry.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ChatType {
+    pub chat: ChatTypeDecoration,
+    pub narration: ChatTypeDecoration,
+}
+impl Parsable for ChatType {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, chat) = ChatTypeDecoration::parse(data)?;
+        let (data, narration) = ChatTypeDecoration::parse(data)?;
+        Ok((data, ChatType { chat, narration }))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.chat.serialize_into(buf);
+        self.narration.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.chat.encoded_len() + self.narration.encoded_len()
+    }
+}
+
+/// How a chat type formats a message.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ChatTypeDecoration {
+    pub translation_key: String,
+    /// The arguments passed to the translation, in order.
+    pub parameters: PrefixedArray<ChatTypeParameter>,
+    pub style: Tag,
+}
+impl Parsable for ChatTypeDecoration {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, translation_key) = String::parse(data)?;
+        let (data, parameters) = Vec::parse(data)?;
+        let (data, style) = Tag::parse(data)?;
+        Ok((
+            data,
+            ChatTypeDecoration {
+                translation_key,
+                parameters,
+                style,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.translation_key.serialize_into(buf);
+        self.parameters.serialize_into(buf);
+        self.style.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.translation_key.encoded_len()
+            + self.parameters.encoded_len()
+            + self.style.encoded_len()
+    }
+}
+
+crate::varint_enum! {
+    /// A part of a chat message that a chat type can include.
+    pub enum ChatTypeParameter {
+        Sender = 0,
+        Target = 1,
+        Content = 2,
+    }
+}
+
+/// A painting variant that isn't in the client's painting variant registry.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PaintingVariant {
+    pub width: VarInt,
+    pub height: VarInt,
+    pub asset_id: Identifier,
+    pub title: PrefixedOptional<TextComponent>,
+    pub author: PrefixedOptional<TextComponent>,
+}
+impl Parsable for PaintingVariant {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, width) = VarInt::parse(data)?;
+        let (data, height) = VarInt::parse(data)?;
+        let (data, asset_id) = Identifier::parse(data)?;
+        let (data, title) = PrefixedOptional::parse(data)?;
+        let (data, author) = PrefixedOptional::parse(data)?;
+        Ok((
+            data,
+            PaintingVariant {
+                width,
+                height,
+                asset_id,
+                title,
+                author,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.width.serialize_into(buf);
+        self.height.serialize_into(buf);
+        self.asset_id.serialize_into(buf);
+        self.title.serialize_into(buf);
+        self.author.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.width.encoded_len()
+            + self.height.encoded_len()
+            + self.asset_id.encoded_len()
+            + self.title.encoded_len()
+            + self.author.encoded_len()
+    }
+}
+
+/// A change to a boss bar, represented in the protocol as a `VarInt`
+/// of the action followed by its fields.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum BossBarAction {
+    Add {
+        title: TextComponent,
+        health: f32,
+        color: BossBarColor,
+        division: BossBarDivision,
+        /// 0x01: darken sky, 0x02: dragon bar, 0x04: create fog.
+        flags: u8,
+    },
+    #[default]
+    Remove,
+    UpdateHealth {
+        health: f32,
+    },
+    UpdateTitle {
+        title: TextComponent,
+    },
+    UpdateStyle {
+        color: BossBarColor,
+        division: BossBarDivision,
+    },
+    UpdateFlags {
+        flags: u8,
+    },
+}
+impl Parsable for BossBarAction {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, action) = VarInt::parse(data)?;
+        match *action {
+            0 => {
+                let (data, title) = TextComponent::parse(data)?;
+                let (data, health) = f32::parse(data)?;
+                let (data, color) = BossBarColor::parse(data)?;
+                let (data, division) = BossBarDivision::parse(data)?;
+                let (data, flags) = u8::parse(data)?;
+                Ok((
+                    data,
+                    BossBarAction::Add {
+                        title,
+                        health,
+                        color,
+                        division,
+                        flags,
+                    },
+                ))
+            }
+            1 => Ok((data, BossBarAction::Remove)),
+            2 => {
+                let (data, health) = f32::parse(data)?;
+                Ok((data, BossBarAction::UpdateHealth { health }))
+            }
+            3 => {
+                let (data, title) = TextComponent::parse(data)?;
+                Ok((data, BossBarAction::UpdateTitle { title }))
+            }
+            4 => {
+                let (data, color) = BossBarColor::parse(data)?;
+                let (data, division) = BossBarDivision::parse(data)?;
+                Ok((data, BossBarAction::UpdateStyle { color, division }))
+            }
+            5 => {
+                let (data, flags) = u8::parse(data)?;
+                Ok((data, BossBarAction::UpdateFlags { flags }))
+            }
+            _ => nom::combinator::fail(data),
+        }
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        match self {
+            BossBarAction::Add {
+                title,
+                health,
+                color,
+                division,
+                flags,
+            } => {
+                VarInt::from(0).serialize_into(buf);
+                title.serialize_into(buf);
+                health.serialize_into(buf);
+                color.serialize_into(buf);
+                division.serialize_into(buf);
+                flags.serialize_into(buf);
+            }
+            BossBarAction::Remove => {
+                VarInt::from(1).serialize_into(buf);
+            }
+            BossBarAction::UpdateHealth { health } => {
+                VarInt::from(2).serialize_into(buf);
+                health.serialize_into(buf);
+            }
+            BossBarAction::UpdateTitle { title } => {
+                VarInt::from(3).serialize_into(buf);
+                title.serialize_into(buf);
+            }
+            BossBarAction::UpdateStyle { color, division } => {
+                VarInt::from(4).serialize_into(buf);
+                color.serialize_into(buf);
+                division.serialize_into(buf);
+            }
+            BossBarAction::UpdateFlags { flags } => {
+                VarInt::from(5).serialize_into(buf);
+                flags.serialize_into(buf);
+            }
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        // Every action's id fits in one byte.
+        1 + match self {
+            BossBarAction::Add {
+                title,
+                health,
+                color,
+                division,
+                flags,
+            } => {
+                title.encoded_len()
+                    + health.encoded_len()
+                    + color.encoded_len()
+                    + division.encoded_len()
+                    + flags.encoded_len()
+            }
+            BossBarAction::Remove => 0,
+            BossBarAction::UpdateHealth { health } => health.encoded_len(),
+            BossBarAction::UpdateTitle { title } => title.encoded_len(),
+            BossBarAction::UpdateStyle { color, division } => {
+                color.encoded_len() + division.encoded_len()
+            }
+            BossBarAction::UpdateFlags { flags } => flags.encoded_len(),
+        }
+    }
+}
+
+crate::varint_enum! {
+    #[derive(Default)]
+    pub enum BossBarColor {
+        #[default]
+        Pink = 0,
+        Blue = 1,
+        Red = 2,
+        Green = 3,
+        Yellow = 4,
+        Purple = 5,
+        White = 6,
+    }
+}
+
+crate::varint_enum! {
+    /// How many notches a boss bar is divided into.
+    #[derive(Default)]
+    pub enum BossBarDivision {
+        #[default]
+        None = 0,
+        Notches6 = 1,
+        Notches10 = 2,
+        Notches12 = 3,
+        Notches20 = 4,
+    }
+}
+
+/// A marker shown on a map.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MapIcon {
+    /// Id in the `minecraft:map_decoration_type` registry.
+    pub kind: VarInt,
+    pub x: i8,
+    pub z: i8,
+    /// Rotation in steps of 1/16 of a full turn.
+    pub direction: i8,
+    pub display_name: PrefixedOptional<TextComponent>,
+}
+impl Parsable for MapIcon {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, kind) = VarInt::parse(data)?;
+        let (data, x) = i8::parse(data)?;
+        let (data, z) = i8::parse(data)?;
+        let (data, direction) = i8::parse(data)?;
+        let (data, display_name) = PrefixedOptional::parse(data)?;
+        Ok((
+            data,
+            MapIcon {
+                kind,
+                x,
+                z,
+                direction,
+                display_name,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.kind.serialize_into(buf);
+        self.x.serialize_into(buf);
+        self.z.serialize_into(buf);
+        self.direction.serialize_into(buf);
+        self.display_name.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.kind.encoded_len()
+            + self.x.encoded_len()
+            + self.z.encoded_len()
+            + self.direction.encoded_len()
+            + self.display_name.encoded_len()
+    }
+}
+
+/// A rectangle of updated map colors.
+///
+/// A patch with no columns changes nothing, and is represented in the
+/// protocol without the other fields.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MapColorPatch {
+    pub columns: u8,
+    pub rows: u8,
+    pub x: u8,
+    pub z: u8,
+    /// Colors in row-major order.
+    pub colors: Vec<u8>,
+}
+impl Parsable for MapColorPatch {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, columns) = u8::parse(data)?;
+        if columns == 0 {
+            return Ok((data, MapColorPatch::default()));
+        }
+        let (data, rows) = u8::parse(data)?;
+        let (data, x) = u8::parse(data)?;
+        let (data, z) = u8::parse(data)?;
+        let (data, colors) = Vec::parse(data)?;
+        Ok((
+            data,
+            MapColorPatch {
+                columns,
+                rows,
+                x,
+                z,
+                colors,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.columns.serialize_into(buf);
+        if self.columns == 0 {
+            return;
+        }
+        self.rows.serialize_into(buf);
+        self.x.serialize_into(buf);
+        self.z.serialize_into(buf);
+        self.colors.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        if self.columns == 0 {
+            return self.columns.encoded_len();
+        }
+        self.columns.encoded_len()
+            + self.rows.encoded_len()
+            + self.x.encoded_len()
+            + self.z.encoded_len()
+            + self.colors.encoded_len()
+    }
+}
+
+/// How much of a chat message the server hid, for messages that were
+/// changed by its chat filter.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ChatFilter {
+    #[default]
+    PassThrough,
+    FullyFiltered,
+    /// The characters whose bits are set were hidden.
+    PartiallyFiltered(BitSet),
+}
+impl Parsable for ChatFilter {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, kind) = VarInt::parse(data)?;
+        match *kind {
+            0 => Ok((data, ChatFilter::PassThrough)),
+            1 => Ok((data, ChatFilter::FullyFiltered)),
+            2 => nom::combinator::map(BitSet::parse, ChatFilter::PartiallyFiltered)(data),
+            _ => nom::combinator::fail(data),
+        }
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        match self {
+            ChatFilter::PassThrough => VarInt::from(0).serialize_into(buf),
+            ChatFilter::FullyFiltered => VarInt::from(1).serialize_into(buf),
+            ChatFilter::PartiallyFiltered(mask) => {
+                VarInt::from(2).serialize_into(buf);
+                mask.serialize_into(buf);
+            }
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        match self {
+            ChatFilter::PassThrough | ChatFilter::FullyFiltered => 1,
+            ChatFilter::PartiallyFiltered(mask) => 1 + mask.encoded_len(),
+        }
+    }
+}
+
+/// Which playing sounds to stop, represented in the protocol as flags
+/// for which of the fields are present.
+///
+/// Every sound is stopped if neither is given.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StopSoundFilter {
+    pub category: Option<SoundCategory>,
+    pub sound: Option<Identifier>,
+}
+impl StopSoundFilter {
+    const HAS_CATEGORY: u8 = 0x01;
+    const HAS_SOUND: u8 = 0x02;
+}
+impl Parsable for StopSoundFilter {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, flags) = u8::parse(data)?;
+        let (data, category) = if flags & StopSoundFilter::HAS_CATEGORY != 0 {
+            nom::combinator::map(SoundCategory::parse, Some)(data)?
+        } else {
+            (data, None)
+        };
+        let (data, sound) = if flags & StopSoundFilter::HAS_SOUND != 0 {
+            nom::combinator::map(Identifier::parse, Some)(data)?
+        } else {
+            (data, None)
+        };
+        Ok((data, StopSoundFilter { category, sound }))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        let mut flags = 0;
+        if self.category.is_some() {
+            flags |= StopSoundFilter::HAS_CATEGORY;
+        }
+        if self.sound.is_some() {
+            flags |= StopSoundFilter::HAS_SOUND;
+        }
+        flags.serialize_into(buf);
+        self.category.serialize_into(buf);
+        self.sound.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        1 + self.category.encoded_len() + self.sound.encoded_len()
+    }
+}
+
+/// A link shown in the pause menu and on the disconnect screen.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ServerLink {
+    pub label: ServerLinkLabel,
+    pub url: String,
+}
+impl Parsable for ServerLink {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, label) = ServerLinkLabel::parse(data)?;
+        let (data, url) = String::parse(data)?;
+        Ok((data, ServerLink { label, url }))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.label.serialize_into(buf);
+        self.url.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.label.encoded_len() + self.url.encoded_len()
+    }
+}
+
+/// A server link's label, represented in the protocol as a boolean that is
+/// true before a built-in label and false before a text component.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerLinkLabel {
+    BuiltIn(ServerLinkKind),
+    Custom(TextComponent),
+}
+impl Default for ServerLinkLabel {
+    fn default() -> Self {
+        ServerLinkLabel::BuiltIn(ServerLinkKind::default())
+    }
+}
+impl Parsable for ServerLinkLabel {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, built_in) = bool::parse(data)?;
+        if built_in {
+            nom::combinator::map(ServerLinkKind::parse, ServerLinkLabel::BuiltIn)(data)
+        } else {
+            nom::combinator::map(TextComponent::parse, ServerLinkLabel::Custom)(data)
+        }
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        match self {
+            ServerLinkLabel::BuiltIn(kind) => {
+                true.serialize_into(buf);
+                kind.serialize_into(buf);
+            }
+            ServerLinkLabel::Custom(text) => {
+                false.serialize_into(buf);
+                text.serialize_into(buf);
+            }
+        }
+    }
+    fn encoded_len(&self) -> usize {
+        1 + match self {
+            ServerLinkLabel::BuiltIn(kind) => kind.encoded_len(),
+            ServerLinkLabel::Custom(text) => text.encoded_len(),
+        }
+    }
+}
+
+crate::varint_enum! {
+    /// A server link label that the client translates.
+    #[derive(Default)]
+    pub enum ServerLinkKind {
+        #[default]
+        BugReport = 0,
+        CommunityGuidelines = 1,
+        Support = 2,
+        Status = 3,
+        Feedback = 4,
+        Community = 5,
+        Website = 6,
+        Forums = 7,
+        News = 8,
+        Announcements = 9,
+    }
+}
+
+crate::varint_enum! {
+    /// A button pressed in a test instance block's screen.
+    #[derive(Default)]
+    pub enum TestInstanceAction {
+        #[default]
+        Init = 0,
+        Query = 1,
+        Set = 2,
+        Reset = 3,
+        Save = 4,
+        Export = 5,
+        Run = 6,
+    }
+}
+
+crate::varint_enum! {
+    #[derive(Default)]
+    pub enum TestInstanceStatus {
+        #[default]
+        Cleared = 0,
+        Running = 1,
+        Finished = 2,
+    }
+}
+
+/// The settings of a test instance block, as edited in its screen.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TestInstanceData {
+    pub test: PrefixedOptional<Identifier>,
+    pub size: (VarInt, VarInt, VarInt),
+    /// Quarter turns clockwise, from 0 to 3.
+    pub rotation: VarInt,
+    pub ignore_entities: bool,
+    pub status: TestInstanceStatus,
+    pub error_message: PrefixedOptional<TextComponent>,
+}
+impl Parsable for TestInstanceData {
+    #[tracing::instrument]
+    fn parse(data: &[u8]) -> IResult<&[u8], Self> {
+        let (data, test) = PrefixedOptional::parse(data)?;
+        let (data, size) = <(VarInt, VarInt, VarInt)>::parse(data)?;
+        let (data, rotation) = VarInt::parse(data)?;
+        let (data, ignore_entities) = bool::parse(data)?;
+        let (data, status) = TestInstanceStatus::parse(data)?;
+        let (data, error_message) = PrefixedOptional::parse(data)?;
+        Ok((
+            data,
+            TestInstanceData {
+                test,
+                size,
+                rotation,
+                ignore_entities,
+                status,
+                error_message,
+            },
+        ))
+    }
+    fn serialize_into(&self, buf: &mut impl BufMut) {
+        self.test.serialize_into(buf);
+        self.size.serialize_into(buf);
+        self.rotation.serialize_into(buf);
+        self.ignore_entities.serialize_into(buf);
+        self.status.serialize_into(buf);
+        self.error_message.serialize_into(buf);
+    }
+    fn encoded_len(&self) -> usize {
+        self.test.encoded_len()
+            + self.size.encoded_len()
+            + self.rotation.encoded_len()
+            + self.ignore_entities.encoded_len()
+            + self.status.encoded_len()
+            + self.error_message.encoded_len()
     }
 }
 
@@ -105,4 +1265,78 @@ mod tests {
             assert_eq!(bytes, value.serialize());
         }
     }
+    #[test]
+    fn offline_uuid_works() {
+        assert_eq!(
+            offline_uuid("Notch"),
+            Uuid::parse_str("b50ad385-829d-3141-a216-7e7d7539ba7f").unwrap()
+        );
+        assert_eq!(offline_uuid("Notch").get_version_num(), 3);
+    }
+    #[test]
+    fn game_profile_works() {
+        let profile = GameProfile {
+            uuid: Uuid::from_u128(1),
+            username: "Notch".to_owned(),
+            properties: vec![
+                ProfileProperty {
+                    name: "textures".to_owned(),
+                    value: "e30=".to_owned(),
+                    signature: Some("c2ln".to_owned()),
+                },
+                ProfileProperty {
+                    name: "unsigned".to_owned(),
+                    value: "".to_owned(),
+                    signature: None,
+                },
+            ],
+        };
+        let mut bytes = Uuid::from_u128(1).serialize();
+        bytes.extend([0x05, b'N', b'o', b't', b'c', b'h', 0x02]);
+        bytes.extend([0x08, b't', b'e', b'x', b't', b'u', b'r', b'e', b's']);
+        bytes.extend([
+            0x04, b'e', b'3', b'0', b'=', 0x01, 0x04, b'c', b'2', b'l', b'n',
+        ]);
+        bytes.extend([
+            0x08, b'u', b'n', b's', b'i', b'g', b'n', b'e', b'd', 0x00, 0x00,
+        ]);
+        assert_eq!(profile.serialize(), bytes);
+        assert_eq!(GameProfile::parse(&bytes).unwrap(), (&[][..], profile));
+    }
+    #[test]
+    fn player_info_updates_work() {
+        let updates = PlayerInfoUpdates {
+            actions: PlayerInfoUpdates::ADD_PLAYER
+                | PlayerInfoUpdates::UPDATE_LISTED
+                | PlayerInfoUpdates::UPDATE_DISPLAY_NAME,
+            players: vec![PlayerInfo {
+                profile: GameProfile::offline("Notch".to_owned(), None),
+                listed: true,
+                display_name: Some(TextComponent::text("Notch")),
+                ..PlayerInfo::default()
+            }],
+        };
+        let bytes = updates.serialize();
+        assert_eq!(
+            PlayerInfoUpdates::parse(&bytes).unwrap(),
+            (&[][..], updates)
+        );
+
+        // Only the uuid and selected fields are sent.
+        let updates = PlayerInfoUpdates {
+            actions: PlayerInfoUpdates::UPDATE_LATENCY,
+            players: vec![PlayerInfo {
+                latency: VarInt::from(100),
+                ..PlayerInfo::default()
+            }],
+        };
+        let mut bytes = vec![0x10, 0x01];
+        bytes.extend(Uuid::nil().serialize());
+        bytes.push(0x64);
+        assert_eq!(updates.serialize(), bytes);
+        assert_eq!(
+            PlayerInfoUpdates::parse(&bytes).unwrap(),
+            (&[][..], updates)
+        );
+    }
 }
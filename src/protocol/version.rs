@@ -0,0 +1,87 @@
+/// A release of the protocol that Composition can speak.
+///
+/// Variants are ordered oldest to newest, so comparisons can check
+/// whether a packet or field exists in a connection's version.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProtocolVersion {
+    /// 1.21.4
+    ///
+    /// Packets are laid out as in 1.21.4, but values whose encoding changed in 1.21.5
+    /// (item stacks, particles, entity metadata and text component events) are not converted.
+    V1_21_4,
+    /// 1.21.5
+    #[default]
+    V1_21_5,
+}
+impl ProtocolVersion {
+    /// Every supported version, oldest first.
+    pub const ALL: [ProtocolVersion; 2] = [ProtocolVersion::V1_21_4, ProtocolVersion::V1_21_5];
+    pub const OLDEST: ProtocolVersion = ProtocolVersion::V1_21_4;
+    pub const LATEST: ProtocolVersion = ProtocolVersion::V1_21_5;
+    /// The oldest version packet definitions go back to.
+    ///
+    /// Packets and fields without a `since` marker exist in every version from this one.
+    /// Ones without an `until` marker exist up to [`ProtocolVersion::LATEST`].
+    pub const BASELINE: ProtocolVersion = ProtocolVersion::V1_21_4;
+
+    /// Look up a version by the number sent in `Handshake.protocol_version`.
+    pub fn from_protocol_version(protocol_version: i32) -> Option<ProtocolVersion> {
+        ProtocolVersion::ALL
+            .into_iter()
+            .find(|version| version.protocol_version() == protocol_version)
+    }
+    /// The game versions that are supported, such as `1.21.5` or `1.21.4-1.21.5`.
+    pub fn supported_range() -> String {
+        if ProtocolVersion::OLDEST == ProtocolVersion::LATEST {
+            return ProtocolVersion::LATEST.game_version().to_owned();
+        }
+        format!(
+            "{}-{}",
+            ProtocolVersion::OLDEST.game_version(),
+            ProtocolVersion::LATEST.game_version()
+        )
+    }
+    pub const fn protocol_version(&self) -> i32 {
+        match self {
+            ProtocolVersion::V1_21_4 => 769,
+            ProtocolVersion::V1_21_5 => 770,
+        }
+    }
+    pub const fn game_version(&self) -> &'static str {
+        match self {
+            ProtocolVersion::V1_21_4 => "1.21.4",
+            ProtocolVersion::V1_21_5 => "1.21.5",
+        }
+    }
+}
+impl std::fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.game_version(), self.protocol_version())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protocol_versions_work() {
+        assert!(ProtocolVersion::OLDEST <= ProtocolVersion::LATEST);
+        assert_eq!(ProtocolVersion::ALL.first(), Some(&ProtocolVersion::OLDEST));
+        assert_eq!(ProtocolVersion::ALL.last(), Some(&ProtocolVersion::LATEST));
+        for version in ProtocolVersion::ALL {
+            assert_eq!(
+                ProtocolVersion::from_protocol_version(version.protocol_version()),
+                Some(version)
+            );
+        }
+        assert!(ProtocolVersion::BASELINE <= ProtocolVersion::OLDEST);
+        assert_eq!(ProtocolVersion::from_protocol_version(767), None);
+        assert_eq!(
+            ProtocolVersion::from_protocol_version(769),
+            Some(ProtocolVersion::V1_21_4)
+        );
+        assert_eq!(ProtocolVersion::LATEST.to_string(), "1.21.5 (770)");
+        assert_eq!(ProtocolVersion::supported_range(), "1.21.4-1.21.5");
+    }
+}
@@ -1,7 +1,10 @@
 pub mod config;
 pub mod error;
 
-use crate::protocol::packets::Packet;
+use crate::protocol::packets::{
+    login::clientbound::EncryptionRequest, status::clientbound::StatusResponse, Packet, RawPacket,
+};
+use crate::protocol::text::TextComponent;
 use crate::protocol::ClientState;
 use crate::App;
 use crate::{
@@ -13,7 +16,7 @@ use error::{Error, NetworkError};
 use tokio::net::TcpStream;
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
-use tracing::{info, trace};
+use tracing::{debug, info, trace, warn};
 
 #[derive(Debug)]
 pub struct Proxy {
@@ -47,6 +50,20 @@ impl Proxy {
             p => Some(p),
         }
     }
+    /// Rewrite a packet that hasn't been parsed, only parsing it if `rewrite_packet` would change it.
+    pub fn rewrite_raw_packet(packet: RawPacket) -> Result<Option<RawPacket>, NetworkError> {
+        if !packet.is::<StatusResponse>() && !packet.is::<EncryptionRequest>() {
+            return Ok(Some(packet));
+        }
+        let version = packet.version;
+        let packet = packet
+            .into_packet()
+            .map_err(|e| NetworkError::InvalidPacket(Box::new(e)))?;
+        Ok(
+            Proxy::rewrite_packet(packet)
+                .and_then(|packet| RawPacket::from_packet(version, packet)),
+        )
+    }
 }
 #[async_trait::async_trait]
 impl App for Proxy {
@@ -99,13 +116,13 @@ impl App for Proxy {
         // At the same time, try to read packets from the server and client.
         // Forward the packet onto the other.
         tokio::select! {
-            packet = client.read_packet() => {
+            packet = client.read_raw_packet() => {
                 if let Some(packet) = packet {
                     match packet {
                         Ok(packet) => {
                             let next_state = packet.state_change();
-                            if let Some(packet) = Proxy::rewrite_packet(packet) {
-                                self.upstream.send_packet(packet).await.map_err(Error::Network)?;
+                            if let Some(packet) = Proxy::rewrite_raw_packet(packet).map_err(Error::Network)? {
+                                self.upstream.forward_raw_packet(packet).await.map_err(Error::Network)?;
                             }
                             if let Some(next_state) = next_state {
                                 *self.upstream.client_state_mut() = next_state;
@@ -118,6 +135,10 @@ impl App for Proxy {
                                     trace!("Got invalid data from client (id {})", client.id());
                                     return Err(Error::Network(NetworkError::Parsing));
                                 }
+                                NetworkError::InvalidPacket(e) => {
+                                    debug!("Got invalid packet from client (id {}): {}", client.id(), e);
+                                    return Err(Error::Network(NetworkError::InvalidPacket(e)));
+                                }
                                 NetworkError::Io(e) => {
                                     if e.kind() == std::io::ErrorKind::UnexpectedEof {
                                         trace!("Client (id {}) disconnected", client.id());
@@ -132,13 +153,13 @@ impl App for Proxy {
                     }
                 }
             }
-            packet = self.upstream.read_packet() => {
+            packet = self.upstream.read_raw_packet() => {
                 if let Some(packet) = packet {
                     match packet {
                         Ok(packet) => {
                             let next_state = packet.state_change();
-                            if let Some(packet) = Proxy::rewrite_packet(packet) {
-                                client.send_packet(packet).await.map_err(Error::Network)?;
+                            if let Some(packet) = Proxy::rewrite_raw_packet(packet).map_err(Error::Network)? {
+                                client.forward_raw_packet(packet).await.map_err(Error::Network)?;
                             }
                             if let Some(next_state) = next_state {
                                 *client.inner_state_mut() = next_state;
@@ -151,6 +172,10 @@ impl App for Proxy {
                                     trace!("Got invalid data from upstream");
                                     Err(Error::Network(NetworkError::Parsing))
                                 }
+                                NetworkError::InvalidPacket(e) => {
+                                    warn!("Got invalid packet from upstream: {}", e);
+                                    Err(Error::Network(NetworkError::InvalidPacket(e)))
+                                }
                                 NetworkError::Io(e) => {
                                     trace!("Got IO error from upstream");
                                     Err(Error::Io(e))
@@ -169,10 +194,7 @@ impl App for Proxy {
             let _ = client;
             let _ = self
                 .connections
-                .disconnect(
-                    id,
-                    Some(serde_json::json!({ "text": "Received malformed data." })),
-                )
+                .disconnect(id, Some(TextComponent::text("Received malformed data.")))
                 .await;
         }
         if self.upstream.client_state() == ClientState::Disconnected || server_error {
@@ -1,4 +1,7 @@
-use crate::config::{read_file, Args, Config};
+use crate::{
+    config::{read_file, Args, Config},
+    protocol::{text::TextComponent, ProtocolVersion},
+};
 use clap::Arg;
 use serde::{Deserialize, Serialize};
 use std::io::Write;
@@ -20,6 +23,24 @@ pub struct ServerConfig {
     pub server_icon: PathBuf,
     #[serde(skip)]
     pub server_icon_bytes: Vec<u8>,
+    /// Packets at least this many bytes long are compressed.
+    /// A negative value disables compression.
+    pub compression_threshold: i32,
+    /// Whether to encrypt connections and authenticate players with the session server.
+    pub online_mode: bool,
+    /// Base URL of the session server used in online mode.
+    pub session_server: String,
+    /// Whether to use the UUID sent by the client in offline mode.
+    ///
+    /// When disabled, UUIDs are derived from the player's name like in vanilla,
+    /// so anything keyed by UUID persists as long as the name does.
+    pub trust_client_uuid: bool,
+    /// Whether to let in clients that another server transferred here.
+    pub accept_transfers: bool,
+    /// The disconnect message for clients on an unsupported version, using `§` formatting codes.
+    ///
+    /// `{versions}` is replaced with the range of supported versions.
+    pub unsupported_version_message: String,
 }
 impl Default for ServerConfig {
     fn default() -> Self {
@@ -30,6 +51,13 @@ impl Default for ServerConfig {
             motd: "Hello world!".to_owned(),
             server_icon: PathBuf::from(DEFAULT_SERVER_ICON),
             server_icon_bytes: include_bytes!("../server-icon.png").to_vec(),
+            compression_threshold: 256,
+            online_mode: false,
+            session_server: crate::net::auth::DEFAULT_SESSION_SERVER.to_owned(),
+            trust_client_uuid: false,
+            accept_transfers: false,
+            unsupported_version_message:
+                "Unsupported client version! Please join using {versions}.".to_owned(),
         }
     }
 }
@@ -37,6 +65,14 @@ impl ServerConfig {
     pub fn instance() -> &'static Self {
         &Config::instance().server
     }
+    /// The disconnect message for clients on an unsupported version.
+    pub fn unsupported_version_message(&self) -> TextComponent {
+        TextComponent::from_legacy(
+            &self
+                .unsupported_version_message
+                .replace("{versions}", &ProtocolVersion::supported_range()),
+        )
+    }
     pub fn load_args(&mut self) {
         self.server_icon = ServerArgs::instance()
             .and_then(|s| s.server_icon.clone())
@@ -1,3 +1,4 @@
+pub use crate::config::ConfigError;
 pub use crate::net::error::Error as NetworkError;
 pub use std::io::Error as IoError;
 pub use tokio::task::JoinError as TaskError;
@@ -12,4 +13,6 @@ pub enum Error {
     Task(#[from] TaskError),
     #[error(transparent)]
     Network(#[from] NetworkError),
+    #[error(transparent)]
+    Config(#[from] ConfigError),
 }
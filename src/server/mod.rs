@@ -5,37 +5,49 @@ pub mod error;
 
 use crate::{
     config::Config,
-    net::connection::{DownstreamConnectionManager, DownstreamConnectionState},
-    server::{config::ServerConfig, error::Error},
+    net::{
+        connection::{
+            DownstreamConnection, DownstreamConnectionManager, DownstreamConnectionState,
+        },
+        plugin::PluginChannels,
+    },
+    protocol::{text::TextComponent, ClientState},
+    server::{
+        config::ServerConfig,
+        error::{Error, NetworkError},
+    },
     App,
 };
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
+use tracing::debug;
 
 /// The main state and logic of the program.
 #[derive(Debug)]
 pub struct Server {
     running: CancellationToken,
     pub connections: DownstreamConnectionManager,
+    /// Handlers for custom payloads from clients in Configuration and Play.
+    pub plugin_channels: PluginChannels,
     listener: JoinHandle<()>,
 }
-#[async_trait::async_trait]
-impl App for Server {
-    type Error = Error;
-
-    fn startup_message() -> String {
-        let config = Config::instance();
-        format!(
-            "Starting {} on port {}",
-            ServerConfig::default().version,
-            config.server.port
-        )
-    }
+impl Server {
+    /// Start the server, handling messages on the given plugin channels.
+    ///
+    /// Channels outside the `minecraft` namespace are announced to clients as they configure.
     #[tracing::instrument]
-    async fn new(running: CancellationToken) -> Result<Self, Self::Error> {
+    pub async fn with_plugin_channels(
+        running: CancellationToken,
+        plugin_channels: PluginChannels,
+    ) -> Result<Self, Error> {
         let config = Config::instance();
         let bind_address = format!("0.0.0.0:{}", config.server.port);
 
+        // Load the RSA key pair now instead of when the first player joins.
+        if config.server.online_mode {
+            config.rsa_key_pair()?;
+        }
+
         // No limit on connections.
         let connections = DownstreamConnectionManager::new(None);
         let listener = connections
@@ -46,9 +58,27 @@ impl App for Server {
         Ok(Server {
             running,
             connections,
+            plugin_channels,
             listener,
         })
     }
+}
+#[async_trait::async_trait]
+impl App for Server {
+    type Error = Error;
+
+    fn startup_message() -> String {
+        let config = Config::instance();
+        format!(
+            "Starting {} on port {}",
+            ServerConfig::default().version,
+            config.server.port
+        )
+    }
+    #[tracing::instrument]
+    async fn new(running: CancellationToken) -> Result<Self, Self::Error> {
+        Server::with_plugin_channels(running, PluginChannels::default()).await
+    }
     #[tracing::instrument]
     async fn update(&mut self) -> Result<(), Self::Error> {
         let online_player_count = self
@@ -57,38 +87,160 @@ impl App for Server {
             .filter(|c| matches!(c.client_state(), DownstreamConnectionState::Play))
             .count();
 
+        let plugin_channels = &self.plugin_channels;
+
         // Receive new connections and remove disconnected ones.
         self.connections.update().await?;
 
         // Read packets from each connection.
         // Handle handshake connections.
-        let _ = futures::future::join_all(
+        futures::future::join_all(
             self.connections
                 .clients_mut()
                 .filter(|c| matches!(c.client_state(), DownstreamConnectionState::Handshake))
-                .map(|c| c.handle_handshake()),
+                .map(|c| async move {
+                    let result = c.handle_handshake().await;
+                    disconnect_on_error(c, result).await
+                }),
         )
         .await;
 
         // Handle status connections.
-        let _ = futures::future::join_all(
+        futures::future::join_all(
+            self.connections
+                .clients_mut()
+                .filter(|c| {
+                    matches!(
+                        c.client_state(),
+                        DownstreamConnectionState::LegacyStatusRequest
+                    )
+                })
+                .map(|c| async move {
+                    let result = c.handle_legacy_status_ping(online_player_count).await;
+                    disconnect_on_error(c, result).await
+                }),
+        )
+        .await;
+        futures::future::join_all(
             self.connections
                 .clients_mut()
                 .filter(|c| matches!(c.client_state(), DownstreamConnectionState::StatusRequest))
-                .map(|c| c.handle_status_ping(online_player_count)),
+                .map(|c| async move {
+                    let result = c.handle_status_ping(online_player_count).await;
+                    disconnect_on_error(c, result).await
+                }),
         )
         .await;
 
         // Handle login connections.
-        let _ = futures::future::join_all(
+        futures::future::join_all(
             self.connections
                 .clients_mut()
                 .filter(|c| matches!(c.client_state(), DownstreamConnectionState::LoginStart))
-                .map(|c| c.handle_login()),
+                .map(|c| async move {
+                    let result = c.handle_login().await;
+                    disconnect_on_error(c, result).await
+                }),
+        )
+        .await;
+
+        // Handle configuration connections.
+        futures::future::join_all(
+            self.connections
+                .clients_mut()
+                .filter(|c| {
+                    matches!(
+                        c.client_state(),
+                        DownstreamConnectionState::LoginAcknowledge
+                    )
+                })
+                .map(|c| async move {
+                    let result = c.handle_login_acknowledged().await;
+                    disconnect_on_error(c, result).await
+                }),
+        )
+        .await;
+        futures::future::join_all(
+            self.connections
+                .clients_mut()
+                .filter(|c| {
+                    matches!(
+                        c.client_state(),
+                        DownstreamConnectionState::ConfigurationStart
+                    )
+                })
+                .map(|c| async move {
+                    let result = c.start_configuration(plugin_channels).await;
+                    disconnect_on_error(c, result).await
+                }),
+        )
+        .await;
+        futures::future::join_all(
+            self.connections
+                .clients_mut()
+                .filter(|c| {
+                    matches!(
+                        c.client_state(),
+                        DownstreamConnectionState::ConfigurationKnownPacks
+                            | DownstreamConnectionState::ConfigurationFinish
+                    )
+                })
+                .map(|c| async move {
+                    let result = c.handle_configuration(plugin_channels).await;
+                    disconnect_on_error(c, result).await
+                }),
+        )
+        .await;
+        futures::future::join_all(
+            self.connections
+                .clients_mut()
+                .filter(|c| {
+                    matches!(
+                        c.client_state(),
+                        DownstreamConnectionState::ConfigurationAcknowledge
+                    )
+                })
+                .map(|c| async move {
+                    let result = c.handle_reconfiguration(plugin_channels).await;
+                    disconnect_on_error(c, result).await
+                }),
+        )
+        .await;
+
+        // Keep configuration and play connections alive.
+        futures::future::join_all(
+            self.connections
+                .clients_mut()
+                .filter(|c| {
+                    matches!(
+                        c.client_state(),
+                        DownstreamConnectionState::ConfigurationKnownPacks
+                            | DownstreamConnectionState::ConfigurationFinish
+                            | DownstreamConnectionState::Play
+                    )
+                })
+                .map(|c| async move {
+                    let result = c.update_keep_alive().await;
+                    disconnect_on_error(c, result).await
+                }),
         )
         .await;
 
         // Handle play connection packets.
+        futures::future::join_all(
+            self.connections
+                .clients_mut()
+                .filter(|c| {
+                    matches!(c.client_state(), DownstreamConnectionState::Play)
+                        && c.inner_state() == ClientState::Play
+                })
+                .map(|c| async move {
+                    let result = c.handle_play(plugin_channels).await;
+                    disconnect_on_error(c, result).await
+                }),
+        )
+        .await;
+
         // Process world updates.
         // Send out play connection updates.
 
@@ -102,12 +254,201 @@ impl App for Server {
         let _ = self.listener.await.map_err(Error::Task)?;
         let _ = self
             .connections
-            .shutdown(Some(
-                serde_json::json!({ "text": "The server is shutting down." }),
-            ))
+            .shutdown(Some(TextComponent::text("The server is shutting down.")))
             .await
             .map_err(Error::Network)?;
 
         Ok(())
     }
 }
+
+/// Log the error from handling a client's packets and disconnect the client.
+async fn disconnect_on_error(client: &mut DownstreamConnection, result: Result<(), NetworkError>) {
+    if let Err(e) = result {
+        debug!("Disconnecting connection {}: {}", client.id(), e);
+        let _ = client.disconnect(None).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        net::{
+            connection::GenericConnection,
+            plugin::{self, PluginMessage},
+        },
+        protocol::packets::{
+            configuration::serverbound::{
+                AcknowledgeFinishConfiguration, ConfigurationServerboundPluginMessage,
+            },
+            play::{clientbound::GameEvent, serverbound::PlayServerboundPluginMessage},
+            Packet, PacketDirection,
+        },
+    };
+    use std::time::Duration;
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// Handlers that echo messages on `example:echo` back to the client.
+    fn echo_channels() -> PluginChannels {
+        let mut plugin_channels = PluginChannels::default();
+        plugin_channels.register("example:echo", |context, data| {
+            context.reply("example:echo", data.to_vec());
+        });
+        plugin_channels
+    }
+
+    /// Connect a client to a downstream connection, with both sides in `state`.
+    async fn connect(state: ClientState) -> (GenericConnection, DownstreamConnection) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let mut client = GenericConnection::new(0, PacketDirection::Clientbound, stream);
+        *client.client_state_mut() = state;
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut connection = DownstreamConnection::new(0, stream);
+        *connection.inner_state_mut() = state;
+        (client, connection)
+    }
+
+    #[tokio::test]
+    async fn registered_plugin_channels_work() {
+        let plugin_channels = echo_channels();
+        let (mut client, mut connection) = connect(ClientState::Configuration).await;
+
+        // The channel is announced after the brand.
+        connection
+            .start_configuration(&plugin_channels)
+            .await
+            .unwrap();
+        let mut messages = vec![];
+        for _ in 0..2 {
+            match client.read_packet().await.unwrap().unwrap() {
+                Packet::ConfigurationClientboundPluginMessage(message) => {
+                    messages.push(PluginMessage::new(message.channel, message.data))
+                }
+                packet => panic!("expected a plugin message, got {:?}", packet),
+            }
+        }
+        assert_eq!(messages[0].channel, plugin::BRAND);
+        assert_eq!(
+            messages[1],
+            PluginMessage::new(plugin::REGISTER, b"example:echo".to_vec())
+        );
+
+        // Messages on the channel reach its handler, which replies.
+        client
+            .send_packet(ConfigurationServerboundPluginMessage {
+                channel: "example:echo".to_owned(),
+                data: vec![1, 2],
+            })
+            .await
+            .unwrap();
+        // Skip the feature flags and known packs.
+        client.read_packet().await.unwrap().unwrap();
+        client.read_packet().await.unwrap().unwrap();
+        connection
+            .handle_configuration(&plugin_channels)
+            .await
+            .unwrap();
+        match client.read_packet().await.unwrap().unwrap() {
+            Packet::ConfigurationClientboundPluginMessage(message) => {
+                assert_eq!(message.channel, "example:echo");
+                assert_eq!(message.data, vec![1, 2]);
+            }
+            packet => panic!("expected a plugin message, got {:?}", packet),
+        }
+    }
+    #[tokio::test]
+    async fn idle_play_clients_dont_block_others() {
+        let running = CancellationToken::new();
+        let connections = DownstreamConnectionManager::new(None);
+        let address = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let listener = connections
+            .spawn_listener(address, running.child_token())
+            .await
+            .unwrap();
+        let mut server = Server {
+            running,
+            connections,
+            plugin_channels: echo_channels(),
+            listener,
+        };
+
+        let mut clients = vec![];
+        for _ in 0..2 {
+            let stream = TcpStream::connect(address).await.unwrap();
+            let mut client = GenericConnection::new(0, PacketDirection::Clientbound, stream);
+            *client.client_state_mut() = ClientState::Play;
+            clients.push(client);
+        }
+        while server.connections.clients().count() < 2 {
+            server.connections.update().await.unwrap();
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        for connection in server.connections.clients_mut() {
+            *connection.client_state_mut() = DownstreamConnectionState::Play;
+            *connection.inner_state_mut() = ClientState::Play;
+        }
+
+        // The first client stays silent while the second sends a message.
+        let active = &mut clients[1];
+        active
+            .send_packet(PlayServerboundPluginMessage {
+                channel: "example:echo".to_owned(),
+                data: vec![1, 2],
+            })
+            .await
+            .unwrap();
+        tokio::time::timeout(Duration::from_secs(1), server.update())
+            .await
+            .expect("update to finish while a client is idle")
+            .unwrap();
+        match active.read_packet().await.unwrap().unwrap() {
+            Packet::PlayClientboundPluginMessage(message) => {
+                assert_eq!(message.channel, "example:echo");
+                assert_eq!(message.data, vec![1, 2]);
+            }
+            packet => panic!("expected a plugin message, got {:?}", packet),
+        }
+        assert_eq!(server.connections.clients().count(), 2);
+
+        server.shutdown().await.unwrap();
+    }
+    #[tokio::test]
+    async fn finishing_configuration_starts_play() {
+        let (mut client, mut connection) = connect(ClientState::Configuration).await;
+        *connection.client_state_mut() = DownstreamConnectionState::ConfigurationFinish;
+
+        client
+            .send_packet(AcknowledgeFinishConfiguration {})
+            .await
+            .unwrap();
+        *client.client_state_mut() = ClientState::Play;
+        connection
+            .handle_configuration(&PluginChannels::default())
+            .await
+            .unwrap();
+        assert_eq!(connection.client_state(), DownstreamConnectionState::Play);
+
+        match client.read_packet().await.unwrap().unwrap() {
+            Packet::LoginPlay(login_play) => {
+                assert_eq!(login_play.dimension_name, "minecraft:overworld");
+                assert_eq!(login_play.dimension_names, vec!["minecraft:overworld"]);
+            }
+            packet => panic!("expected LoginPlay, got {:?}", packet),
+        }
+        assert!(matches!(
+            client.read_packet().await.unwrap().unwrap(),
+            Packet::GameEvent(GameEvent { event: 13, .. })
+        ));
+        assert!(matches!(
+            client.read_packet().await.unwrap().unwrap(),
+            Packet::SynchronizePlayerPosition(_)
+        ));
+    }
+}